@@ -22,6 +22,7 @@ export default function CreateElectionPage() {
     const [endDate, setEndDate] = useState("")
     const [closingType, setClosingType] = useState<"MANUAL" | "AUTO">("MANUAL")
     const [accessType, setAccessType] = useState<"PUBLIC" | "PRIVATE">("PUBLIC")
+    const [hashSuite, setHashSuite] = useState<"SHA-256" | "SHA3-256" | "BLAKE3">("SHA-256")
 
     // Simple textual questions for MVP
     const [questions, setQuestions] = useState([{ id: "q1", text: "Question 1", type: "radio", options: "Yes,No" }])
@@ -65,7 +66,8 @@ export default function CreateElectionPage() {
             },
             start_date: start.toISOString(),
             end_date: end.toISOString(),
-            access_type: accessType
+            access_type: accessType,
+            hash_suite: hashSuite
         }
         createMutation.mutate(payload)
     }
@@ -154,6 +156,24 @@ export default function CreateElectionPage() {
                             </div>
                         </div>
                     </div>
+
+                    <div className="space-y-2">
+                        <label className="text-sm font-medium">Hash Suite</label>
+                        <div className="flex gap-4">
+                            {(["SHA-256", "SHA3-256", "BLAKE3"] as const).map((suite) => (
+                                <div key={suite} className="flex items-center space-x-2">
+                                    <input
+                                        type="radio"
+                                        id={suite}
+                                        name="hash_suite"
+                                        checked={hashSuite === suite}
+                                        onChange={() => setHashSuite(suite)}
+                                    />
+                                    <label htmlFor={suite}>{suite}</label>
+                                </div>
+                            ))}
+                        </div>
+                    </div>
                 </CardContent>
             </Card>
 
@@ -113,7 +113,7 @@ export default function MonitorPage() {
                                 {t("monitor.closeElection")}
                             </Button>
                         )}
-                        {stats?.status === 'SEALED' && (
+                        {(stats?.status === 'SEALED' || stats?.status === 'ARCHIVED') && (
                             <span className="text-muted-foreground text-sm italic">Election Closed</span>
                         )}
                         <Button
@@ -8,12 +8,13 @@ import { Input } from "@/components/ui/input"
 import { Card, CardContent, CardHeader, CardTitle, CardDescription } from "@/components/ui/card"
 import { API_URL, fetcher } from "@/lib/utils"
 import { useLanguage } from "@/components/language-provider"
+import { useAuth } from "@/components/auth-provider"
 import Link from "next/link"
-import { sha256 } from "js-sha256"
 
 export default function WhitelistPage() {
     const { id: electionId } = useParams()
     const { t } = useLanguage()
+    const { token } = useAuth()
     const queryClient = useQueryClient()
     const [newId, setNewId] = useState("")
 
@@ -23,11 +24,14 @@ export default function WhitelistPage() {
     })
 
     const addMutation = useMutation({
-        mutationFn: async (hash: string) => {
+        mutationFn: async (documentNumber: string) => {
             const res = await fetch(`${API_URL}/elections/${electionId}/whitelist`, {
                 method: "POST",
-                headers: { "Content-Type": "application/json" },
-                body: JSON.stringify({ document_hashes: [hash] })
+                headers: {
+                    "Content-Type": "application/json",
+                    "Authorization": `Bearer ${token}`
+                },
+                body: JSON.stringify({ document_numbers: [documentNumber] })
             })
             if (!res.ok) throw new Error("Failed to add")
             return res.text()
@@ -40,21 +44,8 @@ export default function WhitelistPage() {
 
     const handleAdd = () => {
         if (!newId) return
-        // In real app, we might just send the raw ID if we trust the channel, 
-        // OR we hash it here to match what we expect. 
-        // The Identity Validator mocks hash via `hash_data`. 
-        // Let's assume we just enter the RAW ID here, and hashing happens on Server? 
-        // Actually, the server `validate_identity` hashes the raw ID it *guesses* or extracts.
-        // My previous server code in `add_whitelist` expects `document_hashes`.
-        // So I should hash it here IF I know the hashing algo.
-        // For sync simplicity, let's say the Input is ALREADY the Hash, or we just Hash it with sha256 (if that's what backend uses?).
-        // Backend `validate_identity` uses `crypto::hash_data`. We should probably align them.
-        // Since I can't guarantee backend implementation from here easily without checking `crypto/mod.rs`,
-        // I'll assume for this demo the admin enters the "Document ID" and we hash it with SHA256 before sending as "hash".
-        // *Correction*: Backend `crypto::hash_data` usually uses sha256. 
-
-        const hash = sha256(newId);
-        addMutation.mutate(hash)
+        // The server derives the whitelist entry (Argon2id + pepper), so the raw ID goes over the authenticated channel
+        addMutation.mutate(newId)
     }
 
     // Fetch Status
@@ -65,7 +56,7 @@ export default function WhitelistPage() {
 
     if (isLoading) return <div className="p-10 text-center">{t("common.loading")}</div>
 
-    const isClosed = election?.status === 'SEALED';
+    const isClosed = election?.status === 'SEALED' || election?.status === 'ARCHIVED';
 
     return (
         <div className="container mx-auto py-10 space-y-8 max-w-2xl">
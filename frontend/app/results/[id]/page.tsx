@@ -12,6 +12,23 @@ interface Results {
     [candidate: string]: number
 }
 
+// Encrypted elections publish per-question totals next to their decryption proofs
+interface EncryptedResults {
+    ballot_scheme: string
+    totals: Record<string, Results>
+}
+
+function candidateCounts(results: Results | EncryptedResults): Results {
+    if (!("totals" in results)) return results as Results
+    const counts: Results = {}
+    for (const totals of Object.values(results.totals)) {
+        for (const [candidate, count] of Object.entries(totals)) {
+            counts[candidate] = (counts[candidate] ?? 0) + count
+        }
+    }
+    return counts
+}
+
 interface Election {
     title: string
     status: string
@@ -26,7 +43,7 @@ export default function ResultsPage() {
         queryFn: () => fetcher(`/elections/${electionId}`)
     })
 
-    const { data: results, isLoading } = useQuery<Results>({
+    const { data: results, isLoading } = useQuery<Results | EncryptedResults>({
         queryKey: ['election-results', electionId],
         queryFn: () => fetcher(`/elections/${electionId}/results`)
     })
@@ -38,7 +55,7 @@ export default function ResultsPage() {
     // Usually results are hidden until closed, but for this task I will show them.
 
     const sortedResults = results
-        ? Object.entries(results).sort(([, a], [, b]) => b - a)
+        ? Object.entries(candidateCounts(results)).sort(([, a], [, b]) => b - a)
         : []
 
     const totalVotes = sortedResults.reduce((acc, [, count]) => acc + count, 0)
@@ -4,14 +4,26 @@ import { useState } from "react"
 import { Button } from "@/components/ui/button"
 import { Card, CardContent, CardHeader, CardTitle, CardDescription, CardFooter } from "@/components/ui/card"
 import { Input } from "@/components/ui/input"
-import { sha256 } from "js-sha256"
 import { fetcher } from "@/lib/utils"
 import { useToast } from "@/hooks/use-toast"
 import { useLanguage } from "@/components/language-provider"
 
+// Output of verifyReceipt in verify/src/wasm.rs
+type ReceiptOutcome = {
+    valid: boolean
+    error_kind: string | null
+    error: string | null
+    notes: { level: "passed" | "warning" | "info", message: string }[]
+    root_hash: string | null
+    root_timestamp: string | null
+}
+
+const NOTE_ICONS = { passed: "✅", warning: "⚠️", info: "•" }
+
 export default function VerifyPage() {
     const [receipt, setReceipt] = useState<any>(null)
     const [status, setStatus] = useState<"IDLE" | "VALID" | "INVALID" | "ERROR">("IDLE")
+    const [result, setResult] = useState<ReceiptOutcome | null>(null)
     const { toast } = useToast()
     const { t } = useLanguage()
 
@@ -24,6 +36,7 @@ export default function VerifyPage() {
             try {
                 const json = JSON.parse(event.target?.result as string)
                 setReceipt(json)
+                setResult(null)
                 setStatus("IDLE")
             } catch (err) {
                 toast({ title: t("verify.invalidFile"), description: t("verify.notValidJson"), variant: "destructive" })
@@ -36,53 +49,35 @@ export default function VerifyPage() {
         if (!receipt) return
 
         try {
-            // 1. Fetch Public Root from Election Board (API)
-            // We assume the receipt contains election_id or we ask user. 
-            // The receipt structure in Rust 'VoteReceipt' didn't have election_id explicitly, 
-            // but let's assume the user knows the election or we infer/add it.
-            // For now, I'll ask for Election ID if not in receipt, but let's assume the user goes to /verify?election_id=... 
-            // or just fetches based on what's available.
-            // If the receipt doesn't have election_id, we can't fetch the root automatically.
-            // I'll skip fetching and just ask user to input the expected Root for "Off-Mainnet" verification, 
-            // OR assumes receipt has it.
-
-            // Let's assume we can't automaticlly fetch without ID. 
-            // I will implement a "Compare with Public Root" input.
-
-            // Wait, create_election handler didn't put election_id in receipt?
-            // `SubmitVoteRequest` had it. The response `VoteReceipt` didn't. 
-            // I will implement pure cryptographic check: derive root from path -> matches claimed root?
-
-            // Reconstruct Root
-            let currentHash = receipt.ballot_hash
-            const path = receipt.merkle_path || []
-
-            // Simple reconstruction loop (assuming simplistic left/right or order provided)
-            for (const sibling of path) {
-                // We try both combinations? 
-                // Creating a true robust verification requires index/position.
-                // As implemented in Rust crypto, it was hardcoded (combined left,right).
-                // Porting that logic EXACTLY:
-                // impl MerkleTree: next_level.push(hash(left + right));
-                // get_proof: returns the sibling.
-                // If we are left, hash(current + sibling). If right, hash(sibling + current).
-                // Without index, we are guessing.
-
-                // For this demo, let's just show the logic running.
-                const combine1 = sha256(currentHash + sibling)
-                // const combine2 = sha256(sibling + currentHash)
-                currentHash = combine1 // Naive assumption matching the Rust simplicity
+            // The root, key and signatures come from the public board rather than the receipt;
+            // without them the checks fall back to the receipt and say so in the notes
+            let board = ""
+            try {
+                const snapshot = await fetcher<any>(`/audit/${receipt.election_id}/verify`)
+                // A receipt from voting time proves inclusion in its tree head; once sealed, that
+                // head is linked to the sealed root by a consistency proof from the public log
+                if (snapshot.merkle_root && receipt.tree_head) {
+                    try {
+                        const consistency = await fetcher<{ proof: unknown }>(
+                            `/audit/${receipt.election_id}/log/consistency?first=${receipt.tree_head.tree_size}`
+                        )
+                        snapshot.consistency = consistency.proof
+                    } catch (e) {
+                        console.error(e)
+                    }
+                }
+                board = JSON.stringify(snapshot)
+            } catch (e) {
+                toast({ title: t("verify.boardUnavailable"), variant: "destructive" })
             }
 
-            // In a real app, compare `currentHash` with the `merkle_root` from the API.
-            // For now, we display the Computed Root and ask user to check it against the public board.
-
-            setStatus("VALID") // We successfully computed IT.
-            toast({
-                title: t("verify.rootComputed"),
-                description: `${t("verify.merkleRoot")}: ${currentHash.substring(0, 16)}... ${t("verify.checkPublic")}`
-            })
+            // Same checks as the verify_receipt CLI, compiled from the solesigner-verify crate
+            const verifier = await import("@/lib/solesigner-verify/solesigner_verify")
+            await verifier.default()
+            const outcome: ReceiptOutcome = JSON.parse(verifier.verifyReceipt(JSON.stringify(receipt), board))
 
+            setResult(outcome)
+            setStatus(outcome.valid ? "VALID" : "INVALID")
         } catch (e) {
             console.error(e)
             setStatus("ERROR")
@@ -103,13 +98,30 @@ export default function VerifyPage() {
                     {receipt && (
                         <div className="bg-slate-50 p-4 rounded text-xs font-mono break-all">
                             <p>{t("verify.ballotHash")}: {receipt.ballot_hash}</p>
-                            <p>{t("verify.pathLength")}: {receipt.merkle_path?.length || 0}</p>
+                            <p>{t("verify.pathLength")}: {receipt.merkle_path?.siblings?.length || 0}</p>
                         </div>
                     )}
 
+                    {result && (
+                        <ul className="text-xs space-y-1">
+                            {result.notes.map((note, i) => (
+                                <li key={i}>{NOTE_ICONS[note.level]} {note.message}</li>
+                            ))}
+                        </ul>
+                    )}
+
                     {status === "VALID" && (
                         <div className="p-4 bg-green-100 text-green-800 rounded">
                             {t("verify.success")}
+                            {result?.root_hash && (
+                                <p className="text-xs font-mono break-all mt-2">{t("verify.merkleRoot")}: {result.root_hash}</p>
+                            )}
+                        </div>
+                    )}
+
+                    {status === "INVALID" && (
+                        <div className="p-4 bg-red-100 text-red-800 rounded">
+                            {t("verify.failed")}: {result?.error}
                         </div>
                     )}
                 </CardContent>
@@ -29,9 +29,71 @@ interface Election {
     id: string
     title: string
     form_config: FormConfig
-    election_salt: string
     status: string
     access_type: "PUBLIC" | "PRIVATE"
+    ballot_scheme: "PLAINTEXT" | "ELGAMAL_RISTRETTO255"
+    encryption_public_key: string | null
+    proof_version: number
+    credential_scheme: string
+    credential_public_key: string | null
+}
+
+// What the ballot carries to show the voter may vote: a blind-signed credential the
+// server cannot tie to the identity it validated, or the identity nullifier itself
+type VoterProof = { nullifier: string } | { credential: unknown }
+
+async function loadVerifier() {
+    const verifier = await import("@/lib/solesigner-verify/solesigner_verify")
+    await verifier.default()
+    return verifier
+}
+
+// Encrypted elections never see the choices: they are encrypted here, with the proofs
+// the server checks, by the same solesigner-verify code the server verifies them with
+async function sealChoices(election: Election, answers: Record<string, any>) {
+    if (election.ballot_scheme === "PLAINTEXT") return { choices: answers }
+    if (!election.encryption_public_key) throw new Error("Election has no encryption key")
+
+    const selections = Object.fromEntries(
+        Object.entries(answers).map(([id, answer]) => [id, Array.isArray(answer) ? answer : [answer]])
+    )
+    const verifier = await loadVerifier()
+    return JSON.parse(verifier.encryptBallot(
+        election.encryption_public_key,
+        election.id,
+        election.proof_version,
+        JSON.stringify(election.form_config),
+        JSON.stringify(selections)
+    )) as { choices: unknown, proofs: unknown }
+}
+
+// Blind-signed credentials: only the blinded token goes with the identity check, and the
+// signature is unblinded here, so the credential sent with the ballot was never seen
+async function validateIdentity(election: Election, identity: Record<string, unknown>): Promise<VoterProof> {
+    let verifier: Awaited<ReturnType<typeof loadVerifier>> | null = null
+    let blinding: { blinded_token: string } | null = null
+    if (election.credential_scheme !== "NULLIFIER") {
+        if (!election.credential_public_key) throw new Error("Election has no credential key")
+        verifier = await loadVerifier()
+        blinding = JSON.parse(verifier.blindToken(election.credential_public_key))
+    }
+
+    const res = await fetch(`${API_URL}/vote/validate-identity`, {
+        method: "POST",
+        headers: { "Content-Type": "application/json" },
+        body: JSON.stringify(blinding ? { ...identity, blinded_token: blinding.blinded_token } : identity)
+    })
+    if (!res.ok) throw new Error(await res.text())
+    const data = await res.json()
+
+    if (!verifier || !blinding) return { nullifier: data.nullifier }
+    return {
+        credential: JSON.parse(verifier.finalizeCredential(
+            data.credential_public_key,
+            JSON.stringify(blinding),
+            data.blind_signature
+        ))
+    }
 }
 
 export default function VotePage({ params }: { params: { election_id: string } }) {
@@ -47,7 +109,7 @@ export default function VotePage({ params }: { params: { election_id: string } }
     const [docNumber, setDocNumber] = useState("")
     const [selfie, setSelfie] = useState<string | null>(null)
     const [docImage, setDocImage] = useState<string | null>(null)
-    const [nullifier, setNullifier] = useState<string | null>(null)
+    const [voterProof, setVoterProof] = useState<VoterProof | null>(null)
 
     const [answers, setAnswers] = useState<Record<string, any>>({})
     const [receipt, setReceipt] = useState<any>(null)
@@ -82,17 +144,9 @@ export default function VotePage({ params }: { params: { election_id: string } }
 
     // 2. Identity Mutation
     const identityMutation = useMutation({
-        mutationFn: async (data: any) => {
-            const res = await fetch(`${API_URL}/vote/validate-identity`, {
-                method: "POST",
-                headers: { "Content-Type": "application/json" },
-                body: JSON.stringify(data)
-            })
-            if (!res.ok) throw new Error(await res.text())
-            return res.json()
-        },
-        onSuccess: (data) => {
-            setNullifier(data.nullifier)
+        mutationFn: (data: Record<string, unknown>) => validateIdentity(election!, data),
+        onSuccess: (proof) => {
+            setVoterProof(proof)
             setStep(3) // Move to Ballot
             toast({ title: t("msg.identityVerified"), description: t("msg.canVote") })
         },
@@ -104,10 +158,16 @@ export default function VotePage({ params }: { params: { election_id: string } }
     // 3. Submit Vote Mutation
     const submitVoteMutation = useMutation({
         mutationFn: async (data: any) => {
+            const ballot = await sealChoices(election!, data.answers)
             const res = await fetch(`${API_URL}/vote/submit`, {
                 method: "POST",
                 headers: { "Content-Type": "application/json" },
-                body: JSON.stringify(data)
+                body: JSON.stringify({
+                    election_id: data.election_id,
+                    ...ballot,
+                    ...data.voterProof,
+                    request_id: data.request_id
+                })
             })
             if (!res.ok) throw new Error(await res.text())
             return res.json()
@@ -115,6 +175,9 @@ export default function VotePage({ params }: { params: { election_id: string } }
         onSuccess: (data) => {
             setReceipt(data)
             setStep(4)
+        },
+        onError: (err) => {
+            toast({ title: t("error.voteFailed"), description: err.message, variant: "destructive" })
         }
     })
 
@@ -316,8 +379,8 @@ export default function VotePage({ params }: { params: { election_id: string } }
                                 const requestId = crypto.randomUUID();
                                 submitVoteMutation.mutate({
                                     election_id,
-                                    choices: answers,
-                                    nullifier: nullifier!,
+                                    answers,
+                                    voterProof: voterProof!,
                                     request_id: requestId
                                 })
                             }}
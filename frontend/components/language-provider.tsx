@@ -56,13 +56,13 @@ const translations: Translations = {
     "verify.desc": { en: "Upload your receipt to cryptographically verify inclusion.", es: "Sube tu recibo para verificar criptográficamente la inclusión." },
     "verify.ballotHash": { en: "Ballot Hash", es: "Hash de la Boleta" },
     "verify.pathLength": { en: "Path Length", es: "Longitud del Camino" },
-    "verify.success": { en: "Computation Successful. Your vote is mathematically linked to the Merkle Root.", es: "Cálculo Exitoso. Tu voto está matemáticamente vinculado a la Raíz de Merkle." },
+    "verify.success": { en: "Verification Successful. Your vote is included under the signed Merkle Root.", es: "Verificación Exitosa. Tu voto está incluido bajo la Raíz de Merkle firmada." },
+    "verify.failed": { en: "Verification Failed", es: "Verificación Fallida" },
+    "verify.boardUnavailable": { en: "Could not fetch the public board; checking against the receipt alone", es: "No se pudo obtener el tablero público; se verifica solo contra el recibo" },
     "verify.run": { en: "Run Verification Algorithm", es: "Ejecutar Algoritmo de Verificación" },
     "verify.invalidFile": { en: "Invalid File", es: "Archivo Inválido" },
     "verify.notValidJson": { en: "Not a valid JSON receipt", es: "No es un recibo JSON válido" },
-    "verify.rootComputed": { en: "Root Computed", es: "Raíz Calculada" },
     "verify.merkleRoot": { en: "Merkle Root", es: "Raíz de Merkle" },
-    "verify.checkPublic": { en: "(Verify this against public board)", es: "(Verifique esto contra el tablero público)" },
 
     // Vote Steps
     // Step 1
@@ -149,7 +149,7 @@ const translations: Translations = {
     // Whitelist
     "whitelist.manage": { en: "Manage Whitelist", es: "Gestionar Lista Blanca" },
     "whitelist.addVoter": { en: "Add Voter", es: "Agregar Votante" },
-    "whitelist.instruction": { en: "Enter Document ID to authorize (e.g. Passport Number). It will be hashed before sending.", es: "Ingrese el ID del documento para autorizar (ej. Pasaporte). Será hasheado antes de enviarse." },
+    "whitelist.instruction": { en: "Enter Document ID to authorize (e.g. Passport Number). It is sent over your signed-in session and derived on the server.", es: "Ingrese el ID del documento para autorizar (ej. Pasaporte). Se envía por su sesión iniciada y se deriva en el servidor." },
     "whitelist.placeholder": { en: "Document ID", es: "ID del Documento" },
     "whitelist.add": { en: "Add", es: "Agregar" },
     "whitelist.adding": { en: "Adding...", es: "Agregando..." },
@@ -161,6 +161,7 @@ const translations: Translations = {
     "error.eligibilityFailed": { en: "Eligibility Check Failed", es: "Fallo al verificar elegibilidad" },
     "error.identityNotAuthorized": { en: "Identity not recognized or not authorized.", es: "Identidad no reconocida o no autorizada." },
     "error.verificationFailed": { en: "Verification Failed", es: "Verificación Fallida" },
+    "error.voteFailed": { en: "Vote Not Recorded", es: "Voto No Registrado" },
     "msg.identityVerified": { en: "Identity Verified", es: "Identidad Verificada" },
     "msg.canVote": { en: "You may now vote.", es: "Ahora puede votar." },
 }
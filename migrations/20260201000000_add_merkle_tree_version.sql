@@ -0,0 +1,6 @@
+-- 8. Tag sealed roots with the Merkle tree construction that produced them
+ALTER TABLE elections ADD COLUMN merkle_tree_version SMALLINT;
+
+-- Roots sealed before this migration used the original (version 1) tree:
+-- SHA256(left_hex || right_hex) with the last node duplicated on odd levels.
+UPDATE elections SET merkle_tree_version = 1 WHERE merkle_root IS NOT NULL;
@@ -0,0 +1,25 @@
+-- 9. Append-only ballot log: every ballot gets a fixed position in its election's tree
+ALTER TABLE ballots ADD COLUMN leaf_index BIGINT;
+
+-- Existing ballots keep the order the sealing job has always used
+UPDATE ballots b SET leaf_index = ordered.idx
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY election_id ORDER BY created_at, id) - 1 AS idx
+    FROM ballots
+) ordered
+WHERE b.id = ordered.id;
+
+ALTER TABLE ballots ALTER COLUMN leaf_index SET NOT NULL;
+CREATE UNIQUE INDEX ballots_election_leaf_idx ON ballots (election_id, leaf_index);
+
+-- 10. Signed tree heads published for each election's ballot log
+CREATE TABLE tree_heads (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    election_id UUID NOT NULL REFERENCES elections(id),
+    tree_size BIGINT NOT NULL,
+    root_hash VARCHAR NOT NULL,
+    timestamp BIGINT NOT NULL, -- Unix milliseconds, part of the signed message
+    signature VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    UNIQUE(election_id, tree_size)
+);
@@ -0,0 +1,2 @@
+-- 11. Signature over the sealed Merkle root
+ALTER TABLE elections ADD COLUMN root_signature VARCHAR;
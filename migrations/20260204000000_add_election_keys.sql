@@ -0,0 +1,22 @@
+-- 12. Election key material. Private keys are encrypted under the server master key.
+CREATE TYPE key_status AS ENUM ('ACTIVE', 'RETIRED');
+
+CREATE TABLE election_keys (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    election_id UUID NOT NULL REFERENCES elections(id),
+    purpose VARCHAR NOT NULL, -- e.g. 'SIGNING'
+    algorithm VARCHAR NOT NULL,
+    public_key VARCHAR NOT NULL,
+    fingerprint VARCHAR NOT NULL,
+    encrypted_private_key VARCHAR NOT NULL, -- hex(nonce || ciphertext)
+    status key_status NOT NULL DEFAULT 'ACTIVE',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    retired_at TIMESTAMPTZ
+);
+
+-- At most one active key per election and purpose
+CREATE UNIQUE INDEX election_keys_active_idx ON election_keys (election_id, purpose) WHERE status = 'ACTIVE';
+
+-- 13. Record which key produced each signature so rotated keys stay verifiable
+ALTER TABLE tree_heads ADD COLUMN key_fingerprint VARCHAR NOT NULL DEFAULT '';
+ALTER TABLE elections ADD COLUMN root_key_fingerprint VARCHAR;
@@ -0,0 +1,13 @@
+-- 14. Homomorphically encrypted ballots
+-- Elections created before this migration keep counting plaintext choices.
+ALTER TABLE elections ADD COLUMN ballot_scheme VARCHAR NOT NULL DEFAULT 'ELGAMAL_RISTRETTO255';
+UPDATE elections SET ballot_scheme = 'PLAINTEXT';
+
+-- 15. Encrypted tally computed at sealing and its proven decryption
+CREATE TABLE election_tallies (
+    election_id UUID PRIMARY KEY REFERENCES elections(id),
+    ballot_count BIGINT NOT NULL,
+    encrypted_tally JSONB NOT NULL,
+    decrypted_tally JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
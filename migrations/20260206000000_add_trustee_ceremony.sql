@@ -0,0 +1,38 @@
+-- 16. Threshold trustee ceremonies. An election with a ceremony never holds a
+-- server-side decryption key; trustees jointly generate it before the election opens.
+CREATE TABLE trustee_ceremonies (
+    election_id UUID PRIMARY KEY REFERENCES elections(id),
+    threshold INT NOT NULL,
+    trustee_count INT NOT NULL,
+    status VARCHAR NOT NULL DEFAULT 'DEALING', -- DEALING, COMPLETE
+    joint_public_key VARCHAR,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMPTZ
+);
+
+CREATE TABLE trustees (
+    election_id UUID NOT NULL REFERENCES trustee_ceremonies(election_id),
+    trustee_index INT NOT NULL, -- 1..=trustee_count, the point the trustee's share is evaluated at
+    name VARCHAR NOT NULL,
+    auth_public_key VARCHAR NOT NULL, -- Ed25519, signs the trustee's messages
+    share_public_key VARCHAR NOT NULL, -- Ristretto255, receives encrypted shares
+    disqualified BOOLEAN NOT NULL DEFAULT FALSE, -- A valid complaint was upheld against its dealing
+    PRIMARY KEY (election_id, trustee_index)
+);
+
+-- 17. Signed ceremony transcript: dealings, complaints and partial decryptions
+CREATE TABLE trustee_messages (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    election_id UUID NOT NULL,
+    trustee_index INT NOT NULL,
+    kind VARCHAR NOT NULL, -- DEALING, COMPLAINT, PARTIAL_DECRYPTION
+    payload TEXT NOT NULL, -- Exact JSON covered by the signature
+    signature VARCHAR NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (election_id, trustee_index) REFERENCES trustees(election_id, trustee_index)
+);
+
+CREATE UNIQUE INDEX trustee_messages_once_idx ON trustee_messages (election_id, trustee_index, kind) WHERE kind <> 'COMPLAINT';
+
+-- 18. Threshold election keys only have a public half
+ALTER TABLE election_keys ALTER COLUMN encrypted_private_key DROP NOT NULL;
@@ -0,0 +1,3 @@
+-- 19. Zero-knowledge well-formedness proofs submitted with each encrypted ballot.
+-- NULL for plaintext ballots and ballots cast before proofs were required.
+ALTER TABLE ballots ADD COLUMN proofs JSONB;
@@ -0,0 +1,12 @@
+-- 20. Blind-signed voting credentials. Elections created before this migration
+-- keep voting with nullifiers.
+ALTER TABLE elections ADD COLUMN credential_scheme VARCHAR NOT NULL DEFAULT 'RSABSSA-SHA384-PSS-Randomized';
+UPDATE elections SET credential_scheme = 'NULLIFIER';
+
+-- 21. Credentials already used to cast a ballot, by token hash. No timestamp is
+-- kept so spends cannot be lined up against credential issuance.
+CREATE TABLE spent_credentials (
+    election_id UUID NOT NULL REFERENCES elections(id),
+    token_hash VARCHAR NOT NULL,
+    PRIMARY KEY (election_id, token_hash)
+);
@@ -0,0 +1,8 @@
+-- 22. Nullifiers are keyed with the server's NULLIFIER_SECRET instead of a
+-- published salt. Elections that already registered voters keep the salted hash
+-- so re-validation still matches; every other election drops its salt.
+ALTER TABLE elections ADD COLUMN nullifier_scheme VARCHAR NOT NULL DEFAULT 'HMAC-SHA256';
+UPDATE elections e SET nullifier_scheme = 'SHA256-SALT'
+WHERE EXISTS (SELECT 1 FROM voter_registry v WHERE v.election_id = e.id);
+ALTER TABLE elections ALTER COLUMN election_salt DROP NOT NULL;
+UPDATE elections SET election_salt = NULL WHERE nullifier_scheme = 'HMAC-SHA256';
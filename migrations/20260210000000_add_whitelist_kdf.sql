@@ -0,0 +1,10 @@
+-- 23. Whitelist entries are Argon2id-derived under a per-election salt, then
+-- keyed with the server's WHITELIST_PEPPER. Elections that already hold
+-- unsalted SHA-256 entries keep them; the rest switch over.
+ALTER TABLE elections ADD COLUMN whitelist_kdf VARCHAR NOT NULL DEFAULT 'ARGON2ID-HMAC-SHA256';
+ALTER TABLE elections ADD COLUMN whitelist_salt VARCHAR;
+UPDATE elections e SET whitelist_kdf = 'SHA256'
+WHERE EXISTS (SELECT 1 FROM whitelist w WHERE w.election_id = e.id);
+UPDATE elections SET whitelist_salt = replace(uuid_generate_v4()::text, '-', '')
+WHERE whitelist_kdf = 'ARGON2ID-HMAC-SHA256';
+CREATE INDEX whitelist_lookup_idx ON whitelist (election_id, document_id_hash);
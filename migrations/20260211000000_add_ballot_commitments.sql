@@ -0,0 +1,13 @@
+-- 24. Benaloh cast-or-challenge. A committed ballot is either cast into the
+-- ballot log under the same id, or challenged: its randomness and opened
+-- selections are published and it can never be cast.
+CREATE TABLE ballot_commitments (
+    id UUID PRIMARY KEY,
+    election_id UUID NOT NULL REFERENCES elections(id),
+    encrypted_choices JSONB NOT NULL,
+    proofs JSONB NOT NULL,
+    ballot_hash VARCHAR NOT NULL,
+    status VARCHAR NOT NULL DEFAULT 'COMMITTED', -- COMMITTED, CAST or CHALLENGED
+    randomness JSONB,
+    selections JSONB
+);
@@ -0,0 +1,5 @@
+-- 25. Sparse Merkle root over the credentials spent in an election, sealed and
+-- signed alongside the ballot root with the same key.
+ALTER TABLE elections ADD COLUMN spent_root VARCHAR;
+ALTER TABLE elections ADD COLUMN spent_count BIGINT;
+ALTER TABLE elections ADD COLUMN spent_root_signature VARCHAR;
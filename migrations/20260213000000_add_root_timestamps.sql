@@ -0,0 +1,5 @@
+-- 26. RFC 3161 timestamp token over the sealed root, requested from the TSA at
+-- sealing time as independent evidence of when the seal happened.
+ALTER TABLE elections ADD COLUMN root_timestamp TEXT; -- hex DER TimeStampToken
+ALTER TABLE elections ADD COLUMN root_timestamp_tsa VARCHAR;
+ALTER TABLE elections ADD COLUMN root_timestamped_at TIMESTAMPTZ;
@@ -0,0 +1,10 @@
+-- 27. Chain admin audit entries: each one carries the previous entry's hash and
+-- is signed by the server audit key. Nothing wrote to this table before.
+ALTER TABLE audit_logs ADD COLUMN sequence BIGINT;
+ALTER TABLE audit_logs ADD COLUMN prev_hash VARCHAR;
+ALTER TABLE audit_logs ADD COLUMN entry_hash VARCHAR;
+ALTER TABLE audit_logs ADD COLUMN key_fingerprint VARCHAR;
+-- Every API action is made by an authenticated admin, including start and close;
+-- only entries written outside a session (a command-line import) have no admin
+ALTER TABLE audit_logs ALTER COLUMN admin_id DROP NOT NULL;
+CREATE UNIQUE INDEX audit_logs_sequence_idx ON audit_logs (sequence);
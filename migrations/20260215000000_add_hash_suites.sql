@@ -0,0 +1,3 @@
+-- 28. Hash suite chosen at creation (SHA-256, SHA3-256 or BLAKE3). It covers
+-- ballot hashes, nullifiers, spent-credential hashes and both Merkle trees.
+ALTER TABLE elections ADD COLUMN hash_suite VARCHAR NOT NULL DEFAULT 'SHA-256';
@@ -0,0 +1,5 @@
+-- 29. Filled in when the election is sealed: merkle_proof (from the init schema)
+-- proves the ballot against the sealed root, and receipt_signature signs
+-- receipt_message(election_id, ballot_hash, created_at in ms) with the key that
+-- signed that root, so a receipt can be fetched again after the election closes.
+ALTER TABLE ballots ADD COLUMN receipt_signature VARCHAR;
@@ -0,0 +1,13 @@
+-- 30. Elections imported from another deployment's signed archive. They are
+-- served read-only: the importing instance holds none of their private keys,
+-- so the archive is kept exactly as received and re-served as is.
+ALTER TYPE election_status ADD VALUE 'ARCHIVED';
+
+CREATE TABLE election_archives (
+    election_id UUID PRIMARY KEY REFERENCES elections(id),
+    manifest JSONB NOT NULL,
+    signature VARCHAR NOT NULL,
+    files JSONB NOT NULL, -- Name -> JSON text, byte for byte as hashed in the manifest
+    imported_by UUID REFERENCES admins(id),
+    imported_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
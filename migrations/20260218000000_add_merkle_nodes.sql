@@ -0,0 +1,12 @@
+-- 31. Complete nodes of each election's ballot tree, written as ballots are
+-- appended. A node never changes once all its leaves exist, so appends touch
+-- O(log n) rows and proofs are served without reading every ballot.
+CREATE TABLE merkle_nodes (
+    election_id UUID NOT NULL REFERENCES elections(id),
+    level SMALLINT NOT NULL, -- 0 holds the leaf hashes
+    node_index BIGINT NOT NULL, -- Covers leaves node_index * 2^level up to (node_index + 1) * 2^level
+    hash VARCHAR NOT NULL,
+    PRIMARY KEY (election_id, level, node_index)
+);
+
+-- Ballots cast before this table are indexed by the server at startup
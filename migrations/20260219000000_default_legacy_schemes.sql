@@ -0,0 +1,11 @@
+-- 32. The web ballot page sends plaintext choices with a nullifier, so new
+-- elections default to those schemes again; encrypted ballots and blind-signed
+-- credentials are chosen explicitly when the election is created.
+ALTER TABLE elections ALTER COLUMN ballot_scheme SET DEFAULT 'PLAINTEXT';
+ALTER TABLE elections ALTER COLUMN credential_scheme SET DEFAULT 'NULLIFIER';
+
+-- Drafts created under the old defaults that nobody can vote in yet follow them
+UPDATE elections e SET ballot_scheme = 'PLAINTEXT', credential_scheme = 'NULLIFIER'
+WHERE e.status = 'DRAFT'
+  AND NOT EXISTS (SELECT 1 FROM trustee_ceremonies t WHERE t.election_id = e.id)
+  AND NOT EXISTS (SELECT 1 FROM voter_registry v WHERE v.election_id = e.id);
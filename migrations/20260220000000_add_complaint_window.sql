@@ -0,0 +1,4 @@
+-- 33. How long trustees have to complain after the last dealing. The ceremony
+-- cannot be finalized before the window closes, so every trustee gets to check
+-- every share it was dealt.
+ALTER TABLE trustee_ceremonies ADD COLUMN complaint_window_secs INT NOT NULL DEFAULT 86400;
@@ -0,0 +1,8 @@
+-- 34. Tag each election with the zero-knowledge proof construction its ballots,
+-- dealings and decryptions use. Version 2 hashes the election id and the public
+-- key into every Fiat-Shamir challenge, so a proof cannot be replayed in
+-- another election or under another key.
+ALTER TABLE elections ADD COLUMN proof_version SMALLINT NOT NULL DEFAULT 2;
+
+-- Elections created before this migration were proven with version 1 challenges
+UPDATE elections SET proof_version = 1;
@@ -0,0 +1,8 @@
+-- 35. Bind each cast-or-challenge commitment to the voter's credential, so only
+-- the voter who committed a ballot can cast or challenge it. The column holds a
+-- hash of the commitment id and the nullifier or spent-token hash, never the
+-- credential itself.
+ALTER TABLE ballot_commitments ADD COLUMN credential_hash VARCHAR;
+
+-- Commitments made before this migration are bound to no one and can no longer
+-- be cast or challenged; those voters commit their ballot again
@@ -0,0 +1,4 @@
+-- 36. The web ballot page now encrypts choices in the browser (see
+-- `encryptBallot` in solesigner-verify), so new elections default to encrypted
+-- ballots again. Elections already created keep the scheme they have.
+ALTER TABLE elections ALTER COLUMN ballot_scheme SET DEFAULT 'ELGAMAL_RISTRETTO255';
@@ -0,0 +1,5 @@
+-- 37. The web ballot page now blinds and unblinds its credential in the
+-- browser (see `blindToken` and `finalizeCredential` in solesigner-verify), so
+-- new elections default to blind-signed credentials again. Elections already
+-- created keep the scheme they have.
+ALTER TABLE elections ALTER COLUMN credential_scheme SET DEFAULT 'RSABSSA-SHA384-PSS-Randomized';
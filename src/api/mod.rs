@@ -1,7 +1,7 @@
 use axum::{
-    extract::{FromRequestParts, Path, State},
+    extract::{DefaultBodyLimit, FromRequestParts, Path, Query, State},
     http::{request::Parts, StatusCode},
-    response::{IntoResponse, Json},
+    response::{IntoResponse, Json, Response},
     routing::{get, post},
     RequestPartsExt, Router,
 };
@@ -10,18 +10,37 @@ use axum_extra::{
     TypedHeader,
 };
 use bcrypt::{hash, verify, DEFAULT_COST};
+use ed25519_dalek::SigningKey;
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
 use std::env;
+use std::sync::Arc;
 use uuid::Uuid;
 
-use crate::crypto;
+use crate::audit_log;
+use crate::ballot_log;
+use crate::export::{self, ExportError};
+use crate::crypto::{self, archive::ElectionArchive, ballot, credential, elgamal::ProofContext, whitelist::WhitelistPepper, NullifierKey, StoredTree};
+use crate::import::{self, ImportError};
+use crate::keys::{self, KeyError, MasterKey};
+use crate::scheduler;
+use crate::spent_set;
+use crate::trustees::{self, CeremonyError, NewTrustee, SignedSubmission};
+use crate::tsa::TsaClient;
+
+/// Archives carry every ballot, so imports get more room than the default 2 MB
+const IMPORT_BODY_LIMIT: usize = 256 * 1024 * 1024;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: PgPool,
+    pub master_key: Arc<MasterKey>,
+    pub nullifier_key: Arc<NullifierKey>,
+    pub whitelist_pepper: Arc<WhitelistPepper>,
+    pub audit_key: Arc<SigningKey>,
+    pub tsa: Option<Arc<TsaClient>>,
 }
 
 // --- Auth DTOs ---
@@ -47,8 +66,22 @@ pub struct AuthUser {
     pub admin_id: Uuid,
 }
 
-pub fn router(pool: PgPool) -> Router {
-    let state = AppState { db: pool };
+pub fn router(
+    pool: PgPool,
+    master_key: Arc<MasterKey>,
+    nullifier_key: Arc<NullifierKey>,
+    whitelist_pepper: Arc<WhitelistPepper>,
+    audit_key: Arc<SigningKey>,
+    tsa: Option<Arc<TsaClient>>,
+) -> Router {
+    let state = AppState {
+        db: pool,
+        master_key,
+        nullifier_key,
+        whitelist_pepper,
+        audit_key,
+        tsa,
+    };
     Router::new()
         // Auth Routes
         .route("/auth/register", post(register_admin))
@@ -56,20 +89,66 @@ pub fn router(pool: PgPool) -> Router {
         // Protected Routes (handled by extractors in handlers)
         .route("/elections/create", post(create_election))
         .route("/elections", get(list_elections))
+        .route(
+            "/elections/import",
+            post(import_election).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)),
+        )
+        .route("/elections/:id/stats", get(get_election_stats))
+        .route("/elections/:id/start", post(start_election))
+        .route("/elections/:id/close", post(close_election))
+        .route(
+            "/elections/:id/whitelist",
+            get(get_whitelist).post(add_whitelist),
+        )
         // Public Routes
         .route("/elections/:id", get(get_election))
-        .route("/elections/:id/stats", get(get_election_stats)) // Could be protected
-        .route("/elections/:id/start", post(start_election)) // Should be protected, but for now Public
-        .route("/elections/:id/close", post(close_election)) // Should be protected
         .route("/elections/:id/results", get(get_election_results)) // Public results
+        .route("/elections/:id/whitelist/kdf", get(get_whitelist_kdf))
+        .route("/elections/:id/keys/rotate", post(rotate_election_key))
         .route(
-            "/elections/:id/whitelist",
-            get(get_whitelist).post(add_whitelist),
+            "/elections/:id/keys/:key_id/retire",
+            post(retire_election_key),
         )
+        .route("/elections/:id/trustees", post(setup_trustees))
+        .route("/elections/:id/ceremony/dealings", post(submit_dealing))
+        .route("/elections/:id/ceremony/complaints", post(submit_complaint))
+        .route("/elections/:id/ceremony/finalize", post(finalize_ceremony))
+        .route("/elections/:id/ceremony/partials", post(submit_partial_decryption))
         .route("/vote/validate-identity", post(validate_identity))
         .route("/vote/check-eligibility", post(check_eligibility))
         .route("/vote/submit", post(submit_vote))
+        .route("/vote/commit", post(commit_ballot))
+        .route("/vote/cast", post(cast_ballot))
+        .route("/vote/challenge", post(challenge_ballot))
+        .route("/audit/admin-log", get(get_admin_audit_log))
         .route("/audit/:election_id/verify", get(verify_election))
+        .route(
+            "/audit/:election_id/receipt/:ballot_hash",
+            get(get_sealed_receipt),
+        )
+        .route("/audit/:election_id/export", get(export_election))
+        .route("/audit/:election_id/archive", get(archive_election))
+        .route("/audit/:election_id/keys", get(list_election_keys))
+        .route("/audit/:election_id/ceremony", get(get_ceremony_transcript))
+        .route("/audit/:election_id/tally", get(get_encrypted_tally))
+        .route("/audit/:election_id/ballots", get(list_ballots))
+        .route("/audit/:election_id/challenged", get(list_challenged_ballots))
+        .route("/audit/:election_id/spent", get(get_spent_set))
+        .route("/audit/:election_id/spent/proof/:entry", get(get_spent_proof))
+        .route("/audit/:election_id/log/head", get(get_log_head))
+        .route("/audit/:election_id/log/heads", get(list_log_heads))
+        .route(
+            "/audit/:election_id/log/inclusion/:ballot_hash",
+            get(get_inclusion_proof),
+        )
+        .route(
+            "/audit/:election_id/log/multiproof",
+            post(get_inclusion_multiproof),
+        )
+        .route(
+            "/audit/:election_id/log/consistency",
+            get(get_consistency_proof),
+        )
         .with_state(state)
 }
 
@@ -112,21 +191,96 @@ pub struct CreateElectionRequest {
     pub start_date: chrono::DateTime<chrono::Utc>,
     pub end_date: chrono::DateTime<chrono::Utc>,
     pub access_type: String, // "PUBLIC" or "PRIVATE"
+    pub hash_suite: Option<String>, // "SHA-256" (default), "SHA3-256" or "BLAKE3"
+    pub ballot_scheme: Option<String>, // "ELGAMAL_RISTRETTO255" (default) or "PLAINTEXT"
+    pub credential_scheme: Option<String>, // "RSABSSA-SHA384-PSS-Randomized" (default) or "NULLIFIER"
 }
 
 #[derive(Deserialize)]
 pub struct SubmitVoteRequest {
     pub election_id: Uuid,
     pub choices: Value,
-    pub nullifier: String, // In real app, this comes from the validated session/token
-    pub request_id: Uuid,  // GUID for the ballot
+    pub nullifier: Option<String>, // Only for elections still on the NULLIFIER credential scheme
+    pub credential: Option<credential::Credential>, // Unblinded token signed at identity validation
+    pub request_id: Uuid,                           // GUID for the ballot
+    pub proofs: Option<Value>, // ballot::BallotProofs, required for encrypted ballots
 }
 
 #[derive(Serialize)]
 pub struct VoteReceipt {
+    pub election_id: Uuid,
+    pub ballot_id: Uuid, // With the submitted choices, lets the voter recompute ballot_hash
+    pub ballot_hash: String,
+    pub timestamp: i64, // Unix milliseconds
+    pub merkle_path: Option<crypto::MerkleProof>, // Inclusion in `tree_head`
+    pub merkle_tree_version: i16,
+    pub hash_suite: crypto::HashSuite, // Hashes ballot_hash, the Merkle tree and tree_head's root
+    pub tree_head: crypto::SignedTreeHead,
+    pub public_key: String,
+    pub key_fingerprint: String,
+    pub signature: String, // Ed25519 over crypto::receipt_message
+}
+
+#[derive(Deserialize)]
+pub struct CommitBallotRequest {
+    pub election_id: Uuid,
+    pub choices: Value,
+    pub proofs: Value,
+    pub nullifier: Option<String>,
+    pub credential: Option<credential::Credential>,
+}
+
+/// Signed acknowledgement of a committed ballot. `ballot_hash` is the same
+/// tracker the `VoteReceipt` carries if the ballot is later cast.
+#[derive(Serialize)]
+pub struct BallotCommitment {
+    pub commitment_id: Uuid,
+    pub election_id: Uuid,
     pub ballot_hash: String,
-    pub merkle_path: Vec<String>,
-    pub signature: String,
+    pub credential_hash: String, // Binds the commitment to the voter's credential
+    pub timestamp: i64, // Unix milliseconds
+    pub public_key: String,
+    pub key_fingerprint: String,
+    pub signature: String, // Ed25519 over crypto::commitment_message
+}
+
+#[derive(Deserialize)]
+pub struct CastBallotRequest {
+    pub commitment_id: Uuid,
+    pub nullifier: Option<String>,
+    pub credential: Option<credential::Credential>,
+}
+
+#[derive(Deserialize)]
+pub struct ChallengeBallotRequest {
+    pub commitment_id: Uuid,
+    pub randomness: ballot::BallotRandomness,
+    pub nullifier: Option<String>,
+    pub credential: Option<credential::Credential>,
+}
+
+#[derive(Deserialize)]
+pub struct SetupTrusteesRequest {
+    pub threshold: u32,
+    pub complaint_window_secs: Option<u32>, // Defaults to trustees::DEFAULT_COMPLAINT_WINDOW_SECS
+    pub trustees: Vec<NewTrustee>,
+}
+
+#[derive(Deserialize)]
+pub struct InclusionQuery {
+    pub tree_size: Option<i64>, // Defaults to the latest published head
+}
+
+#[derive(Deserialize)]
+pub struct MultiproofRequest {
+    pub ballot_hashes: Vec<String>,
+    pub tree_size: Option<i64>, // Defaults to the latest published head
+}
+
+#[derive(Deserialize)]
+pub struct ConsistencyQuery {
+    pub first: i64,
+    pub second: Option<i64>, // Defaults to the latest published head
 }
 
 // --- Handlers ---
@@ -152,18 +306,29 @@ async fn register_admin(
     let password_hash = hash(payload.password, DEFAULT_COST).unwrap();
 
     // 3. Insert User
+    let mut tx = match state.db.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
     let insert =
         sqlx::query("INSERT INTO admins (username, password_hash) VALUES ($1, $2) RETURNING id")
             .bind(&payload.username)
             .bind(password_hash)
-            .fetch_one(&state.db)
+            .fetch_one(&mut *tx)
             .await;
 
-    match insert {
-        Ok(rec) => {
-            let id: Uuid = rec.try_get("id").unwrap();
-            (StatusCode::CREATED, Json(serde_json::json!({"id": id}))).into_response()
-        }
+    let id: Uuid = match insert {
+        Ok(rec) => rec.try_get("id").unwrap(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let entry = serde_json::json!({ "username": payload.username });
+    if let Err(response) = record_admin_action(&state, &mut tx, Some(id), "REGISTER", entry).await {
+        return response;
+    }
+
+    match tx.commit().await {
+        Ok(_) => (StatusCode::CREATED, Json(serde_json::json!({"id": id}))).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
@@ -203,6 +368,11 @@ async fn login_admin(
                 )
                 .unwrap();
 
+                let entry = serde_json::json!({ "username": payload.username });
+                if let Err(response) = record_standalone_action(&state, Some(id), "LOGIN", entry).await {
+                    return response;
+                }
+
                 (
                     StatusCode::OK,
                     Json(AuthResponse {
@@ -227,13 +397,51 @@ async fn create_election(
     Json(payload): Json<CreateElectionRequest>,
 ) -> impl IntoResponse {
     use sqlx::Row;
-    let election_salt = Uuid::new_v4().to_string();
+
+    let hash_suite: crypto::HashSuite = match payload.hash_suite.as_deref().map(str::parse).transpose() {
+        Ok(suite) => suite.unwrap_or_default(),
+        Err(e) => return (StatusCode::UNPROCESSABLE_ENTITY, e).into_response(),
+    };
+    // The web ballot page encrypts choices in the browser, so the operator never
+    // sees them unless an election opts into plaintext ballots
+    let ballot_scheme = match payload.ballot_scheme.as_deref() {
+        None | Some("ELGAMAL_RISTRETTO255") => "ELGAMAL_RISTRETTO255",
+        Some("PLAINTEXT") => "PLAINTEXT",
+        Some(other) => return (StatusCode::UNPROCESSABLE_ENTITY, format!("Unknown ballot scheme {}", other)).into_response(),
+    };
+    // The page unblinds its credential in the browser too, so by default nothing
+    // sent with a ballot can be matched to the identity that was validated
+    let credential_scheme = match payload.credential_scheme.as_deref() {
+        None | Some(credential::CREDENTIAL_SCHEME) => credential::CREDENTIAL_SCHEME,
+        Some("NULLIFIER") => "NULLIFIER",
+        Some(other) => {
+            return (StatusCode::UNPROCESSABLE_ENTITY, format!("Unknown credential scheme {}", other)).into_response()
+        }
+    };
+    if let Err(e) = ballot::validate_layout(&payload.form_config) {
+        return (StatusCode::UNPROCESSABLE_ENTITY, e).into_response();
+    }
+
+    let mut tx = match state.db.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let mut entry = serde_json::json!({
+        "title": payload.title,
+        "hash_suite": hash_suite,
+        "ballot_scheme": ballot_scheme,
+        "credential_scheme": credential_scheme,
+        "access_type": payload.access_type,
+        "start_date": payload.start_date,
+        "end_date": payload.end_date,
+    });
 
     // Using runtime check query to avoid compile error if DB not migrated yet
     let result = sqlx::query(
         r#"
-        INSERT INTO elections (title, form_config, start_date, end_date, access_type, election_salt, status, admin_id)
-        VALUES ($1, $2, $3, $4, $5::access_type, $6, 'DRAFT', $7)
+        INSERT INTO elections (title, form_config, start_date, end_date, access_type, status, admin_id, whitelist_salt, hash_suite, nullifier_scheme, ballot_scheme, credential_scheme)
+        VALUES ($1, $2, $3, $4, $5::access_type, 'DRAFT', $6, $7, $8, $9, $10, $11)
         RETURNING id
         "#
     )
@@ -242,16 +450,38 @@ async fn create_election(
     .bind(payload.start_date)
     .bind(payload.end_date)
     .bind(payload.access_type) // This might need explicit cast handling if using simple bind
-    .bind(election_salt)
     .bind(auth.admin_id)
-    .fetch_one(&state.db)
+    .bind(crypto::whitelist::generate_salt())
+    .bind(hash_suite.name())
+    .bind(hash_suite.nullifier_scheme())
+    .bind(ballot_scheme)
+    .bind(credential_scheme)
+    .fetch_one(&mut *tx)
     .await;
 
-    match result {
-        Ok(rec) => {
-            let id: Uuid = rec.try_get("id").unwrap();
-            (StatusCode::CREATED, Json(serde_json::json!({ "id": id }))).into_response()
+    let id: Uuid = match result {
+        Ok(rec) => rec.try_get("id").unwrap(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    // Every election gets its own signing, ballot encryption and credential keys from the start
+    for purpose in [
+        keys::KeyPurpose::Signing,
+        keys::KeyPurpose::Encryption,
+        keys::KeyPurpose::Credential,
+    ] {
+        if let Err(e) = keys::generate(&mut *tx, &state.master_key, id, purpose).await {
+            return key_error_response(e);
         }
+    }
+
+    entry["election_id"] = serde_json::json!(id);
+    if let Err(response) = record_admin_action(&state, &mut tx, Some(auth.admin_id), "CREATE_ELECTION", entry).await {
+        return response;
+    }
+
+    match tx.commit().await {
+        Ok(_) => (StatusCode::CREATED, Json(serde_json::json!({ "id": id }))).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
@@ -307,10 +537,16 @@ pub struct ElectionStats {
 
 #[derive(Deserialize)]
 pub struct AddWhitelistRequest {
-    pub document_hashes: Vec<String>,
+    #[serde(default)]
+    pub document_numbers: Vec<String>, // Raw IDs, derived server side
+    #[serde(default)]
+    pub derived_ids: Vec<String>, // Derived client side, see GET /elections/:id/whitelist/kdf
+    #[serde(default)]
+    pub document_hashes: Vec<String>, // Legacy SHA256 elections only
 }
 
 #[derive(Deserialize)]
+#[allow(dead_code)] // Images and coordinates are consumed once IdentityEngine is wired in
 pub struct ValidateIdentityRequest {
     pub election_id: Uuid,
     pub selfie_base64: String,
@@ -318,6 +554,7 @@ pub struct ValidateIdentityRequest {
     pub latitude: f64,
     pub longitude: f64,
     pub document_number: String,
+    pub blinded_token: Option<String>, // hex, see crypto::credential::blind
 }
 
 #[derive(Deserialize)]
@@ -329,7 +566,12 @@ pub struct CheckEligibilityRequest {
 #[derive(Serialize)]
 pub struct ValidateIdentityResponse {
     pub identity_token: String,
-    pub nullifier: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nullifier: Option<String>, // Legacy NULLIFIER elections only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blind_signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential_public_key: Option<String>,
 }
 
 // --- Missing Handlers ---
@@ -339,7 +581,7 @@ async fn check_eligibility(
     Json(payload): Json<CheckEligibilityRequest>,
 ) -> impl IntoResponse {
     let election = match sqlx::query!(
-        "SELECT access_type::text as access_type FROM elections WHERE id = $1",
+        "SELECT access_type::text as access_type, whitelist_kdf, whitelist_salt FROM elections WHERE id = $1",
         payload.election_id
     )
     .fetch_optional(&state.db)
@@ -354,7 +596,17 @@ async fn check_eligibility(
         return StatusCode::OK.into_response();
     }
 
-    let doc_hash = crypto::hash_data(&payload.document_number);
+    let doc_hash = match whitelist_entry(
+        &state,
+        &election.whitelist_kdf,
+        election.whitelist_salt,
+        payload.document_number,
+    )
+    .await
+    {
+        Ok(entry) => entry,
+        Err(response) => return response,
+    };
 
     let exists = sqlx::query!(
         "SELECT id FROM whitelist WHERE election_id = $1 AND document_id_hash = $2",
@@ -377,7 +629,7 @@ async fn validate_identity(
 ) -> impl IntoResponse {
     // 1. Fetch election details
     let election = match sqlx::query!(
-        "SELECT election_salt, status::text as status, access_type::text as access_type FROM elections WHERE id = $1",
+        "SELECT election_salt, nullifier_scheme, hash_suite, status::text as status, access_type::text as access_type, credential_scheme, whitelist_kdf, whitelist_salt FROM elections WHERE id = $1",
         payload.election_id
     )
     .fetch_optional(&state.db)
@@ -389,9 +641,19 @@ async fn validate_identity(
     };
 
     // 2. CHECK WHITELIST IF PRIVATE
-    let doc_hash = crypto::hash_data(&payload.document_number);
-
     if election.access_type.as_deref() == Some("PRIVATE") {
+        let doc_hash = match whitelist_entry(
+            &state,
+            &election.whitelist_kdf,
+            election.whitelist_salt.clone(),
+            payload.document_number.clone(),
+        )
+        .await
+        {
+            Ok(entry) => entry,
+            Err(response) => return response,
+        };
+
         let whitelisted = sqlx::query!(
             "SELECT id FROM whitelist WHERE election_id = $1 AND document_id_hash = $2",
             payload.election_id,
@@ -414,22 +676,100 @@ async fn validate_identity(
     }
 
     // 3. Generate Nullifier
-    let nullifier = crypto::generate_nullifier(&payload.document_number, &election.election_salt);
+    let nullifier = if election.nullifier_scheme == crypto::LEGACY_NULLIFIER_SCHEME {
+        let Some(salt) = &election.election_salt else {
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Legacy election has no salt").into_response();
+        };
+        crypto::generate_nullifier(&payload.document_number, salt)
+    } else {
+        let suite: crypto::HashSuite = match election.hash_suite.parse() {
+            Ok(suite) => suite,
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        };
+        state
+            .nullifier_key
+            .nullifier(suite, &payload.election_id, &payload.document_number)
+    };
+
+    if election.credential_scheme == "NULLIFIER" {
+        return (
+            StatusCode::OK,
+            Json(ValidateIdentityResponse {
+                identity_token: "VALID_TOKEN_PLACEHOLDER".to_string(),
+                nullifier: Some(nullifier),
+                blind_signature: None,
+                credential_public_key: None,
+            }),
+        )
+            .into_response();
+    }
+
+    // 4. Issue one blind-signed credential per identity. The nullifier is
+    // recorded here, so the ballot itself never carries anything identifying.
+    if !matches!(election.status.as_deref(), Some("DRAFT") | Some("OPEN")) {
+        return (StatusCode::FORBIDDEN, "Election is no longer issuing credentials").into_response();
+    }
+    let Some(blinded_token) = payload.blinded_token else {
+        return (StatusCode::UNPROCESSABLE_ENTITY, "blinded_token is required").into_response();
+    };
+
+    let (public_key, secret) =
+        match keys::credential_secret(&state.db, &state.master_key, payload.election_id).await {
+            Ok(key) => key,
+            Err(e) => return key_error_response(e),
+        };
+
+    let mut tx = match state.db.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let insert_registry = sqlx::query!(
+        "INSERT INTO voter_registry (election_id, nullifier_hash, identity_status, location_zone) VALUES ($1, $2, 'Validated', 'ZoneA')",
+        payload.election_id,
+        nullifier
+    )
+    .execute(&mut *tx)
+    .await;
+
+    if insert_registry.is_err() {
+        return (
+            StatusCode::CONFLICT,
+            "A credential was already issued for this identity",
+        )
+            .into_response();
+    }
+
+    let blind_signature = match credential::blind_sign(&secret, &blinded_token) {
+        Ok(signature) => signature,
+        Err(e) => return (StatusCode::UNPROCESSABLE_ENTITY, e).into_response(),
+    };
+
+    if let Err(e) = tx.commit().await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
 
     (
         StatusCode::OK,
         Json(ValidateIdentityResponse {
             identity_token: "VALID_TOKEN_PLACEHOLDER".to_string(),
-            nullifier,
+            nullifier: None,
+            blind_signature: Some(blind_signature),
+            credential_public_key: Some(public_key),
         }),
     )
         .into_response()
 }
 
 async fn get_election_stats(
+    auth: AuthUser,
     Path(election_id): Path<Uuid>,
     State(state): State<AppState>,
 ) -> impl IntoResponse {
+    if let Err(response) = owned_election(&state, &auth, election_id).await {
+        return response;
+    }
+
     let count = sqlx::query!(
         "SELECT COUNT(*) as count FROM ballots WHERE election_id = $1",
         election_id
@@ -458,13 +798,18 @@ async fn get_election_stats(
 }
 
 async fn add_whitelist(
+    auth: AuthUser,
     Path(election_id): Path<Uuid>,
     State(state): State<AppState>,
     Json(payload): Json<AddWhitelistRequest>,
 ) -> impl IntoResponse {
+    if let Err(response) = owned_election(&state, &auth, election_id).await {
+        return response;
+    }
+
     // 1. Check election status
     let election = match sqlx::query!(
-        "SELECT status::text as status FROM elections WHERE id = $1",
+        "SELECT status::text as status, whitelist_kdf, whitelist_salt FROM elections WHERE id = $1",
         election_id
     )
     .fetch_optional(&state.db)
@@ -475,26 +820,163 @@ async fn add_whitelist(
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     };
 
-    if election.status.as_deref() == Some("SEALED") {
+    if matches!(election.status.as_deref(), Some("SEALED") | Some("ARCHIVED")) {
         return (StatusCode::FORBIDDEN, "Election is closed").into_response();
     }
 
-    for hash in payload.document_hashes {
-        let _ = sqlx::query!(
+    // 2. Turn every upload into the stored entry for this election's KDF
+    let entries = if election.whitelist_kdf == crypto::whitelist::LEGACY_WHITELIST_KDF {
+        if !payload.derived_ids.is_empty() {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "This election stores SHA-256 whitelist entries; upload document_numbers or document_hashes",
+            )
+                .into_response();
+        }
+        let mut entries = payload.document_hashes;
+        entries.extend(payload.document_numbers.iter().map(|doc| crypto::hash_data(doc)));
+        entries
+    } else {
+        if !payload.document_hashes.is_empty() {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "This election derives whitelist entries with Argon2id; upload document_numbers or derived_ids",
+            )
+                .into_response();
+        }
+        let Some(salt) = election.whitelist_salt else {
+            return (StatusCode::INTERNAL_SERVER_ERROR, "Election has no whitelist salt").into_response();
+        };
+
+        let mut entries = Vec::with_capacity(payload.derived_ids.len() + payload.document_numbers.len());
+        for derived in &payload.derived_ids {
+            match state.whitelist_pepper.entry(derived) {
+                Some(entry) => entries.push(entry),
+                None => {
+                    return (
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        format!("Malformed derived_id: {}", derived),
+                    )
+                        .into_response()
+                }
+            }
+        }
+
+        // Argon2id is deliberately slow; keep it off the async workers
+        let pepper = state.whitelist_pepper.clone();
+        let documents = payload.document_numbers;
+        let derived = tokio::task::spawn_blocking(move || {
+            documents
+                .iter()
+                .map(|doc| pepper.entry_for_document(doc, &salt))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .await;
+        match derived {
+            Ok(Ok(derived)) => entries.extend(derived),
+            Ok(Err(e)) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+        entries
+    };
+
+    let mut tx = match state.db.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let submitted = entries.len();
+    let mut added = 0;
+    for hash in entries {
+        match sqlx::query!(
             "INSERT INTO whitelist (election_id, document_id_hash) VALUES ($1, $2) ON CONFLICT DO NOTHING",
             election_id,
             hash
         )
-        .execute(&state.db)
-        .await;
+        .execute(&mut *tx)
+        .await
+        {
+            Ok(res) => added += res.rows_affected(),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+
+    // Only counts: the log must not become a second copy of the whitelist
+    let entry = serde_json::json!({
+        "election_id": election_id,
+        "submitted": submitted,
+        "added": added,
+    });
+    if let Err(response) = record_admin_action(&state, &mut tx, Some(auth.admin_id), "ADD_WHITELIST", entry).await {
+        return response;
+    }
+
+    match tx.commit().await {
+        Ok(_) => StatusCode::OK.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// The `whitelist.document_id_hash` a voter's document number has to match
+async fn whitelist_entry(
+    state: &AppState,
+    kdf: &str,
+    salt: Option<String>,
+    document: String,
+) -> Result<String, Response> {
+    if kdf == crypto::whitelist::LEGACY_WHITELIST_KDF {
+        return Ok(crypto::hash_data(&document));
+    }
+    let Some(salt) = salt else {
+        return Err((StatusCode::INTERNAL_SERVER_ERROR, "Election has no whitelist salt").into_response());
+    };
+
+    let pepper = state.whitelist_pepper.clone();
+    match tokio::task::spawn_blocking(move || pepper.entry_for_document(&document, &salt)).await {
+        Ok(Ok(entry)) => Ok(entry),
+        Ok(Err(e)) => Err((StatusCode::INTERNAL_SERVER_ERROR, e).into_response()),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()),
+    }
+}
+
+/// Parameters for deriving whitelist identifiers before upload
+async fn get_whitelist_kdf(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let election = sqlx::query!(
+        "SELECT whitelist_kdf, whitelist_salt FROM elections WHERE id = $1",
+        election_id
+    )
+    .fetch_optional(&state.db)
+    .await;
+
+    match election {
+        Ok(Some(rec)) => match rec.whitelist_salt {
+            Some(salt) if rec.whitelist_kdf != crypto::whitelist::LEGACY_WHITELIST_KDF => (
+                StatusCode::OK,
+                Json(crypto::whitelist::KdfSpec::new(&salt)),
+            )
+                .into_response(),
+            _ => (
+                StatusCode::CONFLICT,
+                "This election stores legacy SHA-256 whitelist entries",
+            )
+                .into_response(),
+        },
+        Ok(None) => (StatusCode::NOT_FOUND, "Election not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
-    StatusCode::OK.into_response()
 }
 
 async fn get_whitelist(
+    auth: AuthUser,
     Path(election_id): Path<Uuid>,
     State(state): State<AppState>,
 ) -> impl IntoResponse {
+    if let Err(response) = owned_election(&state, &auth, election_id).await {
+        return response;
+    }
+
     let records = sqlx::query!(
         "SELECT document_id_hash FROM whitelist WHERE election_id = $1",
         election_id
@@ -515,88 +997,622 @@ async fn submit_vote(
     State(state): State<AppState>,
     Json(payload): Json<SubmitVoteRequest>,
 ) -> impl IntoResponse {
-    // 1. Verify election status
-    let election_result = sqlx::query!(
-        "SELECT status::text as status FROM elections WHERE id = $1",
-        payload.election_id
-    )
-    .fetch_optional(&state.db)
-    .await;
+    // Load the election key before locking the election row
+    let signer = match voting_signer(&state, payload.election_id).await {
+        Ok(signer) => signer,
+        Err(response) => return response,
+    };
 
-    match election_result {
-        Ok(Some(_)) => {
-            // Using check_status helper check if string matches OPEN
-        }
-        _ => return (StatusCode::NOT_FOUND, "Election not found").into_response(),
+    let mut tx = match state.db.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     };
 
-    // 2. Register Nullifier
-    let insert_registry = sqlx::query!(
-        "INSERT INTO voter_registry (election_id, nullifier_hash, identity_status, location_zone) VALUES ($1, $2, 'Validated', 'ZoneA')",
+    // 1. Verify election status and the ballot itself
+    let election = match lock_open_election(&mut tx, payload.election_id).await {
+        Ok(election) => election,
+        Err(response) => return response,
+    };
+    if let Err(response) = verify_encrypted_ballot(
+        &mut tx,
         payload.election_id,
-        payload.nullifier
+        &election,
+        &payload.choices,
+        payload.proofs.as_ref(),
     )
-    .execute(&state.db)
-    .await;
-
-    if let Err(_) = insert_registry {
-        // Likely unique constraint violation
-        return (StatusCode::CONFLICT, "Vote already cast with this identity").into_response();
+    .await
+    {
+        return response;
     }
 
-    // 3. Create Ballot
-    // ballot_hash: SHA256(GUID + Choices)
-    let choices_str = payload.choices.to_string();
-    let ballot_hash = crypto::hash_data(&format!("{}{}", payload.request_id, choices_str));
+    // 2. Spend the voting credential
+    let spent = match voter_credential_id(
+        &mut *tx,
+        payload.election_id,
+        &election.credential_scheme,
+        election.hash_suite,
+        payload.nullifier.as_ref(),
+        payload.credential.as_ref(),
+    )
+    .await
+    {
+        Ok(credential_id) => spend_credential(&mut tx, payload.election_id, &election, &credential_id).await,
+        Err(response) => Err(response),
+    };
+    if let Err(response) = spent {
+        return response;
+    }
 
-    // Insert Ballot
-    let insert_ballot = sqlx::query!(
-        "INSERT INTO ballots (id, election_id, encrypted_choices, ballot_hash) VALUES ($1, $2, $3, $4)",
+    append_ballot(
+        &state,
+        tx,
+        &signer,
+        &election,
         payload.request_id,
+        payload.choices,
+        payload.proofs,
+    )
+    .await
+}
+
+/// First half of cast-or-challenge: records an encrypted ballot without spending
+/// a credential, so the voter can still challenge it instead of casting it
+async fn commit_ballot(
+    State(state): State<AppState>,
+    Json(payload): Json<CommitBallotRequest>,
+) -> impl IntoResponse {
+    let signer = match voting_signer(&state, payload.election_id).await {
+        Ok(signer) => signer,
+        Err(response) => return response,
+    };
+
+    let mut tx = match state.db.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let election = match lock_open_election(&mut tx, payload.election_id).await {
+        Ok(election) => election,
+        Err(response) => return response,
+    };
+    if election.ballot_scheme == "PLAINTEXT" {
+        return (
+            StatusCode::CONFLICT,
+            "Cast-or-challenge needs an election with encrypted ballots",
+        )
+            .into_response();
+    }
+    if let Err(response) = verify_encrypted_ballot(
+        &mut tx,
+        payload.election_id,
+        &election,
+        &payload.choices,
+        Some(&payload.proofs),
+    )
+    .await
+    {
+        return response;
+    }
+
+    // The credential is checked but not spent; only its holder can cast or challenge the ballot
+    let credential_id = match voter_credential_id(
+        &mut *tx,
+        payload.election_id,
+        &election.credential_scheme,
+        election.hash_suite,
+        payload.nullifier.as_ref(),
+        payload.credential.as_ref(),
+    )
+    .await
+    {
+        Ok(credential_id) => credential_id,
+        Err(response) => return response,
+    };
+
+    let commitment_id = Uuid::new_v4();
+    let ballot_hash = crypto::ballot_hash(election.hash_suite, &commitment_id, &payload.choices);
+    let credential_hash = commitment_credential_hash(election.hash_suite, &commitment_id, &credential_id);
+
+    let insert = sqlx::query!(
+        "INSERT INTO ballot_commitments (id, election_id, encrypted_choices, proofs, ballot_hash, credential_hash) VALUES ($1, $2, $3, $4, $5, $6)",
+        commitment_id,
         payload.election_id,
         payload.choices,
-        ballot_hash
+        payload.proofs,
+        ballot_hash,
+        credential_hash
     )
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await;
 
-    if let Err(e) = insert_ballot {
+    if let Err(e) = insert {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
+    if let Err(e) = tx.commit().await {
         return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
     }
 
-    // 4. Generate Receipt
-    let receipt = VoteReceipt {
-        ballot_hash,
-        merkle_path: vec![],
-        signature: "ELECTION_SIGNATURE_PLACEHOLDER".to_string(),
-    };
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    let signature = crypto::sign_message(
+        &signer.key,
+        &crypto::commitment_message(&payload.election_id, &ballot_hash, &credential_hash, timestamp),
+    );
 
-    (StatusCode::OK, Json(receipt)).into_response()
+    (
+        StatusCode::CREATED,
+        Json(BallotCommitment {
+            commitment_id,
+            election_id: payload.election_id,
+            ballot_hash,
+            credential_hash,
+            timestamp,
+            public_key: signer.public_key,
+            key_fingerprint: signer.fingerprint,
+            signature,
+        }),
+    )
+        .into_response()
 }
 
-async fn get_election(
-    Path(election_id): Path<Uuid>,
+/// Casts a committed ballot into the ballot log, exactly as `submit_vote` would
+async fn cast_ballot(
     State(state): State<AppState>,
+    Json(payload): Json<CastBallotRequest>,
 ) -> impl IntoResponse {
-    let result = sqlx::query!(
-        "SELECT id, title, form_config, start_date, end_date, access_type::text as access_type, election_salt, status::text as status FROM elections WHERE id = $1",
-        election_id
+    let election_id = match sqlx::query_scalar!(
+        "SELECT election_id FROM ballot_commitments WHERE id = $1",
+        payload.commitment_id
     )
     .fetch_optional(&state.db)
-    .await;
+    .await
+    {
+        Ok(Some(election_id)) => election_id,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Ballot commitment not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
 
-    match result {
-        Ok(Some(rec)) => (
-            StatusCode::OK,
-            Json(serde_json::json!({
-                "id": rec.id,
-                "title": rec.title,
-                "form_config": rec.form_config,
-                "start_date": rec.start_date,
-                "end_date": rec.end_date,
+    let signer = match voting_signer(&state, election_id).await {
+        Ok(signer) => signer,
+        Err(response) => return response,
+    };
+
+    let mut tx = match state.db.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let election = match lock_open_election(&mut tx, election_id).await {
+        Ok(election) => election,
+        Err(response) => return response,
+    };
+
+    let credential_id = match voter_credential_id(
+        &mut *tx,
+        election_id,
+        &election.credential_scheme,
+        election.hash_suite,
+        payload.nullifier.as_ref(),
+        payload.credential.as_ref(),
+    )
+    .await
+    {
+        Ok(credential_id) => credential_id,
+        Err(response) => return response,
+    };
+    let credential_hash = commitment_credential_hash(election.hash_suite, &payload.commitment_id, &credential_id);
+
+    // The proofs were checked at commit time; a ballot leaves COMMITTED only once
+    let committed = sqlx::query!(
+        "UPDATE ballot_commitments SET status = 'CAST' WHERE id = $1 AND status = 'COMMITTED' AND credential_hash = $2 RETURNING encrypted_choices, proofs",
+        payload.commitment_id,
+        credential_hash
+    )
+    .fetch_optional(&mut *tx)
+    .await;
+
+    let committed = match committed {
+        Ok(Some(committed)) => committed,
+        Ok(None) => return commitment_unavailable(&mut *tx, payload.commitment_id).await,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    if let Err(response) = spend_credential(&mut tx, election_id, &election, &credential_id).await {
+        return response;
+    }
+
+    append_ballot(
+        &state,
+        tx,
+        &signer,
+        &election,
+        payload.commitment_id,
+        committed.encrypted_choices,
+        Some(committed.proofs),
+    )
+    .await
+}
+
+/// Spoils a committed ballot: checks the revealed randomness opens it, publishes
+/// the opened ballot for auditing and makes sure it can never be cast
+async fn challenge_ballot(
+    State(state): State<AppState>,
+    Json(payload): Json<ChallengeBallotRequest>,
+) -> impl IntoResponse {
+    let committed = sqlx::query!(
+        r#"
+        SELECT c.election_id, c.encrypted_choices, c.ballot_hash, e.form_config, e.credential_scheme, e.hash_suite
+        FROM ballot_commitments c JOIN elections e ON e.id = c.election_id
+        WHERE c.id = $1
+        "#,
+        payload.commitment_id
+    )
+    .fetch_optional(&state.db)
+    .await;
+
+    let committed = match committed {
+        Ok(Some(committed)) => committed,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Ballot commitment not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let suite: crypto::HashSuite = match committed.hash_suite.parse() {
+        Ok(suite) => suite,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let credential_id = match voter_credential_id(
+        &state.db,
+        committed.election_id,
+        &committed.credential_scheme,
+        suite,
+        payload.nullifier.as_ref(),
+        payload.credential.as_ref(),
+    )
+    .await
+    {
+        Ok(credential_id) => credential_id,
+        Err(response) => return response,
+    };
+    let credential_hash = commitment_credential_hash(suite, &payload.commitment_id, &credential_id);
+
+    let pk = match keys::encryption_public_key(&state.db, committed.election_id).await {
+        Ok(pk) => pk,
+        Err(e) => return key_error_response(e),
+    };
+    let layout = ballot::ballot_layout(&committed.form_config);
+    let opened = ballot::parse_ballot(&committed.encrypted_choices, &layout)
+        .and_then(|encrypted| ballot::open_ballot(&pk, &encrypted, &payload.randomness, &layout));
+    let selections = match opened {
+        Ok(selections) => selections,
+        Err(e) => return (StatusCode::UNPROCESSABLE_ENTITY, e).into_response(),
+    };
+
+    let spoiled = sqlx::query!(
+        "UPDATE ballot_commitments SET status = 'CHALLENGED', randomness = $2, selections = $3 WHERE id = $1 AND status = 'COMMITTED' AND credential_hash = $4",
+        payload.commitment_id,
+        serde_json::to_value(&payload.randomness).unwrap(),
+        serde_json::to_value(&selections).unwrap(),
+        credential_hash
+    )
+    .execute(&state.db)
+    .await;
+
+    match spoiled {
+        Ok(res) if res.rows_affected() > 0 => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "commitment_id": payload.commitment_id,
+                "ballot_hash": committed.ballot_hash,
+                "selections": selections,
+            })),
+        )
+            .into_response(),
+        Ok(_) => commitment_unavailable(&state.db, payload.commitment_id).await,
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Hash stored with a commitment that ties it to the credential it was made with,
+/// salted with the commitment id so it does not reveal the credential by itself
+fn commitment_credential_hash(suite: crypto::HashSuite, commitment_id: &Uuid, credential_id: &str) -> String {
+    suite.hash_hex(&format!("{}\n{}", commitment_id, credential_id))
+}
+
+/// Why a commitment could not be cast or challenged with the given credential
+async fn commitment_unavailable<'e>(executor: impl PgExecutor<'e>, commitment_id: Uuid) -> Response {
+    let status = sqlx::query_scalar!("SELECT status FROM ballot_commitments WHERE id = $1", commitment_id)
+        .fetch_optional(executor)
+        .await;
+
+    match status {
+        Ok(Some(status)) if status == "COMMITTED" => (
+            StatusCode::FORBIDDEN,
+            "Ballot was committed with another credential",
+        )
+            .into_response(),
+        Ok(Some(_)) => (StatusCode::CONFLICT, "Ballot was already cast or challenged").into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Ballot commitment not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// The election fields ballot casting depends on
+struct VotingElection {
+    id: Uuid,
+    form_config: Value,
+    ballot_scheme: String,
+    credential_scheme: String,
+    hash_suite: crypto::HashSuite,
+    proof_version: i16,
+}
+
+/// Loads the key receipts are signed with; a missing one means there is nothing to vote in
+async fn voting_signer(
+    state: &AppState,
+    election_id: Uuid,
+) -> Result<keys::ActiveSigningKey, Response> {
+    match keys::active_signing_key(&state.db, &state.master_key, election_id).await {
+        Ok(signer) => Ok(signer),
+        Err(KeyError::NoActiveKey) => Err((
+            StatusCode::NOT_FOUND,
+            "Election not found or has no active signing key",
+        )
+            .into_response()),
+        Err(e) => Err(key_error_response(e)),
+    }
+}
+
+/// Checks the election is OPEN, locking its row so ballot log appends are serialized
+async fn lock_open_election(
+    tx: &mut Transaction<'_, Postgres>,
+    election_id: Uuid,
+) -> Result<VotingElection, Response> {
+    let election_result = sqlx::query!(
+        "SELECT status::text as status, form_config, ballot_scheme, credential_scheme, hash_suite, proof_version FROM elections WHERE id = $1 FOR UPDATE",
+        election_id
+    )
+    .fetch_optional(&mut **tx)
+    .await;
+
+    match election_result {
+        Ok(Some(e)) if e.status.as_deref() == Some("OPEN") => Ok(VotingElection {
+            id: election_id,
+            form_config: e.form_config,
+            ballot_scheme: e.ballot_scheme,
+            credential_scheme: e.credential_scheme,
+            hash_suite: e
+                .hash_suite
+                .parse()
+                .map_err(|e: String| (StatusCode::INTERNAL_SERVER_ERROR, e).into_response())?,
+            proof_version: e.proof_version,
+        }),
+        Ok(Some(_)) => Err((StatusCode::FORBIDDEN, "Election is not open").into_response()),
+        _ => Err((StatusCode::NOT_FOUND, "Election not found").into_response()),
+    }
+}
+
+/// Choices must be one ElGamal ciphertext per option, encrypted client-side,
+/// with proofs that each is 0 or 1 and each question stays within its limits
+async fn verify_encrypted_ballot(
+    tx: &mut Transaction<'_, Postgres>,
+    election_id: Uuid,
+    election: &VotingElection,
+    choices: &Value,
+    proofs: Option<&Value>,
+) -> Result<(), Response> {
+    if election.ballot_scheme == "PLAINTEXT" {
+        return Ok(());
+    }
+
+    let layout = ballot::ballot_layout(&election.form_config);
+    let encrypted = ballot::parse_ballot(choices, &layout)
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e).into_response())?;
+    let pk = keys::encryption_public_key(&mut **tx, election_id)
+        .await
+        .map_err(key_error_response)?;
+    let context = ProofContext::for_version(election_id, election.proof_version);
+
+    proofs
+        .ok_or(ballot::ProofError::Malformed("proofs are required".to_string()))
+        .and_then(ballot::parse_proofs)
+        .and_then(|proofs| ballot::verify_ballot(&context, &pk, &encrypted, &proofs, &layout))
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response())
+}
+
+/// Records that the voter has cast their one ballot, by nullifier or by blind-signed
+/// credential; `credential_id` comes from `voter_credential_id`
+async fn spend_credential(
+    tx: &mut Transaction<'_, Postgres>,
+    election_id: Uuid,
+    election: &VotingElection,
+    credential_id: &str,
+) -> Result<(), Response> {
+    if election.credential_scheme == "NULLIFIER" {
+        let insert_registry = sqlx::query!(
+            "INSERT INTO voter_registry (election_id, nullifier_hash, identity_status, location_zone) VALUES ($1, $2, 'Validated', 'ZoneA')",
+            election_id,
+            credential_id
+        )
+        .execute(&mut **tx)
+        .await;
+
+        if insert_registry.is_err() {
+            // Likely unique constraint violation
+            return Err((StatusCode::CONFLICT, "Vote already cast with this identity").into_response());
+        }
+        return Ok(());
+    }
+
+    let spend = sqlx::query!(
+        "INSERT INTO spent_credentials (election_id, token_hash) VALUES ($1, $2)",
+        election_id,
+        credential_id
+    )
+    .execute(&mut **tx)
+    .await;
+
+    if spend.is_err() {
+        return Err((StatusCode::CONFLICT, "Vote already cast with this credential").into_response());
+    }
+    Ok(())
+}
+
+/// Checks the voter presented the credential the election asks for, without
+/// spending it. Returns what the spent set records: the nullifier, or the
+/// spent-token hash of a blind-signed credential.
+async fn voter_credential_id<'e>(
+    executor: impl PgExecutor<'e>,
+    election_id: Uuid,
+    credential_scheme: &str,
+    suite: crypto::HashSuite,
+    nullifier: Option<&String>,
+    voter_credential: Option<&credential::Credential>,
+) -> Result<String, Response> {
+    if credential_scheme == "NULLIFIER" {
+        return nullifier
+            .cloned()
+            .ok_or_else(|| (StatusCode::UNPROCESSABLE_ENTITY, "nullifier is required").into_response());
+    }
+
+    let Some(voter_credential) = voter_credential else {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, "credential is required").into_response());
+    };
+
+    let public_keys = keys::credential_public_keys(executor, election_id)
+        .await
+        .map_err(key_error_response)?;
+    if !public_keys
+        .iter()
+        .any(|pk| credential::verify_credential(pk, voter_credential))
+    {
+        return Err((StatusCode::FORBIDDEN, "Invalid voting credential").into_response());
+    }
+    Ok(credential::spent_token_hash(suite, voter_credential))
+}
+
+/// Appends a validated ballot to the log, commits, and returns its signed receipt
+async fn append_ballot(
+    state: &AppState,
+    mut tx: Transaction<'_, Postgres>,
+    signer: &keys::ActiveSigningKey,
+    election: &VotingElection,
+    ballot_id: Uuid,
+    choices: Value,
+    proofs: Option<Value>,
+) -> Response {
+    let election_id = election.id;
+    let suite = election.hash_suite;
+
+    // 3. Create Ballot
+    let ballot_hash = crypto::ballot_hash(suite, &ballot_id, &choices);
+
+    // Append it to the ballot log
+    let leaf_index = match ballot_log::next_leaf_index(&mut tx, election_id).await {
+        Ok(index) => index,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let insert_ballot = sqlx::query!(
+        "INSERT INTO ballots (id, election_id, encrypted_choices, ballot_hash, leaf_index, proofs) VALUES ($1, $2, $3, $4, $5, $6)",
+        ballot_id,
+        election_id,
+        choices,
+        ballot_hash,
+        leaf_index,
+        proofs
+    )
+    .execute(&mut *tx)
+    .await;
+
+    if let Err(e) = insert_ballot {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
+
+    if let Err(e) = ballot_log::append(&mut tx, suite, election_id, leaf_index, &ballot_hash).await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
+
+    if let Err(e) = tx.commit().await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
+
+    // 4. Prove inclusion against a head that ends at this ballot
+    let tree_size = leaf_index + 1;
+    let needed = StoredTree::proof_nodes(tree_size as usize, leaf_index as usize);
+    let tree = match ballot_log::stored_tree(&state.db, election_id, tree_size, &needed).await {
+        Ok(tree) => tree,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let (Some(root), Some(merkle_path)) = (tree.root(), tree.get_proof(leaf_index as usize)) else {
+        return (StatusCode::INTERNAL_SERVER_ERROR, "Ballot log is incomplete").into_response();
+    };
+    let tree_head = ballot_log::sign_head(&signer.key, election_id, tree_size as usize, root);
+
+    // 5. Generate and sign the Receipt
+    let timestamp = chrono::Utc::now().timestamp_millis();
+    let signature = crypto::sign_message(
+        &signer.key,
+        &crypto::receipt_message(&election_id, &ballot_hash, timestamp),
+    );
+    let receipt = VoteReceipt {
+        election_id,
+        ballot_id,
+        ballot_hash,
+        timestamp,
+        merkle_path: Some(merkle_path),
+        merkle_tree_version: crypto::MERKLE_TREE_VERSION,
+        hash_suite: suite,
+        tree_head,
+        public_key: signer.public_key.clone(),
+        key_fingerprint: signer.fingerprint.clone(),
+        signature,
+    };
+
+    (StatusCode::OK, Json(receipt)).into_response()
+}
+
+async fn get_election(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let result = sqlx::query!(
+        r#"
+        SELECT e.id, e.title, e.form_config, e.start_date, e.end_date, e.access_type::text as access_type,
+               e.status::text as status, e.ballot_scheme, k.public_key as "encryption_public_key?",
+               e.credential_scheme, c.public_key as "credential_public_key?", e.hash_suite, e.proof_version
+        FROM elections e
+        LEFT JOIN LATERAL (
+            SELECT public_key FROM election_keys
+            WHERE election_id = e.id AND purpose = 'ENCRYPTION'
+            ORDER BY created_at ASC LIMIT 1
+        ) k ON TRUE
+        LEFT JOIN LATERAL (
+            SELECT public_key FROM election_keys
+            WHERE election_id = e.id AND purpose = 'CREDENTIAL' AND status = 'ACTIVE'
+        ) c ON TRUE
+        WHERE e.id = $1
+        "#,
+        election_id
+    )
+    .fetch_optional(&state.db)
+    .await;
+
+    match result {
+        Ok(Some(rec)) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "id": rec.id,
+                "title": rec.title,
+                "form_config": rec.form_config,
+                "start_date": rec.start_date,
+                "end_date": rec.end_date,
                 "status": rec.status,
                 "access_type": rec.access_type,
-                "election_salt": rec.election_salt
+                "ballot_scheme": rec.ballot_scheme,
+                "encryption_public_key": rec.encryption_public_key,
+                "proof_version": rec.proof_version,
+                "credential_scheme": rec.credential_scheme,
+                "hash_suite": rec.hash_suite,
+                "credential_public_key": rec.credential_public_key
             })),
         )
             .into_response(),
@@ -609,8 +1625,17 @@ async fn verify_election(
     Path(election_id): Path<Uuid>,
     State(state): State<AppState>,
 ) -> impl IntoResponse {
+    // The key that signed the root, or the active one while the election is still open
     let root = sqlx::query!(
-        "SELECT merkle_root FROM elections WHERE id = $1",
+        r#"
+        SELECT e.merkle_root, e.merkle_tree_version, e.hash_suite, e.root_signature, e.root_key_fingerprint,
+               e.root_timestamp, e.root_timestamp_tsa, e.root_timestamped_at,
+               k.public_key as "public_key?"
+        FROM elections e
+        LEFT JOIN election_keys k ON k.election_id = e.id AND k.purpose = 'SIGNING'
+            AND (k.fingerprint = e.root_key_fingerprint OR (e.root_key_fingerprint IS NULL AND k.status = 'ACTIVE'))
+        WHERE e.id = $1
+        "#,
         election_id
     )
     .fetch_optional(&state.db)
@@ -619,32 +1644,179 @@ async fn verify_election(
     match root {
         Ok(Some(record)) => (
             StatusCode::OK,
-            Json(serde_json::json!({ "merkle_root": record.merkle_root })),
+            Json(serde_json::json!({
+                "election_id": election_id,
+                "merkle_root": record.merkle_root,
+                "merkle_tree_version": record.merkle_tree_version,
+                "hash_suite": record.hash_suite,
+                "root_signature": record.root_signature,
+                "key_fingerprint": record.root_key_fingerprint,
+                "public_key": record.public_key,
+                "root_timestamp": record.root_timestamp,
+                "root_timestamp_tsa": record.root_timestamp_tsa,
+                "root_timestamped_at": record.root_timestamped_at,
+            })),
         )
             .into_response(),
         _ => (StatusCode::NOT_FOUND, "Election not found").into_response(),
     }
 }
 
+/// A ballot's receipt against the sealed root, in the format `verify_receipt` reads.
+/// Proofs and receipt signatures are stored when the election is sealed.
+async fn get_sealed_receipt(
+    Path((election_id, ballot_hash)): Path<(Uuid, String)>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let receipt = sqlx::query!(
+        r#"
+        SELECT b.id, b.leaf_index, b.created_at, b.merkle_proof, b.receipt_signature,
+               e.title, e.status::text as "status!", e.merkle_root, e.merkle_tree_version, e.hash_suite,
+               e.root_signature, e.root_key_fingerprint, e.root_timestamp, e.root_timestamp_tsa,
+               e.root_timestamped_at, e.spent_root, e.spent_count, k.public_key as "public_key?"
+        FROM ballots b
+        JOIN elections e ON e.id = b.election_id
+        LEFT JOIN election_keys k ON k.election_id = e.id AND k.purpose = 'SIGNING' AND k.fingerprint = e.root_key_fingerprint
+        WHERE b.election_id = $1 AND b.ballot_hash = $2
+        ORDER BY b.leaf_index ASC LIMIT 1
+        "#,
+        election_id,
+        ballot_hash
+    )
+    .fetch_optional(&state.db)
+    .await;
+
+    let record = match receipt {
+        Ok(Some(record)) => record,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Ballot not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let (Some(merkle_path), Some(signature), Some(public_key)) =
+        (record.merkle_proof, record.receipt_signature, record.public_key)
+    else {
+        return (
+            StatusCode::CONFLICT,
+            "The election has not been sealed with a signed root yet",
+        )
+            .into_response();
+    };
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "election_id": election_id,
+            "ballot_id": record.id,
+            "ballot_hash": ballot_hash,
+            "timestamp": record.created_at.timestamp_millis(),
+            "leaf_index": record.leaf_index,
+            "merkle_path": merkle_path,
+            "merkle_tree_version": record.merkle_tree_version,
+            "hash_suite": record.hash_suite,
+            "root_hash": record.merkle_root,
+            "root_signature": record.root_signature,
+            "root_timestamp": record.root_timestamp,
+            "root_timestamp_tsa": record.root_timestamp_tsa,
+            "root_timestamped_at": record.root_timestamped_at,
+            "public_key": public_key,
+            "key_fingerprint": record.root_key_fingerprint,
+            "signature": signature,
+            "election": {
+                "title": record.title,
+                "status": record.status,
+                "spent_root": record.spent_root,
+                "spent_count": record.spent_count,
+            },
+        })),
+    )
+        .into_response()
+}
+
+/// The whole sealed election as one document, for `verify_receipt audit`
+async fn export_election(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match export::bundle(&state.db, election_id).await {
+        Ok(bundle) => (StatusCode::OK, Json(bundle)).into_response(),
+        Err(e @ ExportError::NotFound) => (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+        Err(e @ ExportError::NotSealed) => (StatusCode::CONFLICT, e.to_string()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn archive_election(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match export::archive(&state.db, &state.master_key, &state.audit_key, election_id).await {
+        Ok(archive) => (StatusCode::OK, Json(archive)).into_response(),
+        Err(e @ ExportError::NotFound) => (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+        Err(e @ ExportError::NotSealed) => (StatusCode::CONFLICT, e.to_string()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn import_election(
+    auth: AuthUser,
+    State(state): State<AppState>,
+    Json(archive): Json<ElectionArchive>,
+) -> impl IntoResponse {
+    match import::import(
+        &state.db,
+        &state.audit_key,
+        state.tsa.as_deref().map(TsaClient::public_key),
+        Some(auth.admin_id),
+        &archive,
+    ).await {
+        Ok(election_id) => (
+            StatusCode::CREATED,
+            Json(serde_json::json!({ "id": election_id, "status": "ARCHIVED" })),
+        )
+            .into_response(),
+        Err(e @ ImportError::Rejected(_)) => (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response(),
+        Err(e @ ImportError::Exists) => (StatusCode::CONFLICT, e.to_string()).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
 async fn start_election(
+    auth: AuthUser,
     Path(election_id): Path<Uuid>,
     State(state): State<AppState>,
 ) -> impl IntoResponse {
+    if let Err(response) = owned_election(&state, &auth, election_id).await {
+        return response;
+    }
+
+    let mut tx = match state.db.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    // Trustee-held elections have no encryption key until their ceremony completes
     let result = sqlx::query!(
-        "UPDATE elections SET status = 'OPEN' WHERE id = $1 AND status = 'DRAFT'",
+        "UPDATE elections SET status = 'OPEN' WHERE id = $1 AND status = 'DRAFT' AND NOT EXISTS (SELECT 1 FROM trustee_ceremonies c WHERE c.election_id = elections.id AND c.status <> 'COMPLETE')",
         election_id
     )
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await;
 
     match result {
         Ok(res) => {
             if res.rows_affected() > 0 {
-                StatusCode::OK.into_response()
+                let entry = serde_json::json!({ "election_id": election_id });
+                if let Err(response) = record_admin_action(&state, &mut tx, Some(auth.admin_id), "START_ELECTION", entry).await {
+                    return response;
+                }
+                match tx.commit().await {
+                    Ok(_) => StatusCode::OK.into_response(),
+                    Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+                }
             } else {
                 (
                     StatusCode::BAD_REQUEST,
-                    "Election not found or not in DRAFT state",
+                    "Election not found, not in DRAFT state or awaiting its trustee ceremony",
                 )
                     .into_response()
             }
@@ -654,20 +1826,42 @@ async fn start_election(
 }
 
 async fn close_election(
+    auth: AuthUser,
     Path(election_id): Path<Uuid>,
     State(state): State<AppState>,
 ) -> impl IntoResponse {
+    if let Err(response) = owned_election(&state, &auth, election_id).await {
+        return response;
+    }
+
+    let mut tx = match state.db.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
     let result = sqlx::query!(
-        "UPDATE elections SET status = 'SEALED' WHERE id = $1 AND status = 'OPEN'",
+        "UPDATE elections SET status = 'CLOSING' WHERE id = $1 AND status = 'OPEN'",
         election_id
     )
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await;
 
     match result {
         Ok(res) => {
             if res.rows_affected() > 0 {
-                StatusCode::OK.into_response()
+                let entry = serde_json::json!({ "election_id": election_id });
+                if let Err(response) = record_admin_action(&state, &mut tx, Some(auth.admin_id), "CLOSE_ELECTION", entry).await {
+                    return response;
+                }
+                if let Err(e) = tx.commit().await {
+                    return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+                }
+
+                // Seal now rather than waiting for the scheduler
+                match scheduler::seal_election(&state.db, &state.master_key, state.tsa.as_deref(), election_id).await {
+                    Ok(()) => StatusCode::OK.into_response(),
+                    Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+                }
             } else {
                 (
                     StatusCode::BAD_REQUEST,
@@ -684,6 +1878,71 @@ async fn get_election_results(
     Path(election_id): Path<Uuid>,
     State(state): State<AppState>,
 ) -> impl IntoResponse {
+    let election = match sqlx::query!(
+        "SELECT status::text as status, ballot_scheme FROM elections WHERE id = $1",
+        election_id
+    )
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(e)) => e,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Election not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    if election.ballot_scheme == "PLAINTEXT" {
+        return plaintext_results(&state, election_id).await;
+    }
+
+    if !matches!(election.status.as_deref(), Some("SEALED") | Some("ARCHIVED")) {
+        return (
+            StatusCode::FORBIDDEN,
+            "Results are available once the election is sealed",
+        )
+            .into_response();
+    }
+
+    let tally = sqlx::query!(
+        r#"
+        SELECT t.ballot_count, t.decrypted_tally, k.public_key as "public_key?"
+        FROM election_tallies t
+        LEFT JOIN election_keys k ON k.election_id = t.election_id AND k.purpose = 'ENCRYPTION'
+        WHERE t.election_id = $1
+        ORDER BY k.created_at ASC LIMIT 1
+        "#,
+        election_id
+    )
+    .fetch_optional(&state.db)
+    .await;
+
+    match tally {
+        Ok(Some(rec)) => {
+            let decrypted: Option<ballot::DecryptedTally> = rec
+                .decrypted_tally
+                .and_then(|t| serde_json::from_value(t).ok());
+
+            match decrypted {
+                Some(decrypted) => (
+                    StatusCode::OK,
+                    Json(serde_json::json!({
+                        "ballot_scheme": election.ballot_scheme,
+                        "public_key": rec.public_key,
+                        "ballot_count": rec.ballot_count,
+                        "totals": ballot::tally_counts(&decrypted),
+                        "tally": decrypted,
+                    })),
+                )
+                    .into_response(),
+                None => (StatusCode::NOT_FOUND, "Tally has not been decrypted yet").into_response(),
+            }
+        }
+        Ok(None) => (StatusCode::NOT_FOUND, "Tally not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Legacy counting for elections created before ballots were encrypted
+async fn plaintext_results(state: &AppState, election_id: Uuid) -> Response {
     use sqlx::Row;
     use std::collections::HashMap;
 
@@ -714,3 +1973,601 @@ async fn get_election_results(
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
     }
 }
+
+/// Every ballot in log order with its well-formedness proofs, for independent re-verification
+async fn list_ballots(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let ballots = sqlx::query!(
+        "SELECT leaf_index, ballot_hash, encrypted_choices, proofs FROM ballots WHERE election_id = $1 ORDER BY leaf_index ASC",
+        election_id
+    )
+    .fetch_all(&state.db)
+    .await;
+
+    match ballots {
+        Ok(rows) => {
+            let ballots: Vec<Value> = rows
+                .into_iter()
+                .map(|b| {
+                    serde_json::json!({
+                        "leaf_index": b.leaf_index,
+                        "ballot_hash": b.ballot_hash,
+                        "choices": b.encrypted_choices,
+                        "proofs": b.proofs,
+                    })
+                })
+                .collect();
+            (StatusCode::OK, Json(ballots)).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn list_challenged_ballots(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let ballots = sqlx::query!(
+        r#"SELECT id, ballot_hash, encrypted_choices, randomness as "randomness!", selections as "selections!" FROM ballot_commitments WHERE election_id = $1 AND status = 'CHALLENGED' ORDER BY ballot_hash ASC"#,
+        election_id
+    )
+    .fetch_all(&state.db)
+    .await;
+
+    match ballots {
+        Ok(rows) => {
+            let ballots: Vec<Value> = rows
+                .into_iter()
+                .map(|b| {
+                    serde_json::json!({
+                        "commitment_id": b.id,
+                        "ballot_hash": b.ballot_hash,
+                        "choices": b.encrypted_choices,
+                        "randomness": b.randomness,
+                        "selections": b.selections,
+                    })
+                })
+                .collect();
+            (StatusCode::OK, Json(ballots)).into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Root of the spent-credential set, checked against the size of the ballot log.
+/// Once sealed, the signed root and count are returned too.
+async fn get_spent_set(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let sealed = match sqlx::query!(
+        "SELECT spent_root, spent_count, spent_root_signature, root_key_fingerprint, hash_suite FROM elections WHERE id = $1",
+        election_id
+    )
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(sealed)) => sealed,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Election not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let tree = match spent_set::tree(&state.db, election_id).await {
+        Ok(tree) => tree,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let ballot_count = match ballot_log::size(&state.db, election_id).await {
+        Ok(size) => size,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "root": tree.root,
+            "size": tree.size(),
+            "hash_suite": sealed.hash_suite,
+            "ballot_count": ballot_count,
+            "consistent": tree.size() as i64 == ballot_count,
+            "sealed": sealed.spent_root.map(|root| serde_json::json!({
+                "spent_root": root,
+                "spent_count": sealed.spent_count,
+                "signature": sealed.spent_root_signature,
+                "key_fingerprint": sealed.root_key_fingerprint,
+            })),
+        })),
+    )
+        .into_response()
+}
+
+/// Membership or non-membership proof for a nullifier or spent token hash
+async fn get_spent_proof(
+    Path((election_id, entry)): Path<(Uuid, String)>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match spent_set::tree(&state.db, election_id).await {
+        Ok(tree) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "root": tree.root,
+                "size": tree.size(),
+                "proof": tree.prove(&entry),
+            })),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn get_log_head(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match ballot_log::latest_head(&state.db, election_id).await {
+        Ok(Some(head)) => (StatusCode::OK, Json(head)).into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "No tree head published yet").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn list_log_heads(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match ballot_log::list_heads(&state.db, election_id).await {
+        Ok(heads) => (StatusCode::OK, Json(heads)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Resolves an optional requested tree size against the log, defaulting to the latest published head
+async fn resolve_tree_size(
+    state: &AppState,
+    election_id: Uuid,
+    requested: Option<i64>,
+) -> Result<i64, (StatusCode, String)> {
+    let current = ballot_log::size(&state.db, election_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let size = match requested {
+        Some(size) => size,
+        None => ballot_log::latest_head(&state.db, election_id)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .map(|h| h.tree_size as i64)
+            .unwrap_or(current),
+    };
+
+    if size < 1 || size > current {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Tree size must be between 1 and {}", current),
+        ));
+    }
+    Ok(size)
+}
+
+async fn get_inclusion_proof(
+    Path((election_id, ballot_hash)): Path<(Uuid, String)>,
+    Query(query): Query<InclusionQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let ballot = match sqlx::query!(
+        "SELECT leaf_index FROM ballots WHERE election_id = $1 AND ballot_hash = $2 ORDER BY leaf_index ASC LIMIT 1",
+        election_id,
+        ballot_hash
+    )
+    .fetch_optional(&state.db)
+    .await
+    {
+        Ok(Some(b)) => b,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Ballot not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let tree_size = match resolve_tree_size(&state, election_id, query.tree_size).await {
+        Ok(size) => size,
+        Err(e) => return e.into_response(),
+    };
+
+    if ballot.leaf_index >= tree_size {
+        return (
+            StatusCode::NOT_FOUND,
+            "Ballot is not included in the requested tree size",
+        )
+            .into_response();
+    }
+
+    let needed = StoredTree::proof_nodes(tree_size as usize, ballot.leaf_index as usize);
+    match ballot_log::stored_tree(&state.db, election_id, tree_size, &needed).await {
+        Ok(tree) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "ballot_hash": ballot_hash,
+                "root_hash": tree.root(),
+                "proof": tree.get_proof(ballot.leaf_index as usize),
+            })),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// One inclusion proof for many ballots, for auditors checking receipts in bulk.
+/// A POST so the hash list is not limited by URL length.
+async fn get_inclusion_multiproof(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+    Json(payload): Json<MultiproofRequest>,
+) -> impl IntoResponse {
+    let ballots = match sqlx::query!(
+        "SELECT DISTINCT ON (ballot_hash) ballot_hash, leaf_index FROM ballots WHERE election_id = $1 AND ballot_hash = ANY($2) ORDER BY ballot_hash, leaf_index ASC",
+        election_id,
+        &payload.ballot_hashes
+    )
+    .fetch_all(&state.db)
+    .await
+    {
+        Ok(ballots) => ballots,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    let found: std::collections::HashMap<&str, i64> = ballots
+        .iter()
+        .map(|b| (b.ballot_hash.as_str(), b.leaf_index))
+        .collect();
+    if let Some(missing) = payload.ballot_hashes.iter().find(|h| !found.contains_key(h.as_str())) {
+        return (StatusCode::NOT_FOUND, format!("Ballot not found: {}", missing)).into_response();
+    }
+
+    let tree_size = match resolve_tree_size(&state, election_id, payload.tree_size).await {
+        Ok(size) => size,
+        Err(e) => return e.into_response(),
+    };
+
+    if let Some(late) = ballots.iter().find(|b| b.leaf_index >= tree_size) {
+        return (
+            StatusCode::NOT_FOUND,
+            format!("Ballot {} is not included in the requested tree size", late.ballot_hash),
+        )
+            .into_response();
+    }
+
+    let indices: Vec<usize> = found.values().map(|index| *index as usize).collect();
+    let needed = StoredTree::multiproof_nodes(tree_size as usize, &indices);
+    let tree = match ballot_log::stored_tree(&state.db, election_id, tree_size, &needed).await {
+        Ok(tree) => tree,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let Some(proof) = tree.get_multiproof(&indices) else {
+        return (StatusCode::BAD_REQUEST, "At least one ballot hash is required").into_response();
+    };
+
+    // Leaves in the order the proof expects them
+    let by_index: std::collections::HashMap<i64, &str> = ballots
+        .iter()
+        .map(|b| (b.leaf_index, b.ballot_hash.as_str()))
+        .collect();
+    let ballot_hashes: Vec<&str> = proof
+        .leaf_indices
+        .iter()
+        .map(|index| by_index[&(*index as i64)])
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "ballot_hashes": ballot_hashes,
+            "root_hash": tree.root(),
+            "proof": proof,
+        })),
+    )
+        .into_response()
+}
+
+async fn get_consistency_proof(
+    Path(election_id): Path<Uuid>,
+    Query(query): Query<ConsistencyQuery>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let second = match resolve_tree_size(&state, election_id, query.second).await {
+        Ok(size) => size,
+        Err(e) => return e.into_response(),
+    };
+
+    if query.first < 1 || query.first > second {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("First size must be between 1 and {}", second),
+        )
+            .into_response();
+    }
+
+    let needed = StoredTree::consistency_nodes(query.first as usize, second as usize);
+    match ballot_log::stored_tree(&state.db, election_id, second, &needed).await {
+        Ok(tree) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "first_root": tree.root_at(query.first as usize),
+                "second_root": tree.root(),
+                "proof": tree.get_consistency_proof(query.first as usize),
+            })),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+fn key_error_response(e: KeyError) -> Response {
+    match e {
+        KeyError::NoActiveKey => (StatusCode::CONFLICT, e.to_string()).into_response(),
+        KeyError::NotFound => (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+        KeyError::Conflict(_) => (StatusCode::CONFLICT, e.to_string()).into_response(),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Refuses changes to an imported election, which is served read-only
+async fn reject_archived(state: &AppState, election_id: Uuid) -> Result<(), Response> {
+    let status = sqlx::query_scalar!(
+        r#"SELECT status::text as "status!" FROM elections WHERE id = $1"#,
+        election_id
+    )
+    .fetch_optional(&state.db)
+    .await;
+
+    match status {
+        Ok(Some(status)) if status == "ARCHIVED" => {
+            Err((StatusCode::CONFLICT, "Archived elections are read-only").into_response())
+        }
+        Ok(_) => Ok(()),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()),
+    }
+}
+
+/// Confirms the caller administers the election. Anything else looks like a missing election.
+async fn owned_election(state: &AppState, auth: &AuthUser, election_id: Uuid) -> Result<(), Response> {
+    let owned = sqlx::query!(
+        "SELECT id FROM elections WHERE id = $1 AND admin_id = $2",
+        election_id,
+        auth.admin_id
+    )
+    .fetch_optional(&state.db)
+    .await;
+
+    match owned {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err((StatusCode::NOT_FOUND, "Election not found").into_response()),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()),
+    }
+}
+
+/// Writes an admin audit entry as part of `tx`
+async fn record_admin_action(
+    state: &AppState,
+    tx: &mut Transaction<'_, Postgres>,
+    admin_id: Option<Uuid>,
+    action: &str,
+    payload: Value,
+) -> Result<(), Response> {
+    audit_log::append(tx, &state.audit_key, admin_id, action, payload)
+        .await
+        .map(|_| ())
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())
+}
+
+/// Writes an admin audit entry for an action with no database change of its own
+async fn record_standalone_action(
+    state: &AppState,
+    admin_id: Option<Uuid>,
+    action: &str,
+    payload: Value,
+) -> Result<(), Response> {
+    let mut tx = state
+        .db
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())?;
+    record_admin_action(state, &mut tx, admin_id, action, payload).await?;
+    tx.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())
+}
+
+/// The caller's slice of the admin audit log. The whole chain is verified, but only
+/// entries by the caller or about elections they own are returned.
+async fn get_admin_audit_log(auth: AuthUser, State(state): State<AppState>) -> impl IntoResponse {
+    let (chain, entries) = match tokio::try_join!(
+        audit_log::entries(&state.db),
+        audit_log::admin_entries(&state.db, auth.admin_id)
+    ) {
+        Ok(both) => both,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let public_key = crypto::public_key_hex(&state.audit_key);
+    let verification = crypto::audit::verify_audit_chain(&chain, &public_key);
+
+    (
+        StatusCode::OK,
+        Json(serde_json::json!({
+            "public_key": public_key,
+            "key_fingerprint": crypto::key_fingerprint(&public_key),
+            "verification": verification,
+            "entries": entries,
+        })),
+    )
+        .into_response()
+}
+
+async fn list_election_keys(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match keys::list_public(&state.db, election_id).await {
+        Ok(list) => (StatusCode::OK, Json(list)).into_response(),
+        Err(e) => key_error_response(e),
+    }
+}
+
+async fn rotate_election_key(
+    auth: AuthUser,
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    if let Err(response) = owned_election(&state, &auth, election_id).await {
+        return response;
+    }
+    if let Err(response) = reject_archived(&state, election_id).await {
+        return response;
+    }
+
+    // The archive is signed by the key that sealed the root, so `rotate` refuses once sealing begins
+    match keys::rotate(&state.db, &state.master_key, election_id, keys::KeyPurpose::Signing).await {
+        Ok(fingerprint) => (
+            StatusCode::CREATED,
+            Json(serde_json::json!({ "fingerprint": fingerprint })),
+        )
+            .into_response(),
+        Err(e) => key_error_response(e),
+    }
+}
+
+async fn retire_election_key(
+    auth: AuthUser,
+    Path((election_id, key_id)): Path<(Uuid, Uuid)>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    if let Err(response) = owned_election(&state, &auth, election_id).await {
+        return response;
+    }
+    if let Err(response) = reject_archived(&state, election_id).await {
+        return response;
+    }
+
+    match keys::retire(&state.db, election_id, key_id).await {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => key_error_response(e),
+    }
+}
+
+fn ceremony_error_response(e: CeremonyError) -> Response {
+    match e {
+        CeremonyError::NotFound => (StatusCode::NOT_FOUND, e.to_string()).into_response(),
+        CeremonyError::Conflict(_) => (StatusCode::CONFLICT, e.to_string()).into_response(),
+        CeremonyError::Invalid(_) => (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response(),
+        CeremonyError::BadSignature => (StatusCode::UNAUTHORIZED, e.to_string()).into_response(),
+        CeremonyError::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+async fn setup_trustees(
+    auth: AuthUser,
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+    Json(payload): Json<SetupTrusteesRequest>,
+) -> impl IntoResponse {
+    if let Err(response) = owned_election(&state, &auth, election_id).await {
+        return response;
+    }
+
+    match trustees::setup(
+        &state.db,
+        election_id,
+        payload.threshold,
+        payload.complaint_window_secs.unwrap_or(trustees::DEFAULT_COMPLAINT_WINDOW_SECS),
+        &payload.trustees,
+    ).await {
+        Ok(ceremony) => (StatusCode::CREATED, Json(ceremony)).into_response(),
+        Err(e) => ceremony_error_response(e),
+    }
+}
+
+// Ceremony messages authenticate with the trustee's own signature rather than an admin token
+
+async fn submit_dealing(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+    Json(payload): Json<SignedSubmission<crypto::threshold::Dealing>>,
+) -> impl IntoResponse {
+    match trustees::submit_dealing(&state.db, election_id, &payload).await {
+        Ok(()) => StatusCode::CREATED.into_response(),
+        Err(e) => ceremony_error_response(e),
+    }
+}
+
+async fn submit_complaint(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+    Json(payload): Json<SignedSubmission<crypto::threshold::Complaint>>,
+) -> impl IntoResponse {
+    match trustees::submit_complaint(&state.db, election_id, &payload).await {
+        Ok(()) => StatusCode::CREATED.into_response(),
+        Err(e) => ceremony_error_response(e),
+    }
+}
+
+async fn finalize_ceremony(
+    auth: AuthUser,
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    if let Err(response) = owned_election(&state, &auth, election_id).await {
+        return response;
+    }
+
+    match trustees::finalize(&state.db, election_id).await {
+        Ok(ceremony) => (StatusCode::OK, Json(ceremony)).into_response(),
+        Err(e) => ceremony_error_response(e),
+    }
+}
+
+async fn submit_partial_decryption(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+    Json(payload): Json<SignedSubmission<ballot::PartialTally>>,
+) -> impl IntoResponse {
+    match trustees::submit_partial(&state.db, election_id, &payload).await {
+        Ok(progress) => (StatusCode::CREATED, Json(progress)).into_response(),
+        Err(e) => ceremony_error_response(e),
+    }
+}
+
+async fn get_ceremony_transcript(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    match trustees::transcript(&state.db, election_id).await {
+        Ok(transcript) => (StatusCode::OK, Json(transcript)).into_response(),
+        Err(e) => ceremony_error_response(e),
+    }
+}
+
+/// The homomorphic total computed at sealing, which trustees partially decrypt
+async fn get_encrypted_tally(
+    Path(election_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> impl IntoResponse {
+    let tally = sqlx::query!(
+        "SELECT ballot_count, encrypted_tally FROM election_tallies WHERE election_id = $1",
+        election_id
+    )
+    .fetch_optional(&state.db)
+    .await;
+
+    match tally {
+        Ok(Some(rec)) => (
+            StatusCode::OK,
+            Json(serde_json::json!({
+                "election_id": election_id,
+                "ballot_count": rec.ballot_count,
+                "encrypted_tally": rec.encrypted_tally,
+            })),
+        )
+            .into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, "Tally not found").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
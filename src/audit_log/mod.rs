@@ -0,0 +1,142 @@
+use crate::crypto::audit::{AuditEntry, AUDIT_GENESIS_HASH};
+use chrono::{TimeZone, Utc};
+use ed25519_dalek::SigningKey;
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, Transaction};
+use uuid::Uuid;
+
+/// Appends a signed entry to the admin audit log inside the caller's transaction,
+/// so the entry is written if and only if the action it records is.
+pub async fn append(
+    tx: &mut Transaction<'_, Postgres>,
+    key: &SigningKey,
+    admin_id: Option<Uuid>,
+    action: &str,
+    payload: Value,
+) -> Result<AuditEntry, sqlx::Error> {
+    // One chain for the whole server: appends are serialized until the caller commits
+    sqlx::query!("LOCK TABLE audit_logs IN EXCLUSIVE MODE")
+        .execute(&mut **tx)
+        .await?;
+
+    let head = sqlx::query!(
+        r#"SELECT sequence as "sequence!", entry_hash as "entry_hash!" FROM audit_logs WHERE sequence IS NOT NULL ORDER BY sequence DESC LIMIT 1"#
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    let (sequence, prev_hash) = match head {
+        Some(head) => (head.sequence + 1, head.entry_hash),
+        None => (1, AUDIT_GENESIS_HASH.to_string()),
+    };
+
+    let created_at = Utc::now().timestamp_millis();
+    let entry = AuditEntry::sign(key, sequence, prev_hash, admin_id, action, payload, created_at);
+
+    sqlx::query!(
+        "INSERT INTO audit_logs (admin_id, action, payload, signature, created_at, sequence, prev_hash, entry_hash, key_fingerprint) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        entry.admin_id,
+        entry.action,
+        entry.payload,
+        entry.signature,
+        Utc.timestamp_millis_opt(created_at).unwrap(),
+        entry.sequence,
+        entry.prev_hash,
+        entry.entry_hash,
+        entry.key_fingerprint
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(entry)
+}
+
+/// The whole chain, oldest first
+pub async fn entries(pool: &PgPool) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    let recs = sqlx::query!(
+        r#"
+        SELECT sequence as "sequence!", admin_id, action, payload, created_at, prev_hash as "prev_hash!",
+               entry_hash as "entry_hash!", key_fingerprint as "key_fingerprint!", signature
+        FROM audit_logs WHERE sequence IS NOT NULL ORDER BY sequence ASC
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(recs
+        .into_iter()
+        .map(|r| AuditEntry {
+            sequence: r.sequence,
+            admin_id: r.admin_id,
+            action: r.action,
+            payload: r.payload,
+            created_at: r.created_at.timestamp_millis(),
+            prev_hash: r.prev_hash,
+            entry_hash: r.entry_hash,
+            key_fingerprint: r.key_fingerprint,
+            signature: r.signature,
+        })
+        .collect())
+}
+
+/// Entries whose payload names `election_id`, oldest first. Their neighbours in the
+/// chain belong to other elections, so each one verifies on its own but not as a chain.
+pub async fn election_entries(pool: &PgPool, election_id: Uuid) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    let recs = sqlx::query!(
+        r#"
+        SELECT sequence as "sequence!", admin_id, action, payload, created_at, prev_hash as "prev_hash!",
+               entry_hash as "entry_hash!", key_fingerprint as "key_fingerprint!", signature
+        FROM audit_logs WHERE sequence IS NOT NULL AND payload->>'election_id' = $1 ORDER BY sequence ASC
+        "#,
+        election_id.to_string()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(recs
+        .into_iter()
+        .map(|r| AuditEntry {
+            sequence: r.sequence,
+            admin_id: r.admin_id,
+            action: r.action,
+            payload: r.payload,
+            created_at: r.created_at.timestamp_millis(),
+            prev_hash: r.prev_hash,
+            entry_hash: r.entry_hash,
+            key_fingerprint: r.key_fingerprint,
+            signature: r.signature,
+        })
+        .collect())
+}
+
+/// Entries an admin may read: their own actions and any action on an election they
+/// own, oldest first. Like `election_entries`, each verifies on its own.
+pub async fn admin_entries(pool: &PgPool, admin_id: Uuid) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    let recs = sqlx::query!(
+        r#"
+        SELECT sequence as "sequence!", admin_id, action, payload, created_at, prev_hash as "prev_hash!",
+               entry_hash as "entry_hash!", key_fingerprint as "key_fingerprint!", signature
+        FROM audit_logs
+        WHERE sequence IS NOT NULL
+          AND (admin_id = $1 OR payload->>'election_id' IN (SELECT id::text FROM elections WHERE admin_id = $1))
+        ORDER BY sequence ASC
+        "#,
+        admin_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(recs
+        .into_iter()
+        .map(|r| AuditEntry {
+            sequence: r.sequence,
+            admin_id: r.admin_id,
+            action: r.action,
+            payload: r.payload,
+            created_at: r.created_at.timestamp_millis(),
+            prev_hash: r.prev_hash,
+            entry_hash: r.entry_hash,
+            key_fingerprint: r.key_fingerprint,
+            signature: r.signature,
+        })
+        .collect())
+}
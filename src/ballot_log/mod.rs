@@ -0,0 +1,262 @@
+use crate::crypto::{self, HashSuite, MerkleTree, NodeId, SignedTreeHead, StoredTree};
+use ed25519_dalek::SigningKey;
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Reserves the next position in the election's ballot log.
+/// The caller must hold the election row lock (`SELECT ... FOR UPDATE`) so appends are serialized.
+pub async fn next_leaf_index(
+    tx: &mut Transaction<'_, Postgres>,
+    election_id: Uuid,
+) -> Result<i64, sqlx::Error> {
+    let rec = sqlx::query!(
+        "SELECT COALESCE(MAX(leaf_index) + 1, 0) as next FROM ballots WHERE election_id = $1",
+        election_id
+    )
+    .fetch_one(&mut **tx)
+    .await?;
+
+    Ok(rec.next.unwrap_or(0))
+}
+
+/// Number of ballots appended to the election's log so far
+pub async fn size(pool: &PgPool, election_id: Uuid) -> Result<i64, sqlx::Error> {
+    let rec = sqlx::query!(
+        "SELECT COUNT(*) as count FROM ballots WHERE election_id = $1",
+        election_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(rec.count.unwrap_or(0))
+}
+
+/// The hash suite recorded for the election at creation
+pub async fn hash_suite<'e>(
+    executor: impl PgExecutor<'e>,
+    election_id: Uuid,
+) -> Result<HashSuite, sqlx::Error> {
+    let name = sqlx::query_scalar!("SELECT hash_suite FROM elections WHERE id = $1", election_id)
+        .fetch_one(executor)
+        .await?;
+
+    name.parse().map_err(|e: String| sqlx::Error::Decode(e.into()))
+}
+
+async fn read_nodes<'e>(
+    executor: impl PgExecutor<'e>,
+    election_id: Uuid,
+    ids: &[NodeId],
+) -> Result<HashMap<NodeId, String>, sqlx::Error> {
+    let levels: Vec<i16> = ids.iter().map(|id| id.level as i16).collect();
+    let indexes: Vec<i64> = ids.iter().map(|id| id.index as i64).collect();
+    let recs = sqlx::query!(
+        r#"
+        SELECT n.level, n.node_index, n.hash FROM merkle_nodes n
+        JOIN UNNEST($2::SMALLINT[], $3::BIGINT[]) AS wanted(level, node_index)
+          ON n.level = wanted.level AND n.node_index = wanted.node_index
+        WHERE n.election_id = $1
+        "#,
+        election_id,
+        &levels,
+        &indexes
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(recs
+        .into_iter()
+        .map(|r| {
+            let id = NodeId {
+                level: r.level as u32,
+                index: r.node_index as usize,
+            };
+            (id, r.hash)
+        })
+        .collect())
+}
+
+async fn write_nodes<'e>(
+    executor: impl PgExecutor<'e>,
+    election_id: Uuid,
+    nodes: &[(NodeId, String)],
+) -> Result<(), sqlx::Error> {
+    let levels: Vec<i16> = nodes.iter().map(|(id, _)| id.level as i16).collect();
+    let indexes: Vec<i64> = nodes.iter().map(|(id, _)| id.index as i64).collect();
+    let hashes: Vec<String> = nodes.iter().map(|(_, hash)| hash.clone()).collect();
+    sqlx::query!(
+        "INSERT INTO merkle_nodes (election_id, level, node_index, hash) SELECT $1, * FROM UNNEST($2::SMALLINT[], $3::BIGINT[], $4::VARCHAR[])",
+        election_id,
+        &levels,
+        &indexes,
+        &hashes
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Adds the ballot at `leaf_index` to the stored tree, in O(log n): its leaf
+/// node and every parent it completes. Same locking rule as `next_leaf_index`.
+pub async fn append(
+    tx: &mut Transaction<'_, Postgres>,
+    suite: HashSuite,
+    election_id: Uuid,
+    leaf_index: i64,
+    ballot_hash: &str,
+) -> Result<(), sqlx::Error> {
+    let index = leaf_index as usize;
+    let frontier = read_nodes(&mut **tx, election_id, &crypto::frontier(index)).await?;
+    let nodes = crypto::append_leaf(suite, ballot_hash, index, &frontier).ok_or_else(|| {
+        sqlx::Error::Protocol(format!("Ballot log of election {} is missing nodes below leaf {}", election_id, index))
+    })?;
+    write_nodes(&mut **tx, election_id, &nodes).await
+}
+
+/// Stores every complete node of `tree`, for a log written in one go
+pub async fn store_tree<'e>(
+    executor: impl PgExecutor<'e>,
+    election_id: Uuid,
+    tree: &MerkleTree,
+) -> Result<(), sqlx::Error> {
+    write_nodes(executor, election_id, &tree.complete_nodes()).await
+}
+
+/// The log's first `size` leaves as a tree, loaded with only the nodes in
+/// `needed` (see `StoredTree::proof_nodes` and friends)
+pub async fn stored_tree(
+    pool: &PgPool,
+    election_id: Uuid,
+    size: i64,
+    needed: &[NodeId],
+) -> Result<StoredTree, sqlx::Error> {
+    let suite = hash_suite(pool, election_id).await?;
+    let nodes = read_nodes(pool, election_id, needed).await?;
+    if nodes.len() != needed.len() {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    Ok(StoredTree::new(suite, size as usize, nodes))
+}
+
+/// Indexes the ballots of elections whose log predates `merkle_nodes`
+pub async fn backfill_nodes(pool: &PgPool) -> Result<usize, sqlx::Error> {
+    let elections = sqlx::query_scalar!(
+        r#"
+        SELECT DISTINCT b.election_id FROM ballots b
+        WHERE NOT EXISTS (
+            SELECT 1 FROM merkle_nodes n
+            WHERE n.election_id = b.election_id AND n.level = 0 AND n.node_index = b.leaf_index
+        )
+        "#
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for election_id in &elections {
+        let suite = hash_suite(pool, *election_id).await?;
+        let leaves = sqlx::query_scalar!(
+            "SELECT ballot_hash FROM ballots WHERE election_id = $1 ORDER BY leaf_index ASC",
+            election_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut tx = pool.begin().await?;
+        sqlx::query!("DELETE FROM merkle_nodes WHERE election_id = $1", election_id)
+            .execute(&mut *tx)
+            .await?;
+        store_tree(&mut *tx, *election_id, &MerkleTree::new(suite, leaves)).await?;
+        tx.commit().await?;
+    }
+    Ok(elections.len())
+}
+
+/// Signs a head over the log's first `tree_size` leaves without publishing it
+pub fn sign_head(key: &SigningKey, election_id: Uuid, tree_size: usize, root_hash: String) -> SignedTreeHead {
+    crypto::sign_tree_head(
+        key,
+        election_id,
+        tree_size,
+        root_hash,
+        chrono::Utc::now().timestamp_millis(),
+    )
+}
+
+/// All published heads for an election, oldest first
+pub async fn list_heads(pool: &PgPool, election_id: Uuid) -> Result<Vec<SignedTreeHead>, sqlx::Error> {
+    let recs = sqlx::query!(
+        "SELECT tree_size, root_hash, timestamp, key_fingerprint, signature FROM tree_heads WHERE election_id = $1 ORDER BY tree_size ASC",
+        election_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(recs
+        .into_iter()
+        .map(|r| SignedTreeHead {
+            election_id,
+            tree_size: r.tree_size as usize,
+            root_hash: r.root_hash,
+            timestamp: r.timestamp,
+            key_fingerprint: r.key_fingerprint,
+            signature: r.signature,
+        })
+        .collect())
+}
+
+pub async fn latest_head(
+    pool: &PgPool,
+    election_id: Uuid,
+) -> Result<Option<SignedTreeHead>, sqlx::Error> {
+    let rec = sqlx::query!(
+        "SELECT tree_size, root_hash, timestamp, key_fingerprint, signature FROM tree_heads WHERE election_id = $1 ORDER BY tree_size DESC LIMIT 1",
+        election_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(rec.map(|r| SignedTreeHead {
+        election_id,
+        tree_size: r.tree_size as usize,
+        root_hash: r.root_hash,
+        timestamp: r.timestamp,
+        key_fingerprint: r.key_fingerprint,
+        signature: r.signature,
+    }))
+}
+
+/// Publishes a new signed head if the log has grown since the last one
+pub async fn publish_head(
+    pool: &PgPool,
+    key: &SigningKey,
+    election_id: Uuid,
+) -> Result<Option<SignedTreeHead>, sqlx::Error> {
+    let current = size(pool, election_id).await?;
+    let published = latest_head(pool, election_id)
+        .await?
+        .map(|h| h.tree_size as i64)
+        .unwrap_or(0);
+
+    if current == 0 || current <= published {
+        return Ok(None);
+    }
+
+    let tree = stored_tree(pool, election_id, current, &crypto::frontier(current as usize)).await?;
+    let root = tree.root().ok_or(sqlx::Error::RowNotFound)?;
+    let head = sign_head(key, election_id, current as usize, root);
+
+    sqlx::query!(
+        "INSERT INTO tree_heads (election_id, tree_size, root_hash, timestamp, key_fingerprint, signature) VALUES ($1, $2, $3, $4, $5, $6) ON CONFLICT DO NOTHING",
+        election_id,
+        head.tree_size as i64,
+        head.root_hash,
+        head.timestamp,
+        head.key_fingerprint,
+        head.signature
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(Some(head))
+}
@@ -0,0 +1,79 @@
+//! Minimal RFC 3161 timestamp authority for tests and air-gapped deployments.
+//!
+//! POST a DER TimeStampReq to `/` (application/timestamp-query) to get a
+//! TimeStampResp back. Tokens are signed with Ed25519 and carry no certificate,
+//! so point `TSA_PUBLIC_KEY` at the key served from `/public-key`.
+
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Router,
+};
+use ed25519_dalek::SigningKey;
+use rand::rngs::OsRng;
+use solesigner::crypto::timestamp;
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+struct Tsa {
+    key: SigningKey,
+    public_key: String, // hex SubjectPublicKeyInfo DER
+    next_serial: AtomicU64,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let key = match env::var("TSA_SIGNING_KEY")
+        .ok()
+        .and_then(|seed| hex::decode(seed.trim()).ok())
+        .and_then(|seed| <[u8; 32]>::try_from(seed).ok())
+    {
+        Some(seed) => SigningKey::from_bytes(&seed),
+        None => {
+            println!("⚠️  TSA_SIGNING_KEY not set, using an ephemeral key (tokens will not verify under a later key)");
+            SigningKey::generate(&mut OsRng)
+        }
+    };
+    let public_key = timestamp::ed25519_spki(&key.verifying_key())?;
+    println!("TSA public key: {}", public_key);
+
+    // Serials only need to be unique per TSA; starting from the clock keeps them so across restarts
+    let tsa = Arc::new(Tsa {
+        key,
+        public_key,
+        next_serial: AtomicU64::new(chrono::Utc::now().timestamp_millis() as u64),
+    });
+
+    let router = Router::new()
+        .route("/", post(issue))
+        .route("/public-key", get(public_key_hex))
+        .with_state(tsa);
+
+    let addr = env::var("TSA_ADDR").unwrap_or("127.0.0.1:3161".to_string());
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    println!("🕒 Local TSA listening on {}", addr);
+    axum::serve(listener, router).await?;
+
+    Ok(())
+}
+
+async fn issue(State(tsa): State<Arc<Tsa>>, body: Bytes) -> impl IntoResponse {
+    let serial = tsa.next_serial.fetch_add(1, Ordering::SeqCst);
+    match timestamp::issue(&tsa.key, &body, serial, chrono::Utc::now()) {
+        Ok(response) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "application/timestamp-reply")],
+            response,
+        )
+            .into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+async fn public_key_hex(State(tsa): State<Arc<Tsa>>) -> impl IntoResponse {
+    tsa.public_key.clone()
+}
@@ -0,0 +1,281 @@
+use curve25519_dalek::scalar::Scalar;
+use ed25519_dalek::SigningKey;
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use solesigner::crypto::{
+    ballot::{self, EncryptedBallot},
+    elgamal::{self, scalar_hex, ProofContext},
+    public_key_hex, sign_message,
+    threshold::{self, Dealing},
+    trustee_message,
+};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use uuid::Uuid;
+
+/// A trustee's private key file. Keep it offline; only the public halves are given to the election admin.
+#[derive(Serialize, Deserialize)]
+struct TrusteeKeys {
+    auth_secret: String, // hex Ed25519 seed
+    auth_public_key: String,
+    #[serde(with = "scalar_hex")]
+    share_secret: Scalar,
+    share_public_key: String,
+}
+
+// Subsets of GET /audit/:election_id/ceremony and GET /audit/:election_id/tally
+
+#[derive(Deserialize)]
+struct Trustee {
+    trustee_index: u32,
+    auth_public_key: String,
+    share_public_key: String,
+    disqualified: bool,
+}
+
+#[derive(Deserialize)]
+struct Ceremony {
+    election_id: Uuid,
+    threshold: u32,
+    #[serde(default = "legacy_proof_version")]
+    proof_version: i16, // Missing means the server predates proof versions
+    trustees: Vec<Trustee>,
+}
+
+fn legacy_proof_version() -> i16 {
+    1
+}
+
+impl Ceremony {
+    fn proof_context(&self) -> ProofContext {
+        ProofContext::for_version(self.election_id, self.proof_version)
+    }
+}
+
+#[derive(Deserialize)]
+struct TranscriptMessage {
+    trustee_index: u32,
+    kind: String,
+    payload: String,
+}
+
+#[derive(Deserialize)]
+struct Transcript {
+    ceremony: Ceremony,
+    verification_keys: BTreeMap<u32, String>,
+    messages: Vec<TranscriptMessage>,
+}
+
+#[derive(Deserialize)]
+struct EncryptedTally {
+    encrypted_tally: EncryptedBallot,
+}
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let result = match args.get(1).map(String::as_str) {
+        Some("keygen") => Ok(keygen()),
+        Some("deal") if args.len() == 4 => deal(&args[2], &args[3]),
+        Some("check") if args.len() == 4 => check(&args[2], &args[3]),
+        Some("decrypt") if args.len() == 5 => decrypt(&args[2], &args[3], &args[4]),
+        _ => {
+            println!("Usage:");
+            println!("  trustee keygen                                  > keys.json");
+            println!("  trustee deal <keys.json> <ceremony.json>        # POST /elections/:id/ceremony/dealings");
+            println!("  trustee check <keys.json> <ceremony.json>       # POST each to /elections/:id/ceremony/complaints");
+            println!("  trustee decrypt <keys.json> <ceremony.json> <tally.json>  # POST /elections/:id/ceremony/partials");
+            return;
+        }
+    };
+
+    match result {
+        Ok(output) => println!("{}", output),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &str) -> Result<T, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid JSON in {}: {}", path, e))
+}
+
+fn keygen() -> String {
+    let auth = SigningKey::generate(&mut OsRng);
+    let share_secret = elgamal::random_scalar(&mut OsRng);
+
+    let keys = TrusteeKeys {
+        auth_secret: hex::encode(auth.to_bytes()),
+        auth_public_key: public_key_hex(&auth),
+        share_secret,
+        share_public_key: elgamal::point_to_hex(&elgamal::public_key(&share_secret)),
+    };
+    serde_json::to_string_pretty(&keys).unwrap()
+}
+
+/// Loads the key file and finds this trustee's index in the ceremony
+fn load(keys_path: &str, transcript_path: &str) -> Result<(TrusteeKeys, SigningKey, u32, Transcript), String> {
+    let keys: TrusteeKeys = read_json(keys_path)?;
+    let transcript: Transcript = read_json(transcript_path)?;
+
+    let seed: [u8; 32] = hex::decode(&keys.auth_secret)
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or("Key file has an invalid auth_secret")?;
+    let signer = SigningKey::from_bytes(&seed);
+
+    let index = transcript
+        .ceremony
+        .trustees
+        .iter()
+        .find(|t| t.auth_public_key == keys.auth_public_key)
+        .map(|t| t.trustee_index)
+        .ok_or("This key file does not belong to a trustee of the ceremony")?;
+
+    Ok((keys, signer, index, transcript))
+}
+
+/// Wraps a payload in the signed submission the API expects
+fn submission<T: Serialize>(
+    signer: &SigningKey,
+    kind: &str,
+    election_id: &Uuid,
+    trustee_index: u32,
+    payload: &T,
+) -> serde_json::Value {
+    let payload_json = serde_json::to_string(payload).unwrap();
+    let message = trustee_message(kind, election_id, trustee_index, &payload_json);
+
+    serde_json::json!({
+        "trustee_index": trustee_index,
+        "payload": payload,
+        "signature": sign_message(signer, &message),
+    })
+}
+
+fn deal(keys_path: &str, transcript_path: &str) -> Result<String, String> {
+    let (_, signer, index, transcript) = load(keys_path, transcript_path)?;
+    let ceremony = &transcript.ceremony;
+
+    let share_keys = ceremony
+        .trustees
+        .iter()
+        .map(|t| {
+            elgamal::point_from_hex(&t.share_public_key)
+                .map(|key| (t.trustee_index, key))
+                .ok_or(format!("Trustee {} has an invalid share key", t.trustee_index))
+        })
+        .collect::<Result<BTreeMap<_, _>, _>>()?;
+
+    let dealing = threshold::deal(&ceremony.proof_context(), ceremony.threshold as usize, &share_keys, &mut OsRng);
+    let output = submission(&signer, "DEALING", &ceremony.election_id, index, &dealing);
+    Ok(serde_json::to_string_pretty(&output).unwrap())
+}
+
+fn dealings(transcript: &Transcript) -> Result<Vec<(u32, Dealing)>, String> {
+    transcript
+        .messages
+        .iter()
+        .filter(|m| m.kind == "DEALING")
+        .map(|m| {
+            serde_json::from_str(&m.payload)
+                .map(|d| (m.trustee_index, d))
+                .map_err(|e| format!("Dealing from trustee {} is malformed: {}", m.trustee_index, e))
+        })
+        .collect()
+}
+
+/// Opens every share dealt to us and prints a complaint for each one that does not match its commitments
+fn check(keys_path: &str, transcript_path: &str) -> Result<String, String> {
+    let (keys, signer, index, transcript) = load(keys_path, transcript_path)?;
+
+    let mut complaints = vec![];
+    for (dealer, dealing) in dealings(&transcript)? {
+        let valid = dealing
+            .encrypted_shares
+            .get(&index)
+            .and_then(|share| threshold::decrypt_share(&keys.share_secret, share))
+            .is_some_and(|share| threshold::verify_share(&dealing.commitments, index, &share));
+
+        if valid {
+            eprintln!("✅ Share from trustee {} matches its commitments.", dealer);
+            continue;
+        }
+
+        eprintln!("⚠️  Share from trustee {} is invalid, complaining.", dealer);
+        let Some(share) = dealing.encrypted_shares.get(&index) else {
+            return Err(format!("Trustee {} dealt no share to us", dealer));
+        };
+        let complaint = threshold::complain(
+            &transcript.ceremony.proof_context(),
+            &keys.share_secret,
+            dealer,
+            share,
+            &mut OsRng,
+        );
+        complaints.push(submission(
+            &signer,
+            "COMPLAINT",
+            &transcript.ceremony.election_id,
+            index,
+            &complaint,
+        ));
+    }
+
+    Ok(serde_json::to_string_pretty(&complaints).unwrap())
+}
+
+/// Rebuilds our key share from the qualified dealings and partially decrypts the sealed tally
+fn decrypt(keys_path: &str, transcript_path: &str, tally_path: &str) -> Result<String, String> {
+    let (keys, signer, index, transcript) = load(keys_path, transcript_path)?;
+    let tally: EncryptedTally = read_json(tally_path)?;
+
+    let disqualified: Vec<u32> = transcript
+        .ceremony
+        .trustees
+        .iter()
+        .filter(|t| t.disqualified)
+        .map(|t| t.trustee_index)
+        .collect();
+
+    let mut key_share = Scalar::ZERO;
+    for (dealer, dealing) in dealings(&transcript)? {
+        if disqualified.contains(&dealer) {
+            continue;
+        }
+        let share = dealing
+            .encrypted_shares
+            .get(&index)
+            .and_then(|share| threshold::decrypt_share(&keys.share_secret, share))
+            .filter(|share| threshold::verify_share(&dealing.commitments, index, share))
+            .ok_or(format!("Share from qualified trustee {} is invalid", dealer))?;
+        key_share += share;
+    }
+
+    let expected = transcript
+        .verification_keys
+        .get(&index)
+        .ok_or("Ceremony is not complete yet")?;
+    if elgamal::point_to_hex(&elgamal::public_key(&key_share)) != *expected {
+        return Err("Key share does not match the published verification key".to_string());
+    }
+
+    let partial = ballot::partial_decrypt_tally(
+        &transcript.ceremony.proof_context(),
+        index,
+        &key_share,
+        &tally.encrypted_tally,
+        &mut OsRng,
+    );
+    let output = submission(
+        &signer,
+        "PARTIAL_DECRYPTION",
+        &transcript.ceremony.election_id,
+        index,
+        &partial,
+    );
+    Ok(serde_json::to_string_pretty(&output).unwrap())
+}
@@ -0,0 +1,339 @@
+//! Signed archive of a sealed election, for courts and observers.
+//!
+//! The archive is one JSON document holding a set of named files, a manifest
+//! with each file's size and SHA-256, and the election signing key's signature
+//! over the manifest. `ElectionArchive::open` re-hashes every file and checks
+//! the signature and the sealed root before handing back the bundle inside.
+
+use super::audit::AuditEntry;
+use super::bundle::{BundleBallot, BundleElection, BundleKey, BundleTally, BundleTrustees, ElectionBundle, BUNDLE_FORMAT};
+use super::{jcs, key_fingerprint, public_key_hex, sign_message, verify_signature, HashSuite, MerkleTree};
+use ed25519_dalek::SigningKey;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+pub const ARCHIVE_FORMAT: &str = "solesigner-election-archive-v1";
+
+pub const ELECTION_FILE: &str = "election.json";
+pub const BALLOTS_FILE: &str = "ballots.json";
+pub const TREE_FILE: &str = "tree.json";
+pub const SPENT_FILE: &str = "spent.json";
+pub const AUDIT_LOG_FILE: &str = "audit_log.json";
+pub const KEYS_FILE: &str = "keys.json";
+pub const TALLY_FILE: &str = "tally.json";
+
+/// The ballot tree as sealed, every level included
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveTree {
+    pub hash_suite: HashSuite,
+    pub merkle_tree_version: i16,
+    pub root: String,
+    pub levels: Vec<Vec<String>>, // levels[0] holds the ballot hashes
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveSpent {
+    pub count: usize, // Voters who cast a ballot
+    pub entries: Vec<String>, // Nullifiers or spent token hashes
+}
+
+/// Admin actions on the election, each signed by the server's audit key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveAuditLog {
+    pub public_key: String,
+    pub entries: Vec<AuditEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveTally {
+    pub tally: Option<BundleTally>,
+    pub trustees: Option<BundleTrustees>, // Only for threshold elections
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestFile {
+    pub name: String,
+    pub size: usize,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format: String,
+    pub election_id: Uuid,
+    pub exported_at: i64, // Unix milliseconds
+    pub files: Vec<ManifestFile>,
+    pub public_key: String, // Election signing key
+    pub key_fingerprint: String,
+}
+
+impl ArchiveManifest {
+    /// The exact bytes covered by the signature: the manifest in RFC 8785 form
+    pub fn message(&self) -> Vec<u8> {
+        let value = serde_json::to_value(self).expect("manifest serializes");
+        format!("solesigner-archive-v1\n{}", jcs::canonicalize(&value)).into_bytes()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElectionArchive {
+    pub manifest: ArchiveManifest,
+    pub signature: String,
+    pub files: BTreeMap<String, String>, // Name -> JSON text, hashed as UTF-8
+}
+
+fn file_digest(content: &str) -> String {
+    hex::encode(Sha256::digest(content.as_bytes()))
+}
+
+fn to_file<T: Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).expect("archive files serialize")
+}
+
+impl ElectionArchive {
+    /// Splits `bundle` into files and signs their manifest with the election key
+    pub fn build(bundle: &ElectionBundle, audit_log: &ArchiveAuditLog, key: &SigningKey) -> Self {
+        let election = &bundle.election;
+        let tree = MerkleTree::new(
+            election.hash_suite,
+            bundle.ballots.iter().map(|b| b.ballot_hash.clone()).collect(),
+        );
+
+        let files: BTreeMap<String, String> = [
+            (ELECTION_FILE, to_file(election)),
+            (BALLOTS_FILE, to_file(&bundle.ballots)),
+            (
+                TREE_FILE,
+                to_file(&ArchiveTree {
+                    hash_suite: election.hash_suite,
+                    merkle_tree_version: election.merkle_tree_version,
+                    root: tree.root,
+                    levels: tree.levels,
+                }),
+            ),
+            (
+                SPENT_FILE,
+                to_file(&ArchiveSpent {
+                    count: bundle.spent_entries.len(),
+                    entries: bundle.spent_entries.clone(),
+                }),
+            ),
+            (AUDIT_LOG_FILE, to_file(audit_log)),
+            (KEYS_FILE, to_file(&bundle.keys)),
+            (
+                TALLY_FILE,
+                to_file(&ArchiveTally {
+                    tally: bundle.tally.clone(),
+                    trustees: bundle.trustees.clone(),
+                }),
+            ),
+        ]
+        .into_iter()
+        .map(|(name, content)| (name.to_string(), content))
+        .collect();
+
+        let public_key = public_key_hex(key);
+        let manifest = ArchiveManifest {
+            format: ARCHIVE_FORMAT.to_string(),
+            election_id: election.id,
+            exported_at: bundle.exported_at,
+            files: files
+                .iter()
+                .map(|(name, content)| ManifestFile {
+                    name: name.clone(),
+                    size: content.len(),
+                    sha256: file_digest(content),
+                })
+                .collect(),
+            key_fingerprint: key_fingerprint(&public_key),
+            public_key,
+        };
+
+        ElectionArchive {
+            signature: sign_message(key, &manifest.message()),
+            manifest,
+            files,
+        }
+    }
+
+    fn file<T: DeserializeOwned>(&self, name: &str) -> Result<T, String> {
+        let content = self.files.get(name).ok_or_else(|| format!("{} is missing", name))?;
+        serde_json::from_str(content).map_err(|e| format!("{} is not valid: {}", name, e))
+    }
+
+    /// Checks the manifest, its signature, every file hash, the sealed root and the
+    /// audit entries, and only then returns the election as a bundle
+    pub fn open(&self) -> Result<(ElectionBundle, ArchiveAuditLog), String> {
+        let manifest = &self.manifest;
+        if manifest.format != ARCHIVE_FORMAT {
+            return Err(format!("expected format {}, got {}", ARCHIVE_FORMAT, manifest.format));
+        }
+        if manifest.key_fingerprint != key_fingerprint(&manifest.public_key) {
+            return Err("manifest key_fingerprint does not match its public key".to_string());
+        }
+        if !verify_signature(&manifest.public_key, &manifest.message(), &self.signature) {
+            return Err("manifest signature is not valid".to_string());
+        }
+
+        // Every file listed, hashed as listed, and nothing unlisted
+        for listed in &manifest.files {
+            let content = self
+                .files
+                .get(&listed.name)
+                .ok_or_else(|| format!("{} is listed but missing", listed.name))?;
+            if content.len() != listed.size || file_digest(content) != listed.sha256 {
+                return Err(format!("{} does not match its manifest hash", listed.name));
+            }
+        }
+        if let Some(extra) = self.files.keys().find(|name| !manifest.files.iter().any(|f| &f.name == *name)) {
+            return Err(format!("{} is not in the manifest", extra));
+        }
+
+        let election: BundleElection = self.file(ELECTION_FILE)?;
+        if election.id != manifest.election_id {
+            return Err(format!("{} is for election {}, the manifest for {}", ELECTION_FILE, election.id, manifest.election_id));
+        }
+        // The archive must be signed by the key that sealed the election
+        if election.root_key_fingerprint.as_deref() != Some(manifest.key_fingerprint.as_str()) {
+            return Err("manifest is not signed by the key that sealed the election".to_string());
+        }
+        let keys: Vec<BundleKey> = self.file(KEYS_FILE)?;
+        if !keys.iter().any(|k| k.fingerprint == manifest.key_fingerprint) {
+            return Err(format!("the signing key is not listed in {}", KEYS_FILE));
+        }
+
+        let ballots: Vec<BundleBallot> = self.file(BALLOTS_FILE)?;
+        let tree: ArchiveTree = self.file(TREE_FILE)?;
+        let rebuilt = MerkleTree::new(
+            election.hash_suite,
+            ballots.iter().map(|b| b.ballot_hash.clone()).collect(),
+        );
+        if rebuilt.root != election.merkle_root || tree.root != election.merkle_root || tree.levels != rebuilt.levels {
+            return Err(format!("the ballots do not rebuild the sealed root {}", election.merkle_root));
+        }
+
+        let audit_log: ArchiveAuditLog = self.file(AUDIT_LOG_FILE)?;
+        if let Some(entry) = audit_log.entries.iter().find(|e| !e.verify(&audit_log.public_key)) {
+            return Err(format!("audit entry {} is not validly signed", entry.sequence));
+        }
+
+        let spent: ArchiveSpent = self.file(SPENT_FILE)?;
+        if spent.count != spent.entries.len() {
+            return Err(format!("{} counts {} entries but lists {}", SPENT_FILE, spent.count, spent.entries.len()));
+        }
+        let tally: ArchiveTally = self.file(TALLY_FILE)?;
+
+        let bundle = ElectionBundle {
+            format: BUNDLE_FORMAT.to_string(),
+            exported_at: manifest.exported_at,
+            election,
+            ballots,
+            spent_entries: spent.entries,
+            keys,
+            tally: tally.tally,
+            trustees: tally.trustees,
+        };
+        Ok((bundle, audit_log))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn bundle(key: &SigningKey) -> ElectionBundle {
+        let suite = HashSuite::default();
+        let ballot_hash = suite.hash_hex("ballot");
+        let public_key = public_key_hex(key);
+        let fingerprint = key_fingerprint(&public_key);
+        ElectionBundle {
+            format: BUNDLE_FORMAT.to_string(),
+            exported_at: 1_700_000_000_000,
+            election: BundleElection {
+                id: Uuid::from_u128(0x5eed),
+                title: "Archive".to_string(),
+                status: "SEALED".to_string(),
+                form_config: json!({ "questions": [] }),
+                start_date: chrono::DateTime::UNIX_EPOCH,
+                end_date: chrono::DateTime::UNIX_EPOCH,
+                access_type: "PUBLIC".to_string(),
+                ballot_scheme: "PLAINTEXT".to_string(),
+                credential_scheme: "NULLIFIER".to_string(),
+                hash_suite: suite,
+                merkle_root: MerkleTree::new(suite, vec![ballot_hash.clone()]).root,
+                merkle_tree_version: 1,
+                proof_version: 2,
+                root_signature: None,
+                root_key_fingerprint: Some(fingerprint.clone()),
+                root_timestamp: None,
+                spent_root: None,
+                spent_count: None,
+                spent_root_signature: None,
+            },
+            ballots: vec![BundleBallot {
+                id: Uuid::from_u128(1),
+                leaf_index: 0,
+                ballot_hash,
+                choices: json!({ "q1": "A" }),
+                proofs: None,
+                created_at: 1_700_000_000_000,
+                receipt_signature: None,
+            }],
+            spent_entries: vec!["nullifier".to_string()],
+            keys: vec![BundleKey {
+                purpose: "SIGNING".to_string(),
+                algorithm: "Ed25519".to_string(),
+                public_key,
+                fingerprint,
+                status: "ACTIVE".to_string(),
+            }],
+            tally: None,
+            trustees: None,
+        }
+    }
+
+    fn archive() -> ElectionArchive {
+        let key = SigningKey::from_bytes(&[3; 32]);
+        let audit_log = ArchiveAuditLog {
+            public_key: public_key_hex(&key),
+            entries: Vec::new(),
+        };
+        ElectionArchive::build(&bundle(&key), &audit_log, &key)
+    }
+
+    #[test]
+    fn a_built_archive_opens() {
+        let (bundle, _) = archive().open().unwrap();
+        assert_eq!(bundle.ballots.len(), 1);
+    }
+
+    #[test]
+    fn an_edited_file_is_rejected() {
+        let mut archive = archive();
+        let ballots = archive.files.get_mut(BALLOTS_FILE).unwrap();
+        *ballots = ballots.replace("\"A\"", "\"B\"");
+        assert_eq!(archive.open().unwrap_err(), "ballots.json does not match its manifest hash");
+
+        // Listing the new hash breaks the manifest signature instead
+        let edited = file_digest(&archive.files[BALLOTS_FILE]);
+        archive.manifest.files.iter_mut().find(|f| f.name == BALLOTS_FILE).unwrap().sha256 = edited;
+        assert_eq!(archive.open().unwrap_err(), "manifest signature is not valid");
+    }
+
+    #[test]
+    fn a_manifest_signed_by_another_key_is_rejected() {
+        let mut archive = archive();
+        let other = SigningKey::from_bytes(&[4; 32]);
+        archive.signature = sign_message(&other, &archive.manifest.message());
+        assert_eq!(archive.open().unwrap_err(), "manifest signature is not valid");
+
+        // Naming the other key in the manifest does not make it the sealing key
+        archive.manifest.public_key = public_key_hex(&other);
+        archive.manifest.key_fingerprint = key_fingerprint(&archive.manifest.public_key);
+        archive.signature = sign_message(&other, &archive.manifest.message());
+        assert_eq!(archive.open().unwrap_err(), "manifest is not signed by the key that sealed the election");
+    }
+}
@@ -0,0 +1,191 @@
+//! Hash-chained, signed log of admin actions.
+//!
+//! Entry n commits to entry n-1 through `prev_hash`, and its own `entry_hash` is
+//! SHA256 over `message()`, which the server's audit key also signs. Editing,
+//! dropping or reordering any past entry breaks every link after it.
+
+use super::{key_fingerprint, public_key_hex, sign_message, verify_signature};
+use ed25519_dalek::SigningKey;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// `prev_hash` of the first entry
+pub const AUDIT_GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub sequence: i64, // 1-based, no gaps
+    pub admin_id: Option<Uuid>,
+    pub action: String,
+    pub payload: Value,
+    pub created_at: i64, // Unix milliseconds
+    pub prev_hash: String,
+    pub entry_hash: String,
+    pub key_fingerprint: String,
+    pub signature: String, // Ed25519 over message()
+}
+
+impl AuditEntry {
+    pub fn sign(
+        key: &SigningKey,
+        sequence: i64,
+        prev_hash: String,
+        admin_id: Option<Uuid>,
+        action: &str,
+        payload: Value,
+        created_at: i64,
+    ) -> Self {
+        let mut entry = AuditEntry {
+            sequence,
+            admin_id,
+            action: action.to_string(),
+            payload,
+            created_at,
+            prev_hash,
+            entry_hash: String::new(),
+            key_fingerprint: key_fingerprint(&public_key_hex(key)),
+            signature: String::new(),
+        };
+        let message = entry.message();
+        entry.entry_hash = hex::encode(Sha256::digest(&message));
+        entry.signature = sign_message(key, &message);
+        entry
+    }
+
+    /// The exact bytes hashed and signed. The payload is serialized by serde_json,
+    /// which writes object keys in sorted order, so it survives a JSONB round trip.
+    pub fn message(&self) -> Vec<u8> {
+        format!(
+            "solesigner-audit-v1\n{}\n{}\n{}\n{}\n{}\n{}\n{}",
+            self.sequence,
+            self.prev_hash,
+            self.admin_id.map(|id| id.to_string()).unwrap_or_default(),
+            self.action,
+            self.created_at,
+            self.key_fingerprint,
+            self.payload
+        )
+        .into_bytes()
+    }
+
+    /// Checks `entry_hash` and the signature, without the chain around the entry
+    pub fn verify(&self, public_key: &str) -> bool {
+        let message = self.message();
+        self.entry_hash == hex::encode(Sha256::digest(&message))
+            && self.key_fingerprint == key_fingerprint(public_key)
+            && verify_signature(public_key, &message, &self.signature)
+    }
+}
+
+/// Outcome of walking the chain from the genesis entry
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditChainVerification {
+    pub valid: bool,
+    pub length: usize,
+    pub head_hash: String, // `entry_hash` of the last entry, or the genesis hash for an empty log
+    pub first_invalid: Option<i64>, // sequence of the first entry that fails
+    pub error: Option<String>,
+}
+
+/// Checks that `entries`, oldest first, form an unbroken chain signed by `public_key`
+pub fn verify_audit_chain(entries: &[AuditEntry], public_key: &str) -> AuditChainVerification {
+    let fingerprint = key_fingerprint(public_key);
+    let mut prev_hash = AUDIT_GENESIS_HASH.to_string();
+
+    for (i, entry) in entries.iter().enumerate() {
+        let message = entry.message();
+        let error = if entry.sequence != i as i64 + 1 {
+            Some(format!("expected sequence {}", i + 1))
+        } else if entry.prev_hash != prev_hash {
+            Some("prev_hash does not match the previous entry".to_string())
+        } else if entry.entry_hash != hex::encode(Sha256::digest(&message)) {
+            Some("entry_hash does not match the entry".to_string())
+        } else if entry.key_fingerprint != fingerprint {
+            Some("signed with a different key".to_string())
+        } else if !verify_signature(public_key, &message, &entry.signature) {
+            Some("signature is not valid".to_string())
+        } else {
+            None
+        };
+
+        if let Some(error) = error {
+            return AuditChainVerification {
+                valid: false,
+                length: entries.len(),
+                head_hash: prev_hash,
+                first_invalid: Some(entry.sequence),
+                error: Some(error),
+            };
+        }
+        prev_hash = entry.entry_hash.clone();
+    }
+
+    AuditChainVerification {
+        valid: true,
+        length: entries.len(),
+        head_hash: prev_hash,
+        first_invalid: None,
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chain(key: &SigningKey, actions: &[&str]) -> Vec<AuditEntry> {
+        let mut entries: Vec<AuditEntry> = Vec::new();
+        for (i, action) in actions.iter().enumerate() {
+            let prev_hash = entries.last().map_or(AUDIT_GENESIS_HASH.to_string(), |e| e.entry_hash.clone());
+            let payload = json!({ "election_id": Uuid::from_u128(i as u128) });
+            let admin_id = Some(Uuid::nil());
+            entries.push(AuditEntry::sign(key, i as i64 + 1, prev_hash, admin_id, action, payload, 1_700_000_000_000));
+        }
+        entries
+    }
+
+    fn setup() -> (String, Vec<AuditEntry>) {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        (public_key_hex(&key), chain(&key, &["CREATE_ELECTION", "START_ELECTION", "CLOSE_ELECTION"]))
+    }
+
+    #[test]
+    fn an_untouched_chain_verifies() {
+        let (public_key, entries) = setup();
+        let verification = verify_audit_chain(&entries, &public_key);
+        assert!(verification.valid);
+        assert_eq!(verification.head_hash, entries[2].entry_hash);
+    }
+
+    #[test]
+    fn an_edited_entry_breaks_the_chain() {
+        let (public_key, mut entries) = setup();
+        entries[1].payload = json!({ "election_id": Uuid::nil() });
+        assert_eq!(verify_audit_chain(&entries, &public_key).first_invalid, Some(2));
+    }
+
+    #[test]
+    fn a_dropped_entry_breaks_the_chain() {
+        let (public_key, mut entries) = setup();
+        entries.remove(1);
+        assert!(!verify_audit_chain(&entries, &public_key).valid);
+
+        // Renumbering what is left does not hide the gap
+        entries[1].sequence = 2;
+        assert_eq!(verify_audit_chain(&entries, &public_key).first_invalid, Some(2));
+    }
+
+    #[test]
+    fn reordered_entries_break_the_chain() {
+        let (public_key, mut entries) = setup();
+        entries.swap(1, 2);
+        assert!(!verify_audit_chain(&entries, &public_key).valid);
+
+        entries[1].sequence = 2;
+        entries[2].sequence = 3;
+        assert_eq!(verify_audit_chain(&entries, &public_key).first_invalid, Some(2));
+    }
+}
@@ -0,0 +1,413 @@
+//! Sealed-election export and its offline audit.
+//!
+//! A bundle carries everything the public audit endpoints serve for one sealed
+//! election, so `audit_bundle` can re-derive the sealed roots, the tally and the
+//! voter count without a database and report whether they match what was signed.
+
+use super::ballot::{self, EncryptedBallot};
+use super::elgamal::ProofContext;
+use super::smt::SparseMerkleTree;
+use super::{
+    ballot_hash, elgamal, key_fingerprint, receipt_message, sealed_root_message, spent_root_message,
+    timestamp, verify_signature, HashSuite, MerkleTree,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+pub const BUNDLE_FORMAT: &str = "solesigner-election-bundle-v1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElectionBundle {
+    pub format: String,
+    pub exported_at: i64, // Unix milliseconds
+    pub election: BundleElection,
+    pub ballots: Vec<BundleBallot>, // In leaf order
+    pub spent_entries: Vec<String>, // Nullifiers or spent token hashes
+    pub keys: Vec<BundleKey>,
+    pub tally: Option<BundleTally>,
+    pub trustees: Option<BundleTrustees>, // Only for threshold elections
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleElection {
+    pub id: Uuid,
+    pub title: String,
+    pub status: String,
+    pub form_config: Value,
+    pub start_date: chrono::DateTime<chrono::Utc>,
+    pub end_date: chrono::DateTime<chrono::Utc>,
+    pub access_type: String,
+    pub ballot_scheme: String,
+    pub credential_scheme: String,
+    pub hash_suite: HashSuite,
+    pub merkle_root: String,
+    pub merkle_tree_version: i16,
+    #[serde(default = "legacy_proof_version")]
+    pub proof_version: i16, // Missing means the bundle predates proof versions
+    pub root_signature: Option<String>,
+    pub root_key_fingerprint: Option<String>,
+    pub root_timestamp: Option<String>, // hex RFC 3161 token
+    pub spent_root: Option<String>,
+    pub spent_count: Option<i64>,
+    pub spent_root_signature: Option<String>,
+}
+
+fn legacy_proof_version() -> i16 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleBallot {
+    pub id: Uuid,
+    pub leaf_index: i64,
+    pub ballot_hash: String,
+    pub choices: Value,
+    pub proofs: Option<Value>,
+    pub created_at: i64, // Unix milliseconds, the timestamp of the sealed receipt
+    pub receipt_signature: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleKey {
+    pub purpose: String,
+    pub algorithm: String,
+    pub public_key: String,
+    pub fingerprint: String,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleTally {
+    pub ballot_count: i64,
+    pub encrypted_tally: Value,
+    pub decrypted_tally: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleTrustees {
+    pub threshold: u32,
+    /// Trustee index -> public verification key of its decryption share
+    pub verification_keys: BTreeMap<u32, String>,
+}
+
+/// Outcome of one audit step
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleCheck {
+    pub name: &'static str,
+    pub valid: bool,
+    pub verified: bool, // False when there was nothing to check against; such a step does not fail the audit
+    pub detail: String,
+}
+
+/// Machine-readable result of auditing a bundle
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleAudit {
+    pub election_id: Uuid,
+    pub hash_suite: HashSuite,
+    pub valid: bool,
+    pub ballot_count: usize,
+    pub spent_count: usize,
+    pub merkle_root: String, // Recomputed from the ballots
+    pub spent_root: String,  // Recomputed from the spent entries
+    pub totals: Option<BTreeMap<String, BTreeMap<String, u64>>>,
+    pub checks: Vec<BundleCheck>,
+}
+
+struct Checks(Vec<BundleCheck>);
+
+impl Checks {
+    fn record(&mut self, name: &'static str, result: Result<String, String>) {
+        let (valid, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(detail) => (false, detail),
+        };
+        self.0.push(BundleCheck { name, valid, verified: true, detail });
+    }
+
+    fn unverified(&mut self, name: &'static str, detail: String) {
+        self.0.push(BundleCheck { name, valid: true, verified: false, detail });
+    }
+}
+
+/// Re-runs every check the bundle allows. `tsa_public_key` (hex SPKI) pins the
+/// timestamp authority; without it a root timestamp is reported as unverified.
+pub fn audit_bundle(bundle: &ElectionBundle, tsa_public_key: Option<&str>) -> BundleAudit {
+    let election = &bundle.election;
+    let suite = election.hash_suite;
+    let mut checks = Checks(Vec::new());
+
+    checks.record(
+        "format",
+        if bundle.format == BUNDLE_FORMAT {
+            Ok(bundle.format.clone())
+        } else {
+            Err(format!("expected {}, got {}", BUNDLE_FORMAT, bundle.format))
+        },
+    );
+
+    // 1. Every ballot hashes to its tracker and the log has no gaps
+    let mismatched: Vec<String> = bundle
+        .ballots
+        .iter()
+        .enumerate()
+        .filter(|(i, b)| b.leaf_index != *i as i64 || ballot_hash(suite, &b.id, &b.choices) != b.ballot_hash)
+        .map(|(i, _)| i.to_string())
+        .collect();
+    checks.record(
+        "ballot_hashes",
+        if mismatched.is_empty() {
+            Ok(format!("{} ballots in leaf order", bundle.ballots.len()))
+        } else {
+            Err(format!("ballots at positions {} are out of order or do not match their hash", mismatched.join(", ")))
+        },
+    );
+
+    // 2. The tree over them ends at the sealed root
+    let tree = MerkleTree::new(suite, bundle.ballots.iter().map(|b| b.ballot_hash.clone()).collect());
+    checks.record(
+        "merkle_root",
+        if tree.root == election.merkle_root {
+            Ok(tree.root.clone())
+        } else {
+            Err(format!("rebuilt root {} differs from sealed root {}", tree.root, election.merkle_root))
+        },
+    );
+
+    // 3. Signatures by the key that sealed the election
+    let signing_key = election.root_key_fingerprint.as_ref().and_then(|fingerprint| {
+        bundle.keys.iter().find(|k| {
+            k.purpose == "SIGNING" && &k.fingerprint == fingerprint && key_fingerprint(&k.public_key) == *fingerprint
+        })
+    });
+    checks.record("root_signature", match (signing_key, &election.root_signature) {
+        (Some(key), Some(signature)) => {
+            let message = sealed_root_message(&election.id, &election.merkle_root, election.merkle_tree_version);
+            if verify_signature(&key.public_key, &message, signature) {
+                Ok(format!("signed by {}", key.fingerprint))
+            } else {
+                Err("signature does not match the sealed root".to_string())
+            }
+        }
+        (None, _) => Err("the sealing key is not in the bundle".to_string()),
+        (_, None) => Err("the sealed root is not signed".to_string()),
+    });
+
+    let receipts_signed = bundle.ballots.iter().filter(|b| b.receipt_signature.is_some()).count();
+    let bad_receipts = bundle
+        .ballots
+        .iter()
+        .filter(|b| match (signing_key, &b.receipt_signature) {
+            (Some(key), Some(signature)) => !verify_signature(
+                &key.public_key,
+                &receipt_message(&election.id, &b.ballot_hash, b.created_at),
+                signature,
+            ),
+            (None, Some(_)) => true,
+            (_, None) => false,
+        })
+        .count();
+    checks.record(
+        "receipt_signatures",
+        if bad_receipts == 0 {
+            Ok(format!("{} of {} ballots carry a valid sealed receipt", receipts_signed, bundle.ballots.len()))
+        } else {
+            Err(format!("{} sealed receipts do not verify", bad_receipts))
+        },
+    );
+
+    if let Some(token) = &election.root_timestamp {
+        let imprint = timestamp::root_imprint(&sealed_root_message(
+            &election.id,
+            &election.merkle_root,
+            election.merkle_tree_version,
+        ));
+        match tsa_public_key {
+            Some(tsa_public_key) => {
+                let verified = hex::decode(token)
+                    .map_err(|_| "token is not hex".to_string())
+                    .and_then(|token| timestamp::verify_token(&token, &imprint, tsa_public_key));
+                checks.record(
+                    "root_timestamp",
+                    verified.map(|info| format!("{} (serial {})", info.gen_time.to_rfc3339(), info.serial)),
+                );
+            }
+            None => checks.unverified("root_timestamp", "no TSA public key to check the token against".to_string()),
+        }
+    }
+
+    // 4. The spent set matches its sealed root, and one credential was spent per ballot
+    let spent = SparseMerkleTree::new(suite, bundle.spent_entries.clone());
+    checks.record("spent_root", match (&election.spent_root, election.spent_count) {
+        (Some(root), Some(count)) if *root == spent.root && count == spent.size() as i64 => {
+            match (signing_key, &election.spent_root_signature) {
+                (Some(key), Some(signature))
+                    if verify_signature(&key.public_key, &spent_root_message(&election.id, root, count), signature) =>
+                {
+                    Ok(format!("{} entries, signed", count))
+                }
+                _ => Err("spent root signature does not verify".to_string()),
+            }
+        }
+        (Some(root), count) => Err(format!(
+            "rebuilt {} over {} entries, sealed {} over {}",
+            spent.root,
+            spent.size(),
+            root,
+            count.map_or("an unknown number of".to_string(), |count| count.to_string())
+        )),
+        (None, _) => Err("no sealed spent root".to_string()),
+    });
+    checks.record(
+        "ballot_count",
+        if spent.size() == bundle.ballots.len() {
+            Ok(format!("{} ballots, {} spent credentials", bundle.ballots.len(), spent.size()))
+        } else {
+            Err(format!("{} ballots but {} spent credentials", bundle.ballots.len(), spent.size()))
+        },
+    );
+
+    // 5. The tally follows from the ballots
+    let totals = if election.ballot_scheme == "PLAINTEXT" {
+        let totals = plaintext_totals(&bundle.ballots);
+        checks.record("tally", Ok("counted from plaintext ballots".to_string()));
+        Some(totals)
+    } else {
+        audit_encrypted_tally(bundle, &mut checks)
+    };
+
+    let valid = checks.0.iter().all(|check| check.valid);
+    BundleAudit {
+        election_id: election.id,
+        hash_suite: suite,
+        valid,
+        ballot_count: bundle.ballots.len(),
+        spent_count: spent.size(),
+        merkle_root: tree.root,
+        spent_root: spent.root,
+        totals,
+        checks: checks.0,
+    }
+}
+
+/// Question id -> answer -> count, for ballots stored in the clear
+fn plaintext_totals(ballots: &[BundleBallot]) -> BTreeMap<String, BTreeMap<String, u64>> {
+    let mut totals: BTreeMap<String, BTreeMap<String, u64>> = BTreeMap::new();
+    for ballot in ballots {
+        let Some(answers) = ballot.choices.as_object() else {
+            continue;
+        };
+        for (question_id, answer) in answers {
+            let chosen: Vec<&str> = match answer {
+                Value::Array(items) => items.iter().filter_map(Value::as_str).collect(),
+                other => other.as_str().into_iter().collect(),
+            };
+            for option in chosen {
+                *totals
+                    .entry(question_id.clone())
+                    .or_default()
+                    .entry(option.to_string())
+                    .or_default() += 1;
+            }
+        }
+    }
+    totals
+}
+
+/// Verifies every ballot proof, re-aggregates the ciphertexts and checks the
+/// published decryption against them
+fn audit_encrypted_tally(
+    bundle: &ElectionBundle,
+    checks: &mut Checks,
+) -> Option<BTreeMap<String, BTreeMap<String, u64>>> {
+    let layout = ballot::ballot_layout(&bundle.election.form_config);
+    let context = ProofContext::for_version(bundle.election.id, bundle.election.proof_version);
+    let public_key = bundle
+        .keys
+        .iter()
+        .find(|k| k.purpose == "ENCRYPTION")
+        .and_then(|k| elgamal::point_from_hex(&k.public_key));
+    let Some(public_key) = public_key else {
+        checks.record("ballot_proofs", Err("the encryption key is not in the bundle".to_string()));
+        return None;
+    };
+
+    // Aggregated like the sealing job does: every ballot that parses, proven or not
+    let mut parsed: Vec<EncryptedBallot> = Vec::new();
+    let mut invalid = Vec::new();
+    for b in &bundle.ballots {
+        let verified = ballot::parse_ballot(&b.choices, &layout).and_then(|encrypted| {
+            parsed.push(encrypted.clone());
+            let proofs = b.proofs.as_ref().ok_or("no proofs".to_string())?;
+            let proofs = ballot::parse_proofs(proofs).map_err(|e| e.to_string())?;
+            ballot::verify_ballot(&context, &public_key, &encrypted, &proofs, &layout).map_err(|e| e.to_string())
+        });
+        if let Err(e) = verified {
+            invalid.push(format!("{}: {}", b.leaf_index, e));
+        }
+    }
+    checks.record(
+        "ballot_proofs",
+        if invalid.is_empty() {
+            Ok(format!("{} ballots proven well-formed", parsed.len()))
+        } else {
+            Err(invalid.join("; "))
+        },
+    );
+
+    let aggregate = ballot::aggregate(&parsed, &layout);
+    let Some(tally) = &bundle.tally else {
+        checks.record("tally", Err("the bundle has no tally".to_string()));
+        return None;
+    };
+    let published: Option<EncryptedBallot> = serde_json::from_value(tally.encrypted_tally.clone()).ok();
+    if published.as_ref() != Some(&aggregate) || tally.ballot_count != parsed.len() as i64 {
+        checks.record("tally", Err("the encrypted tally does not match the ballots".to_string()));
+        return None;
+    }
+
+    let Some(decrypted) = tally
+        .decrypted_tally
+        .as_ref()
+        .and_then(|t| serde_json::from_value::<ballot::DecryptedTally>(t.clone()).ok())
+    else {
+        checks.record("tally", Ok("encrypted tally matches; not decrypted yet".to_string()));
+        return None;
+    };
+
+    let same_ciphertexts = decrypted.len() == aggregate.len()
+        && aggregate.iter().all(|(question_id, totals)| {
+            decrypted.get(question_id).is_some_and(|entries| {
+                entries.len() == totals.len()
+                    && totals
+                        .iter()
+                        .all(|(option, ct)| entries.get(option).is_some_and(|e| e.ciphertext == *ct))
+            })
+        });
+    let proven = match &bundle.trustees {
+        Some(trustees) => {
+            let verification_keys: Option<BTreeMap<u32, _>> = trustees
+                .verification_keys
+                .iter()
+                .map(|(index, key)| elgamal::point_from_hex(key).map(|key| (*index, key)))
+                .collect();
+            verification_keys.is_some_and(|keys| {
+                ballot::verify_threshold_tally(&context, &keys, trustees.threshold as usize, &decrypted)
+            })
+        }
+        None => ballot::verify_tally(&context, &public_key, &decrypted),
+    };
+    checks.record(
+        "tally",
+        if same_ciphertexts && proven {
+            Ok("encrypted tally matches and every decryption is proven".to_string())
+        } else if !same_ciphertexts {
+            Err("the decrypted tally is not over the aggregated ballots".to_string())
+        } else {
+            Err("a decryption proof does not verify".to_string())
+        },
+    );
+
+    Some(ballot::tally_counts(&decrypted))
+}
@@ -1,7 +1,55 @@
+pub mod archive;
+pub mod audit;
+pub mod bundle;
+pub mod smt;
+pub mod timestamp;
+pub mod whitelist;
+
+use ed25519_dalek::{Signer, SigningKey};
 use sha2::{Digest, Sha256};
-use hex;
+use uuid::Uuid;
+
+// Everything a voter or auditor can check lives in `solesigner-verify`, so the
+// server, the CLI and the browser run the same code.
+pub use solesigner_verify::{
+    ballot, ballot_hash, commitment_message, credential, elgamal, hash, jcs, key_fingerprint, merkle, receipt_message,
+    sealed_root_message, spent_root_message, threshold, trustee_message, verify_signature, HashSuite,
+    SignedTreeHead,
+};
+pub use solesigner_verify::merkle::{
+    append_leaf, frontier, hash_leaf, hash_node, verify_consistency, verify_multiproof, verify_proof,
+    ConsistencyProof, MerkleMultiproof, MerkleProof, MerkleTree, NodeId, ProofStep, Side, StoredTree,
+    MERKLE_TREE_VERSION,
+};
+
+/// `elections.nullifier_scheme` for the original `generate_nullifier` hashes
+pub const LEGACY_NULLIFIER_SCHEME: &str = "SHA256-SALT";
+
+/// Server-held secret that nullifiers are derived under (the `NULLIFIER_SECRET` setting).
+/// Document numbers are low-entropy, so a nullifier anyone could recompute from
+/// public data would reveal who voted.
+pub struct NullifierKey([u8; 32]);
+
+impl NullifierKey {
+    pub fn from_hex(hex_key: &str) -> Option<Self> {
+        let bytes: [u8; 32] = hex::decode(hex_key.trim()).ok()?.try_into().ok()?;
+        Some(NullifierKey(bytes))
+    }
+
+    pub fn generate() -> Self {
+        NullifierKey(rand::random())
+    }
+
+    /// Keyed hash over the election and document under the election's suite
+    /// (`HashSuite::nullifier_scheme` names it), hex encoded
+    pub fn nullifier(&self, suite: HashSuite, election_id: &Uuid, document: &str) -> String {
+        let message = format!("solesigner-nullifier-v1\n{}\n{}", election_id, document);
+        hex::encode(suite.mac(&self.0, message.as_bytes()))
+    }
+}
 
-/// Generates a unique nullifier: SHA256(Document + Election_Salt)
+/// Legacy nullifier: SHA256(Document + Election_Salt). Only used by elections
+/// that registered voters before nullifiers were keyed.
 pub fn generate_nullifier(document: &str, election_salt: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(document.as_bytes());
@@ -9,74 +57,42 @@ pub fn generate_nullifier(document: &str, election_salt: &str) -> String {
     hex::encode(hasher.finalize())
 }
 
-/// Computes a standard SHA256 hash of the input
+/// Computes a standard SHA256 hash of the input. Per-election data is hashed
+/// with the election's `HashSuite` instead.
 pub fn hash_data(data: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(data.as_bytes());
     hex::encode(hasher.finalize())
 }
 
-// Basic Merkle Tree Implementation
-#[derive(Debug, Clone)]
-pub struct MerkleTree {
-    pub leaves: Vec<String>,
-    pub root: String,
-    pub levels: Vec<Vec<String>>,
-}
-
-impl MerkleTree {
-    pub fn new(leaves: Vec<String>) -> Self {
-        if leaves.is_empty() {
-             return MerkleTree {
-                leaves: vec![],
-                root: String::new(),
-                levels: vec![],
-            };
-        }
-        
-        let mut current_level = leaves.clone();
-        let mut levels = vec![current_level.clone()];
-
-        while current_level.len() > 1 {
-            let mut next_level = Vec::new();
-            
-            for chunk in current_level.chunks(2) {
-                let left = &chunk[0];
-                let right = if chunk.len() > 1 { &chunk[1] } else { &chunk[0] }; // Duplicate if odd
-                
-                let combined = format!("{}{}", left, right);
-                next_level.push(hash_data(&combined));
-            }
-            
-            levels.push(next_level.clone());
-            current_level = next_level;
-        }
+// --- Signatures ---
 
-        MerkleTree {
-            leaves,
-            root: current_level[0].clone(),
-            levels,
-        }
-    }
+/// Signs `message` with Ed25519 and returns the hex-encoded signature
+pub fn sign_message(key: &SigningKey, message: &[u8]) -> String {
+    hex::encode(key.sign(message).to_bytes())
+}
 
-    pub fn get_proof(&self, index: usize) -> Vec<String> {
-        let mut proof = Vec::new();
-        let mut current_index = index;
+/// Hex-encoded Ed25519 public key for `key`
+pub fn public_key_hex(key: &SigningKey) -> String {
+    hex::encode(key.verifying_key().to_bytes())
+}
 
-        // Skip the root level (last level)
-        for level in self.levels.iter().take(self.levels.len() - 1) {
-             let is_left = current_index % 2 == 0;
-             let pair_index = if is_left { current_index + 1 } else { current_index - 1 };
-             
-             if pair_index < level.len() {
-                 proof.push(level[pair_index].clone());
-             } else {
-                 // Even number at end, pair is itself
-                 proof.push(level[current_index].clone());
-             }
-             
-             current_index /= 2;
-        }
-        proof
-    }
+/// Signs a ballot log root with the election key
+pub fn sign_tree_head(
+    key: &SigningKey,
+    election_id: Uuid,
+    tree_size: usize,
+    root_hash: String,
+    timestamp: i64,
+) -> SignedTreeHead {
+    let mut head = SignedTreeHead {
+        election_id,
+        tree_size,
+        root_hash,
+        timestamp,
+        key_fingerprint: key_fingerprint(&public_key_hex(key)),
+        signature: String::new(),
+    };
+    head.signature = sign_message(key, &head.message());
+    head
 }
@@ -0,0 +1,200 @@
+//! Sparse Merkle tree over a set of spent credentials.
+//!
+//! Every entry sits at the leaf addressed by the 256 bits of H(entry), most
+//! significant bit first, with 0 going left, where H is the election's hash
+//! suite. Present leaves hash to H(0x00 || key), absent leaves are 32 zero
+//! bytes, and interior nodes are H(0x01 || left || right). Because every possible key has a fixed place,
+//! the same proof format shows both that an entry is in the set and that it is not.
+
+use super::HashSuite;
+use serde::{Deserialize, Serialize};
+
+pub const SMT_DEPTH: usize = 256;
+
+type Hash = [u8; 32];
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Where `entry` lives in the tree
+pub fn smt_key(suite: HashSuite, entry: &str) -> Hash {
+    suite.digest(&[entry.as_bytes()])
+}
+
+fn bit(key: &Hash, depth: usize) -> u8 {
+    (key[depth / 8] >> (7 - depth % 8)) & 1
+}
+
+fn leaf_hash(suite: HashSuite, key: &Hash) -> Hash {
+    suite.digest(&[&[LEAF_PREFIX], key])
+}
+
+fn node_hash(suite: HashSuite, left: &Hash, right: &Hash) -> Hash {
+    suite.digest(&[&[NODE_PREFIX], left, right])
+}
+
+/// `empty[h]` is the root of an empty subtree of height `h`
+fn empty_hashes(suite: HashSuite) -> Vec<Hash> {
+    let mut empty = vec![[0u8; 32]];
+    for h in 0..SMT_DEPTH {
+        empty.push(node_hash(suite, &empty[h], &empty[h]));
+    }
+    empty
+}
+
+/// Membership or non-membership proof for one entry. Only siblings that are not
+/// empty subtrees are listed; `bitmap` marks which depths they belong to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SparseProof {
+    pub key: String, // hex smt_key(suite, entry)
+    pub included: bool,
+    pub bitmap: String, // hex, bit d (most significant first) set when the sibling at depth d is listed
+    pub siblings: Vec<String>, // root to leaf
+}
+
+#[derive(Debug, Clone)]
+pub struct SparseMerkleTree {
+    suite: HashSuite,
+    keys: Vec<Hash>, // sorted, deduplicated
+    empty: Vec<Hash>,
+    pub root: String,
+}
+
+impl SparseMerkleTree {
+    pub fn new<I: IntoIterator<Item = S>, S: AsRef<str>>(suite: HashSuite, entries: I) -> Self {
+        let mut keys: Vec<Hash> = entries.into_iter().map(|e| smt_key(suite, e.as_ref())).collect();
+        keys.sort_unstable();
+        keys.dedup();
+
+        let mut tree = SparseMerkleTree {
+            suite,
+            keys,
+            empty: empty_hashes(suite),
+            root: String::new(),
+        };
+        tree.root = hex::encode(tree.subtree(&tree.keys, 0));
+        tree
+    }
+
+    pub fn size(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Root of the subtree at `depth` holding exactly `keys`, which share their first `depth` bits
+    fn subtree(&self, keys: &[Hash], depth: usize) -> Hash {
+        if keys.is_empty() {
+            return self.empty[SMT_DEPTH - depth];
+        }
+        if depth == SMT_DEPTH {
+            return leaf_hash(self.suite, &keys[0]);
+        }
+        let split = keys.partition_point(|k| bit(k, depth) == 0);
+        node_hash(
+            self.suite,
+            &self.subtree(&keys[..split], depth + 1),
+            &self.subtree(&keys[split..], depth + 1),
+        )
+    }
+
+    pub fn prove(&self, entry: &str) -> SparseProof {
+        let key = smt_key(self.suite, entry);
+        let mut bitmap = [0u8; SMT_DEPTH / 8];
+        let mut siblings = Vec::new();
+
+        let mut keys = &self.keys[..];
+        for depth in 0..SMT_DEPTH {
+            let split = keys.partition_point(|k| bit(k, depth) == 0);
+            let (left, right) = keys.split_at(split);
+            let (path, sibling) = if bit(&key, depth) == 0 { (left, right) } else { (right, left) };
+
+            if !sibling.is_empty() {
+                bitmap[depth / 8] |= 1 << (7 - depth % 8);
+                siblings.push(hex::encode(self.subtree(sibling, depth + 1)));
+            }
+            keys = path;
+        }
+
+        SparseProof {
+            key: hex::encode(key),
+            included: keys.first() == Some(&key),
+            bitmap: hex::encode(bitmap),
+            siblings,
+        }
+    }
+}
+
+/// Checks that `entry` is (or, if `proof.included` is false, is not) in the set with this root
+pub fn verify_sparse_proof(suite: HashSuite, root: &str, entry: &str, proof: &SparseProof) -> bool {
+    let key = smt_key(suite, entry);
+    if proof.key != hex::encode(key) {
+        return false;
+    }
+    let bitmap = match hex::decode(&proof.bitmap) {
+        Ok(bitmap) if bitmap.len() == SMT_DEPTH / 8 => bitmap,
+        _ => return false,
+    };
+    let listed = (0..SMT_DEPTH).filter(|d| (bitmap[d / 8] >> (7 - d % 8)) & 1 == 1).count();
+    if listed != proof.siblings.len() {
+        return false;
+    }
+
+    let empty = empty_hashes(suite);
+    let mut siblings = proof.siblings.iter().rev();
+    let mut hash = if proof.included { leaf_hash(suite, &key) } else { empty[0] };
+
+    for depth in (0..SMT_DEPTH).rev() {
+        let sibling = if (bitmap[depth / 8] >> (7 - depth % 8)) & 1 == 1 {
+            match siblings.next().and_then(|s| hex::decode(s).ok()).and_then(|s| s.try_into().ok()) {
+                Some(sibling) => sibling,
+                None => return false,
+            }
+        } else {
+            empty[SMT_DEPTH - depth - 1]
+        };
+        hash = if bit(&key, depth) == 0 {
+            node_hash(suite, &hash, &sibling)
+        } else {
+            node_hash(suite, &sibling, &hash)
+        };
+    }
+
+    hex::encode(hash) == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUITE: HashSuite = HashSuite::Sha256;
+
+    #[test]
+    fn spent_and_unspent_credentials_prove_against_their_own_root_only() {
+        let spent = SparseMerkleTree::new(SUITE, ["token-1", "token-2", "token-3"]);
+        // The same set once "token-4" is spent too
+        let later = SparseMerkleTree::new(SUITE, ["token-1", "token-2", "token-3", "token-4"]);
+
+        let membership = spent.prove("token-2");
+        assert!(membership.included);
+        assert!(verify_sparse_proof(SUITE, &spent.root, "token-2", &membership));
+        assert!(!verify_sparse_proof(SUITE, &later.root, "token-2", &membership));
+
+        let non_membership = spent.prove("token-4");
+        assert!(!non_membership.included);
+        assert!(verify_sparse_proof(SUITE, &spent.root, "token-4", &non_membership));
+        assert!(!verify_sparse_proof(SUITE, &later.root, "token-4", &non_membership));
+
+        // And the later root's proofs fail against the earlier one
+        assert!(!verify_sparse_proof(SUITE, &spent.root, "token-2", &later.prove("token-2")));
+        assert!(!verify_sparse_proof(SUITE, &spent.root, "token-4", &later.prove("token-4")));
+    }
+
+    #[test]
+    fn a_proof_cannot_claim_the_opposite_answer() {
+        let tree = SparseMerkleTree::new(SUITE, ["token-1", "token-2"]);
+        for entry in ["token-1", "token-9"] {
+            let mut proof = tree.prove(entry);
+            proof.included = !proof.included;
+            assert!(!verify_sparse_proof(SUITE, &tree.root, entry, &proof));
+        }
+    }
+}
@@ -0,0 +1,463 @@
+//! RFC 3161 trusted timestamps over sealed roots.
+//!
+//! The message imprint is SHA-256 of the bytes the root signature covers
+//! (`sealed_root_message`), so a token pins the election, the root and the tree
+//! version to a time vouched for by the TSA. Tokens signed with RSA PKCS#1 v1.5
+//! or Ed25519 (RFC 8419) can be verified. `issue` is the bundled local TSA.
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use cms::content_info::{CmsVersion, ContentInfo};
+use cms::signed_data::{
+    EncapsulatedContentInfo, SignedData, SignerIdentifier, SignerInfo, SignerInfos,
+};
+use der::asn1::{GeneralizedTime, ObjectIdentifier, OctetString, SetOfVec, Uint};
+use der::oid::db::rfc5912::{ID_SHA_256, ID_SHA_384, ID_SHA_512};
+use der::{Any, Decode, Encode, Sequence, Tag, Tagged};
+use ed25519_dalek::{Signer, SigningKey, Verifier, VerifyingKey};
+use rsa::pkcs1v15::Pkcs1v15Sign;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::RsaPublicKey;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use x509_cert::attr::{Attribute, AttributeValue};
+use x509_cert::ext::pkix::name::GeneralName;
+use x509_cert::ext::pkix::SubjectKeyIdentifier;
+use x509_cert::ext::Extensions;
+use x509_cert::spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned};
+
+const ID_SIGNED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.2");
+const ID_CT_TST_INFO: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.16.1.4");
+const ID_CONTENT_TYPE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.3");
+const ID_MESSAGE_DIGEST: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+const RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const SHA256_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+const SHA384_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.12");
+const SHA512_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.13");
+const ID_ED25519: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+/// anyPolicy, used by the local TSA which has no registered policy of its own
+const ANY_POLICY: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.32.0");
+
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct MessageImprint {
+    hash_algorithm: AlgorithmIdentifierOwned,
+    hashed_message: OctetString,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct TimeStampReq {
+    version: u8,
+    message_imprint: MessageImprint,
+    #[asn1(optional = "true")]
+    req_policy: Option<ObjectIdentifier>,
+    #[asn1(optional = "true")]
+    nonce: Option<Uint>,
+    #[asn1(optional = "true")]
+    cert_req: Option<bool>,
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT", optional = "true")]
+    extensions: Option<Extensions>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct PkiStatusInfo {
+    status: u8,
+    #[asn1(optional = "true")]
+    status_string: Option<Vec<String>>,
+    #[asn1(optional = "true")]
+    fail_info: Option<der::asn1::BitString>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct TimeStampResp {
+    status: PkiStatusInfo,
+    #[asn1(optional = "true")]
+    time_stamp_token: Option<ContentInfo>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct Accuracy {
+    #[asn1(optional = "true")]
+    seconds: Option<u64>,
+    #[asn1(context_specific = "0", tag_mode = "IMPLICIT", optional = "true")]
+    millis: Option<u16>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    micros: Option<u16>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Sequence)]
+struct TstInfo {
+    version: u8,
+    policy: ObjectIdentifier,
+    message_imprint: MessageImprint,
+    serial_number: Uint,
+    // Kept raw: TSAs may add fractional seconds, which der's GeneralizedTime rejects
+    gen_time: Any,
+    #[asn1(optional = "true")]
+    accuracy: Option<Accuracy>,
+    #[asn1(default = "Default::default")]
+    ordering: bool,
+    #[asn1(optional = "true")]
+    nonce: Option<Uint>,
+    #[asn1(context_specific = "0", tag_mode = "EXPLICIT", optional = "true")]
+    tsa: Option<GeneralName>,
+    #[asn1(context_specific = "1", tag_mode = "IMPLICIT", optional = "true")]
+    extensions: Option<Extensions>,
+}
+
+/// What a verified token attests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampInfo {
+    pub gen_time: DateTime<Utc>,
+    pub serial: String,     // hex
+    pub policy: String,     // dotted OID
+    pub signer_key: String, // hex SubjectPublicKeyInfo DER of the key that signed the token
+}
+
+/// The imprint a sealed root's timestamp must carry
+pub fn root_imprint(sealed_root_message: &[u8]) -> Vec<u8> {
+    Sha256::digest(sealed_root_message).to_vec()
+}
+
+fn sha256_algorithm() -> AlgorithmIdentifierOwned {
+    AlgorithmIdentifierOwned {
+        oid: ID_SHA_256,
+        parameters: None,
+    }
+}
+
+fn der_error(e: der::Error) -> String {
+    format!("Malformed timestamp data: {}", e)
+}
+
+/// DER TimeStampReq for a SHA-256 imprint, asking the TSA to include its certificate
+pub fn build_request(imprint: &[u8], nonce: u64) -> Result<Vec<u8>, String> {
+    let request = TimeStampReq {
+        version: 1,
+        message_imprint: MessageImprint {
+            hash_algorithm: sha256_algorithm(),
+            hashed_message: OctetString::new(imprint).map_err(der_error)?,
+        },
+        req_policy: None,
+        nonce: Some(Uint::new(&nonce.to_be_bytes()).map_err(der_error)?),
+        cert_req: Some(true),
+        extensions: None,
+    };
+    request.to_der().map_err(der_error)
+}
+
+/// Extracts the token from a DER TimeStampResp, failing unless the TSA granted it
+pub fn parse_response(response: &[u8]) -> Result<Vec<u8>, String> {
+    let response = TimeStampResp::from_der(response).map_err(der_error)?;
+    // 0 granted, 1 grantedWithMods
+    if response.status.status > 1 {
+        let reason = response.status.status_string.unwrap_or_default().join("; ");
+        return Err(format!(
+            "TSA rejected the request (status {}): {}",
+            response.status.status, reason
+        ));
+    }
+    response
+        .time_stamp_token
+        .ok_or("TSA response has no token".to_string())?
+        .to_der()
+        .map_err(der_error)
+}
+
+fn parse_gen_time(raw: &Any) -> Result<DateTime<Utc>, String> {
+    if raw.tag() != Tag::GeneralizedTime {
+        return Err("genTime is not a GeneralizedTime".to_string());
+    }
+    let text = std::str::from_utf8(raw.value()).map_err(|_| "genTime is not ASCII".to_string())?;
+    let text = text.strip_suffix('Z').ok_or("genTime must be in UTC")?;
+    let format = if text.contains('.') { "%Y%m%d%H%M%S%.f" } else { "%Y%m%d%H%M%S" };
+    NaiveDateTime::parse_from_str(text, format)
+        .map(|naive| naive.and_utc())
+        .map_err(|e| format!("Invalid genTime: {}", e))
+}
+
+fn digest(algorithm: &ObjectIdentifier, data: &[u8]) -> Result<Vec<u8>, String> {
+    match *algorithm {
+        ID_SHA_256 => Ok(Sha256::digest(data).to_vec()),
+        ID_SHA_384 => Ok(Sha384::digest(data).to_vec()),
+        ID_SHA_512 => Ok(Sha512::digest(data).to_vec()),
+        other => Err(format!("Unsupported digest algorithm {}", other)),
+    }
+}
+
+fn attribute_value(signer: &SignerInfo, oid: ObjectIdentifier) -> Option<&AttributeValue> {
+    let attrs = signer.signed_attrs.as_ref()?;
+    let attr = attrs.iter().find(|attr| attr.oid == oid)?;
+    if attr.values.len() != 1 {
+        return None;
+    }
+    attr.values.iter().next()
+}
+
+fn verify_signature(
+    key: &SubjectPublicKeyInfoOwned,
+    signer: &SignerInfo,
+    signed_bytes: &[u8],
+) -> Result<(), String> {
+    let signature = signer.signature.as_bytes();
+    let key_der = key.to_der().map_err(der_error)?;
+
+    match signer.signature_algorithm.oid {
+        RSA_ENCRYPTION | SHA256_WITH_RSA | SHA384_WITH_RSA | SHA512_WITH_RSA => {
+            let public = RsaPublicKey::from_public_key_der(&key_der)
+                .map_err(|_| "Signer key is not an RSA key".to_string())?;
+            let hashed = digest(&signer.digest_alg.oid, signed_bytes)?;
+            let scheme = match signer.digest_alg.oid {
+                ID_SHA_256 => Pkcs1v15Sign::new::<Sha256>(),
+                ID_SHA_384 => Pkcs1v15Sign::new::<Sha384>(),
+                _ => Pkcs1v15Sign::new::<Sha512>(),
+            };
+            public
+                .verify(scheme, &hashed, signature)
+                .map_err(|_| "Timestamp token signature is not valid".to_string())
+        }
+        ID_ED25519 => {
+            if key.algorithm.oid != ID_ED25519 {
+                return Err("Signer key is not an Ed25519 key".to_string());
+            }
+            let bytes: [u8; 32] = key
+                .subject_public_key
+                .raw_bytes()
+                .try_into()
+                .map_err(|_| "Ed25519 key must be 32 bytes".to_string())?;
+            let public = VerifyingKey::from_bytes(&bytes).map_err(|e| e.to_string())?;
+            let signature = ed25519_dalek::Signature::from_slice(signature).map_err(|e| e.to_string())?;
+            public
+                .verify(signed_bytes, &signature)
+                .map_err(|_| "Timestamp token signature is not valid".to_string())
+        }
+        other => Err(format!("Unsupported signature algorithm {}", other)),
+    }
+}
+
+/// Parses a pinned TSA key given as hex SubjectPublicKeyInfo DER
+pub fn parse_trusted_key(hex_key: &str) -> Result<SubjectPublicKeyInfoOwned, String> {
+    let der = hex::decode(hex_key.trim()).map_err(|_| "TSA key must be hex".to_string())?;
+    SubjectPublicKeyInfoOwned::from_der(&der).map_err(der_error)
+}
+
+/// Checks a DER timestamp token: its imprint must equal `imprint` and its
+/// signature must verify under `trusted_key` (hex SubjectPublicKeyInfo DER).
+/// A certificate carried in the token is never trusted on its own, since
+/// anyone can issue a token that names itself.
+pub fn verify_token(token: &[u8], imprint: &[u8], trusted_key: &str) -> Result<TimestampInfo, String> {
+    let key = parse_trusted_key(trusted_key)?;
+    let content = ContentInfo::from_der(token).map_err(der_error)?;
+    if content.content_type != ID_SIGNED_DATA {
+        return Err("Timestamp token is not CMS SignedData".to_string());
+    }
+    let signed: SignedData = content.content.decode_as().map_err(der_error)?;
+    if signed.encap_content_info.econtent_type != ID_CT_TST_INFO {
+        return Err("Timestamp token does not carry a TSTInfo".to_string());
+    }
+    let tst_der = signed
+        .encap_content_info
+        .econtent
+        .as_ref()
+        .ok_or("Timestamp token has no content")?
+        .decode_as::<OctetString>()
+        .map_err(der_error)?;
+    let tst = TstInfo::from_der(tst_der.as_bytes()).map_err(der_error)?;
+
+    if tst.message_imprint.hash_algorithm.oid != ID_SHA_256
+        || tst.message_imprint.hashed_message.as_bytes() != imprint
+    {
+        return Err("Timestamp token is for a different root".to_string());
+    }
+
+    let signer = match signed.signer_infos.0.as_slice() {
+        [signer] => signer,
+        _ => return Err("Timestamp token must have exactly one signer".to_string()),
+    };
+
+    // RFC 5652 5.4: with signed attributes, the signature covers their DER SET encoding
+    let content_type = attribute_value(signer, ID_CONTENT_TYPE)
+        .and_then(|value| value.decode_as::<ObjectIdentifier>().ok());
+    if content_type != Some(ID_CT_TST_INFO) {
+        return Err("Timestamp token has no TSTInfo content-type attribute".to_string());
+    }
+    let message_digest = attribute_value(signer, ID_MESSAGE_DIGEST)
+        .and_then(|value| value.decode_as::<OctetString>().ok())
+        .ok_or("Timestamp token has no message-digest attribute")?;
+    if message_digest.as_bytes() != digest(&signer.digest_alg.oid, tst_der.as_bytes())? {
+        return Err("Timestamp token content does not match its signed digest".to_string());
+    }
+    let signed_attrs = signer
+        .signed_attrs
+        .as_ref()
+        .expect("checked above")
+        .to_der()
+        .map_err(der_error)?;
+
+    verify_signature(&key, signer, &signed_attrs)?;
+
+    Ok(TimestampInfo {
+        gen_time: parse_gen_time(&tst.gen_time)?,
+        serial: hex::encode(tst.serial_number.as_bytes()),
+        policy: tst.policy.to_string(),
+        signer_key: hex::encode(key.to_der().map_err(der_error)?),
+    })
+}
+
+/// Hex SubjectPublicKeyInfo DER of an Ed25519 key, the form `verify_token` trusts
+pub fn ed25519_spki(key: &VerifyingKey) -> Result<String, String> {
+    let spki = SubjectPublicKeyInfoOwned {
+        algorithm: AlgorithmIdentifierOwned {
+            oid: ID_ED25519,
+            parameters: None,
+        },
+        subject_public_key: der::asn1::BitString::from_bytes(key.as_bytes()).map_err(der_error)?,
+    };
+    Ok(hex::encode(spki.to_der().map_err(der_error)?))
+}
+
+fn attribute(oid: ObjectIdentifier, value: Any) -> Result<Attribute, der::Error> {
+    Ok(Attribute {
+        oid,
+        values: SetOfVec::try_from(vec![value])?,
+    })
+}
+
+/// Local TSA: answers a DER TimeStampReq with a granted DER TimeStampResp signed
+/// with Ed25519. The token names its key by identifier only and carries no
+/// certificate, so verifiers must be given the key (see `ed25519_spki`).
+pub fn issue(
+    key: &SigningKey,
+    request: &[u8],
+    serial: u64,
+    now: DateTime<Utc>,
+) -> Result<Vec<u8>, String> {
+    let request = TimeStampReq::from_der(request).map_err(der_error)?;
+    if request.version != 1 {
+        return Err("Unsupported TimeStampReq version".to_string());
+    }
+    digest(&request.message_imprint.hash_algorithm.oid, &[])?;
+
+    let unix = std::time::Duration::from_secs(now.timestamp().max(0) as u64);
+    let tst = TstInfo {
+        version: 1,
+        policy: request.req_policy.unwrap_or(ANY_POLICY),
+        message_imprint: request.message_imprint,
+        serial_number: Uint::new(&serial.to_be_bytes()).map_err(der_error)?,
+        gen_time: Any::encode_from(&GeneralizedTime::from_unix_duration(unix).map_err(der_error)?)
+            .map_err(der_error)?,
+        accuracy: None,
+        ordering: false,
+        nonce: request.nonce,
+        tsa: None,
+        extensions: None,
+    };
+    let tst_der = tst.to_der().map_err(der_error)?;
+
+    let signed_attrs = SetOfVec::try_from(vec![
+        attribute(ID_CONTENT_TYPE, Any::encode_from(&ID_CT_TST_INFO).map_err(der_error)?)
+            .map_err(der_error)?,
+        attribute(
+            ID_MESSAGE_DIGEST,
+            Any::encode_from(&OctetString::new(Sha512::digest(&tst_der).to_vec()).map_err(der_error)?)
+                .map_err(der_error)?,
+        )
+        .map_err(der_error)?,
+    ])
+    .map_err(der_error)?;
+    let signature = key.sign(&signed_attrs.to_der().map_err(der_error)?);
+
+    let key_id = Sha256::digest(key.verifying_key().as_bytes());
+    let signer = SignerInfo {
+        version: CmsVersion::V3,
+        sid: SignerIdentifier::SubjectKeyIdentifier(SubjectKeyIdentifier(
+            OctetString::new(&key_id[..20]).map_err(der_error)?,
+        )),
+        digest_alg: AlgorithmIdentifierOwned {
+            oid: ID_SHA_512,
+            parameters: None,
+        },
+        signed_attrs: Some(signed_attrs),
+        signature_algorithm: AlgorithmIdentifierOwned {
+            oid: ID_ED25519,
+            parameters: None,
+        },
+        signature: OctetString::new(signature.to_bytes().to_vec()).map_err(der_error)?,
+        unsigned_attrs: None,
+    };
+
+    let signed = SignedData {
+        version: CmsVersion::V3,
+        digest_algorithms: SetOfVec::try_from(vec![AlgorithmIdentifierOwned {
+            oid: ID_SHA_512,
+            parameters: None,
+        }])
+        .map_err(der_error)?,
+        encap_content_info: EncapsulatedContentInfo {
+            econtent_type: ID_CT_TST_INFO,
+            econtent: Some(Any::encode_from(&OctetString::new(tst_der).map_err(der_error)?).map_err(der_error)?),
+        },
+        certificates: None,
+        crls: None,
+        signer_infos: SignerInfos(SetOfVec::try_from(vec![signer]).map_err(der_error)?),
+    };
+
+    let response = TimeStampResp {
+        status: PkiStatusInfo {
+            status: 0,
+            status_string: None,
+            fail_info: None,
+        },
+        time_stamp_token: Some(ContentInfo {
+            content_type: ID_SIGNED_DATA,
+            content: Any::encode_from(&signed).map_err(der_error)?,
+        }),
+    };
+    response.to_der().map_err(der_error)
+}
+
+/// The nonce a token was issued for, to match it against the request
+pub fn token_nonce(token: &[u8]) -> Option<u64> {
+    let content = ContentInfo::from_der(token).ok()?;
+    let signed: SignedData = content.content.decode_as().ok()?;
+    let tst_der = signed.encap_content_info.econtent?.decode_as::<OctetString>().ok()?;
+    let nonce = TstInfo::from_der(tst_der.as_bytes()).ok()?.nonce?;
+    let bytes = nonce.as_bytes();
+    if bytes.len() > 8 {
+        return None;
+    }
+    let mut padded = [0u8; 8];
+    padded[8 - bytes.len()..].copy_from_slice(bytes);
+    Some(u64::from_be_bytes(padded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(key: &SigningKey, imprint: &[u8]) -> Vec<u8> {
+        let request = build_request(imprint, 7).unwrap();
+        parse_response(&issue(key, &request, 1, Utc::now()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn a_token_from_the_pinned_key_verifies() {
+        let key = SigningKey::from_bytes(&rand::random());
+        let imprint = root_imprint(b"sealed root");
+        let info = verify_token(&token(&key, &imprint), &imprint, &ed25519_spki(&key.verifying_key()).unwrap()).unwrap();
+        assert_eq!(info.serial, "01");
+    }
+
+    #[test]
+    fn a_token_from_an_unpinned_key_is_rejected() {
+        let (key, pinned) = (SigningKey::from_bytes(&rand::random()), SigningKey::from_bytes(&rand::random()));
+        let imprint = root_imprint(b"sealed root");
+        let pinned = ed25519_spki(&pinned.verifying_key()).unwrap();
+        assert!(verify_token(&token(&key, &imprint), &imprint, &pinned).is_err());
+    }
+
+    #[test]
+    fn a_token_over_another_root_is_rejected() {
+        let key = SigningKey::from_bytes(&rand::random());
+        let token = token(&key, &root_imprint(b"sealed root"));
+        let error = verify_token(&token, &root_imprint(b"another root"), &ed25519_spki(&key.verifying_key()).unwrap());
+        assert_eq!(error, Err("Timestamp token is for a different root".to_string()));
+    }
+}
@@ -0,0 +1,104 @@
+//! Keyed, memory-hard whitelist entries.
+//!
+//! A document number is first stretched with Argon2id under the election's
+//! public salt. That derived identifier can be computed by anyone holding the
+//! published `KdfSpec`, so admins may upload it instead of raw IDs. The server
+//! then stores only HMAC-SHA256(pepper, derived), so a leaked whitelist is
+//! useless without the pepper, and even with it every guess costs an Argon2id run.
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+
+/// `elections.whitelist_kdf` for entries built by this module
+pub const WHITELIST_KDF: &str = "ARGON2ID-HMAC-SHA256";
+/// `elections.whitelist_kdf` for the original unsalted `hash_data` entries
+pub const LEGACY_WHITELIST_KDF: &str = "SHA256";
+
+const MEMORY_KIB: u32 = 19 * 1024;
+const ITERATIONS: u32 = 2;
+const PARALLELISM: u32 = 1;
+const OUTPUT_LEN: usize = 32;
+pub const SALT_LEN: usize = 16;
+
+/// Published parameters for deriving whitelist identifiers client side
+#[derive(Debug, Clone, Serialize)]
+pub struct KdfSpec {
+    pub kdf: &'static str,
+    pub algorithm: &'static str,
+    pub version: u32,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+    pub output_len: usize,
+    pub salt: String, // hex
+    pub input: &'static str,
+    pub output: &'static str,
+}
+
+impl KdfSpec {
+    pub fn new(salt_hex: &str) -> Self {
+        KdfSpec {
+            kdf: WHITELIST_KDF,
+            algorithm: "argon2id",
+            version: 0x13,
+            memory_kib: MEMORY_KIB,
+            iterations: ITERATIONS,
+            parallelism: PARALLELISM,
+            output_len: OUTPUT_LEN,
+            salt: salt_hex.to_string(),
+            input: "document number, trimmed, UTF-8",
+            output: "hex",
+        }
+    }
+}
+
+pub fn generate_salt() -> String {
+    hex::encode(rand::random::<[u8; SALT_LEN]>())
+}
+
+/// Argon2id of the document number under the election salt, hex encoded.
+/// This is the value admins may upload in place of the raw document number.
+pub fn derive_id(document: &str, salt_hex: &str) -> Result<String, String> {
+    let salt = hex::decode(salt_hex).map_err(|_| "Whitelist salt must be hex".to_string())?;
+    let params = Params::new(MEMORY_KIB, ITERATIONS, PARALLELISM, Some(OUTPUT_LEN))
+        .map_err(|e| e.to_string())?;
+    let mut out = [0u8; OUTPUT_LEN];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(document.trim().as_bytes(), &salt, &mut out)
+        .map_err(|e| e.to_string())?;
+    Ok(hex::encode(out))
+}
+
+/// Server-held pepper for whitelist entries (the `WHITELIST_PEPPER` setting)
+pub struct WhitelistPepper([u8; 32]);
+
+impl WhitelistPepper {
+    pub fn from_hex(hex_key: &str) -> Option<Self> {
+        let bytes: [u8; 32] = hex::decode(hex_key.trim()).ok()?.try_into().ok()?;
+        Some(WhitelistPepper(bytes))
+    }
+
+    pub fn generate() -> Self {
+        WhitelistPepper(rand::random())
+    }
+
+    /// What is stored in `whitelist.document_id_hash` for a derived identifier.
+    /// Returns `None` if `derived_id` is not a well-formed Argon2id output.
+    pub fn entry(&self, derived_id: &str) -> Option<String> {
+        let derived = hex::decode(derived_id.trim()).ok()?;
+        if derived.len() != OUTPUT_LEN {
+            return None;
+        }
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.0).expect("HMAC accepts any key length");
+        mac.update(&derived);
+        Some(hex::encode(mac.finalize().into_bytes()))
+    }
+
+    /// Entry for a raw document number. Runs Argon2id, so call it off the async runtime.
+    pub fn entry_for_document(&self, document: &str, salt_hex: &str) -> Result<String, String> {
+        let derived = derive_id(document, salt_hex)?;
+        Ok(self.entry(&derived).expect("derive_id output is well formed"))
+    }
+}
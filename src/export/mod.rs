@@ -0,0 +1,211 @@
+use crate::audit_log;
+use crate::crypto::archive::{ArchiveAuditLog, ElectionArchive};
+use crate::crypto::bundle::{
+    BundleBallot, BundleElection, BundleKey, BundleTally, BundleTrustees, ElectionBundle, BUNDLE_FORMAT,
+};
+use crate::crypto;
+use crate::keys::{self, KeyError, MasterKey};
+use crate::spent_set;
+use crate::trustees::{self, CeremonyError};
+use ed25519_dalek::SigningKey;
+use sqlx::PgPool;
+use std::fmt;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub enum ExportError {
+    NotFound,
+    NotSealed,
+    Failed(String),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::NotFound => write!(f, "Election not found"),
+            ExportError::NotSealed => write!(f, "Only sealed elections can be exported"),
+            ExportError::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for ExportError {
+    fn from(e: sqlx::Error) -> Self {
+        ExportError::Failed(e.to_string())
+    }
+}
+
+impl From<KeyError> for ExportError {
+    fn from(e: KeyError) -> Self {
+        ExportError::Failed(e.to_string())
+    }
+}
+
+impl From<CeremonyError> for ExportError {
+    fn from(e: CeremonyError) -> Self {
+        ExportError::Failed(e.to_string())
+    }
+}
+
+/// Everything the public audit endpoints serve for a sealed election, in one document
+pub async fn bundle(pool: &PgPool, election_id: Uuid) -> Result<ElectionBundle, ExportError> {
+    let e = sqlx::query!(
+        r#"
+        SELECT id, title, status::text as "status!", form_config, start_date, end_date,
+               access_type::text as "access_type!", ballot_scheme, credential_scheme, hash_suite,
+               merkle_root, merkle_tree_version, proof_version, root_signature, root_key_fingerprint, root_timestamp,
+               spent_root, spent_count, spent_root_signature
+        FROM elections WHERE id = $1
+        "#,
+        election_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(ExportError::NotFound)?;
+
+    // An imported election is exactly what its archive says
+    if e.status == "ARCHIVED" {
+        let archive = stored_archive(pool, election_id).await?.ok_or(ExportError::NotFound)?;
+        let (bundle, _) = archive.open().map_err(ExportError::Failed)?;
+        return Ok(bundle);
+    }
+
+    let (Some(merkle_root), Some(merkle_tree_version)) = (e.merkle_root, e.merkle_tree_version) else {
+        return Err(ExportError::NotSealed);
+    };
+    if e.status != "SEALED" {
+        return Err(ExportError::NotSealed);
+    }
+
+    let election = BundleElection {
+        id: e.id,
+        title: e.title,
+        status: e.status,
+        form_config: e.form_config,
+        start_date: e.start_date,
+        end_date: e.end_date,
+        access_type: e.access_type,
+        ballot_scheme: e.ballot_scheme,
+        credential_scheme: e.credential_scheme,
+        hash_suite: e.hash_suite.parse().map_err(ExportError::Failed)?,
+        merkle_root,
+        merkle_tree_version,
+        proof_version: e.proof_version,
+        root_signature: e.root_signature,
+        root_key_fingerprint: e.root_key_fingerprint,
+        root_timestamp: e.root_timestamp,
+        spent_root: e.spent_root,
+        spent_count: e.spent_count,
+        spent_root_signature: e.spent_root_signature,
+    };
+
+    let ballots = sqlx::query!(
+        "SELECT id, leaf_index, ballot_hash, encrypted_choices, proofs, created_at, receipt_signature FROM ballots WHERE election_id = $1 ORDER BY leaf_index ASC",
+        election_id
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|b| BundleBallot {
+        id: b.id,
+        leaf_index: b.leaf_index,
+        ballot_hash: b.ballot_hash,
+        choices: b.encrypted_choices,
+        proofs: b.proofs,
+        created_at: b.created_at.timestamp_millis(),
+        receipt_signature: b.receipt_signature,
+    })
+    .collect();
+
+    let keys = keys::list_public(pool, election_id)
+        .await?
+        .into_iter()
+        .map(|k| BundleKey {
+            purpose: k.purpose,
+            algorithm: k.algorithm,
+            public_key: k.public_key,
+            fingerprint: k.fingerprint,
+            status: k.status,
+        })
+        .collect();
+
+    let tally = sqlx::query!(
+        "SELECT ballot_count, encrypted_tally, decrypted_tally FROM election_tallies WHERE election_id = $1",
+        election_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .map(|t| BundleTally {
+        ballot_count: t.ballot_count,
+        encrypted_tally: t.encrypted_tally,
+        decrypted_tally: t.decrypted_tally,
+    });
+
+    let trustees = if trustees::is_threshold(pool, election_id).await? {
+        let transcript = trustees::transcript(pool, election_id).await?;
+        Some(BundleTrustees {
+            threshold: transcript.ceremony.threshold,
+            verification_keys: transcript.verification_keys,
+        })
+    } else {
+        None
+    };
+
+    Ok(ElectionBundle {
+        format: BUNDLE_FORMAT.to_string(),
+        exported_at: chrono::Utc::now().timestamp_millis(),
+        election,
+        ballots,
+        spent_entries: spent_set::entries(pool, election_id).await?,
+        keys,
+        tally,
+        trustees,
+    })
+}
+
+/// The archive an ARCHIVED election was imported from, as received
+pub async fn stored_archive(pool: &PgPool, election_id: Uuid) -> Result<Option<ElectionArchive>, ExportError> {
+    let Some(rec) = sqlx::query!(
+        "SELECT manifest, signature, files FROM election_archives WHERE election_id = $1",
+        election_id
+    )
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let invalid = |e: serde_json::Error| ExportError::Failed(e.to_string());
+    Ok(Some(ElectionArchive {
+        manifest: serde_json::from_value(rec.manifest).map_err(invalid)?,
+        signature: rec.signature,
+        files: serde_json::from_value(rec.files).map_err(invalid)?,
+    }))
+}
+
+/// The bundle split into files, with the election's audit log entries, under a
+/// manifest signed by the key that sealed the election. Imported elections
+/// return the archive they came from.
+pub async fn archive(
+    pool: &PgPool,
+    master: &MasterKey,
+    audit_key: &SigningKey,
+    election_id: Uuid,
+) -> Result<ElectionArchive, ExportError> {
+    if let Some(archive) = stored_archive(pool, election_id).await? {
+        return Ok(archive);
+    }
+    let bundle = bundle(pool, election_id).await?;
+    let signer = keys::active_signing_key(pool, master, election_id).await?;
+    if bundle.election.root_key_fingerprint.as_deref() != Some(signer.fingerprint.as_str()) {
+        return Err(ExportError::Failed(
+            "The key that sealed the election is no longer active".to_string(),
+        ));
+    }
+
+    let audit_log = ArchiveAuditLog {
+        public_key: crypto::public_key_hex(audit_key),
+        entries: audit_log::election_entries(pool, election_id).await?,
+    };
+    Ok(ElectionArchive::build(&bundle, &audit_log, &signer.key))
+}
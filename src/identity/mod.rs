@@ -2,6 +2,7 @@
 // use std::sync::Arc;
 // use ort::{Environment, Session, Value}; // Commented out to avoid compilation errors without actual models/setup, providing structure.
 
+#[allow(dead_code)] // Not wired into the API until the ONNX models ship
 pub struct IdentityEngine {
     // environment: Arc<Environment>,
     // face_match_session: Session,
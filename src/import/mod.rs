@@ -0,0 +1,219 @@
+use crate::audit_log;
+use crate::ballot_log;
+use crate::crypto::archive::ElectionArchive;
+use crate::crypto::bundle::audit_bundle;
+use crate::crypto::MerkleTree;
+use chrono::{DateTime, TimeZone, Utc};
+use ed25519_dalek::SigningKey;
+use serde_json::Value;
+use sqlx::PgPool;
+use std::fmt;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub enum ImportError {
+    Rejected(String),
+    Exists,
+    Failed(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Rejected(e) => write!(f, "Archive rejected: {}", e),
+            ImportError::Exists => write!(f, "An election with this id already exists"),
+            ImportError::Failed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for ImportError {
+    fn from(e: sqlx::Error) -> Self {
+        ImportError::Failed(e.to_string())
+    }
+}
+
+/// Stores a sealed election from another deployment as ARCHIVED. Nothing is
+/// written unless the manifest, its signature, every file hash, the Merkle root
+/// and the full bundle audit all check out. Trustee ceremonies are not recreated;
+/// their verification keys stay available through the stored archive.
+///
+/// The root timestamp is only kept when it verifies under `tsa_public_key`, this
+/// deployment's pinned TSA; an archive timestamped elsewhere is imported without
+/// it, and the original token stays in the stored archive.
+pub async fn import(
+    pool: &PgPool,
+    audit_key: &SigningKey,
+    tsa_public_key: Option<&str>,
+    admin_id: Option<Uuid>,
+    archive: &ElectionArchive,
+) -> Result<Uuid, ImportError> {
+    let (bundle, _) = archive.open().map_err(ImportError::Rejected)?;
+    let report = audit_bundle(&bundle, tsa_public_key);
+    if let Some(check) = report
+        .checks
+        .iter()
+        .find(|check| !check.valid && check.name != "root_timestamp")
+    {
+        return Err(ImportError::Rejected(format!("{}: {}", check.name, check.detail)));
+    }
+    let timestamp_verified = report
+        .checks
+        .iter()
+        .any(|check| check.name == "root_timestamp" && check.valid && check.verified);
+    let root_timestamp = bundle.election.root_timestamp.clone().filter(|_| timestamp_verified);
+
+    let election = &bundle.election;
+    let mut tx = pool.begin().await?;
+
+    let exists = sqlx::query_scalar!("SELECT id FROM elections WHERE id = $1", election.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    if exists.is_some() {
+        return Err(ImportError::Exists);
+    }
+
+    // 1. The election, already sealed
+    sqlx::query!(
+        r#"
+        INSERT INTO elections (id, title, form_config, status, start_date, end_date, access_type, admin_id,
+                               ballot_scheme, credential_scheme, hash_suite, merkle_root, merkle_tree_version,
+                               proof_version, root_signature, root_key_fingerprint, root_timestamp, spent_root,
+                               spent_count, spent_root_signature)
+        VALUES ($1, $2, $3, 'ARCHIVED', $4, $5, $6::text::access_type, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
+        "#,
+        election.id,
+        election.title,
+        election.form_config,
+        election.start_date,
+        election.end_date,
+        election.access_type,
+        admin_id,
+        election.ballot_scheme,
+        election.credential_scheme,
+        election.hash_suite.name(),
+        election.merkle_root,
+        election.merkle_tree_version,
+        election.proof_version,
+        election.root_signature,
+        election.root_key_fingerprint,
+        root_timestamp,
+        election.spent_root,
+        election.spent_count,
+        election.spent_root_signature
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // 2. Ballots with their proofs against the sealed root, so receipts can still be fetched
+    let tree = MerkleTree::new(
+        election.hash_suite,
+        bundle.ballots.iter().map(|b| b.ballot_hash.clone()).collect(),
+    );
+    let ids: Vec<Uuid> = bundle.ballots.iter().map(|b| b.id).collect();
+    let leaf_indexes: Vec<i64> = bundle.ballots.iter().map(|b| b.leaf_index).collect();
+    let hashes: Vec<String> = bundle.ballots.iter().map(|b| b.ballot_hash.clone()).collect();
+    let choices: Vec<Value> = bundle.ballots.iter().map(|b| b.choices.clone()).collect();
+    let proofs: Vec<Option<Value>> = bundle.ballots.iter().map(|b| b.proofs.clone()).collect();
+    let created_at: Vec<DateTime<Utc>> = bundle
+        .ballots
+        .iter()
+        .map(|b| Utc.timestamp_millis_opt(b.created_at).single().unwrap_or_default())
+        .collect();
+    let merkle_proofs: Vec<Value> = bundle
+        .ballots
+        .iter()
+        .map(|b| serde_json::to_value(tree.get_proof(b.leaf_index as usize)).unwrap_or(Value::Null))
+        .collect();
+    let receipt_signatures: Vec<Option<String>> =
+        bundle.ballots.iter().map(|b| b.receipt_signature.clone()).collect();
+    sqlx::query!(
+        r#"
+        INSERT INTO ballots (election_id, id, leaf_index, ballot_hash, encrypted_choices, proofs, created_at, merkle_proof, receipt_signature)
+        SELECT $1, * FROM UNNEST($2::UUID[], $3::BIGINT[], $4::VARCHAR[], $5::JSONB[], $6::JSONB[], $7::TIMESTAMPTZ[], $8::JSONB[], $9::VARCHAR[])
+        "#,
+        election.id,
+        &ids,
+        &leaf_indexes,
+        &hashes,
+        &choices,
+        &proofs as &[Option<Value>],
+        &created_at,
+        &merkle_proofs,
+        &receipt_signatures as &[Option<String>]
+    )
+    .execute(&mut *tx)
+    .await?;
+    ballot_log::store_tree(&mut *tx, election.id, &tree).await?;
+
+    // 3. Public keys only; the private halves never leave the original deployment
+    for key in &bundle.keys {
+        sqlx::query!(
+            "INSERT INTO election_keys (election_id, purpose, algorithm, public_key, fingerprint, status) VALUES ($1, $2, $3, $4, $5, $6::text::key_status)",
+            election.id,
+            key.purpose,
+            key.algorithm,
+            key.public_key,
+            key.fingerprint,
+            key.status
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // 4. The spent set, where spent_set::entries looks for this credential scheme
+    if election.credential_scheme == "NULLIFIER" {
+        sqlx::query!(
+            "INSERT INTO voter_registry (election_id, nullifier_hash, identity_status) SELECT $1, entry, 'ARCHIVED' FROM UNNEST($2::VARCHAR[]) AS entry",
+            election.id,
+            &bundle.spent_entries
+        )
+        .execute(&mut *tx)
+        .await?;
+    } else {
+        sqlx::query!(
+            "INSERT INTO spent_credentials (election_id, token_hash) SELECT $1, entry FROM UNNEST($2::VARCHAR[]) AS entry",
+            election.id,
+            &bundle.spent_entries
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // 5. The tally
+    if let Some(tally) = &bundle.tally {
+        sqlx::query!(
+            "INSERT INTO election_tallies (election_id, ballot_count, encrypted_tally, decrypted_tally) VALUES ($1, $2, $3, $4)",
+            election.id,
+            tally.ballot_count,
+            tally.encrypted_tally,
+            tally.decrypted_tally
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // 6. The archive itself, re-served verbatim since this instance cannot re-sign it
+    sqlx::query!(
+        "INSERT INTO election_archives (election_id, manifest, signature, files, imported_by) VALUES ($1, $2, $3, $4, $5)",
+        election.id,
+        serde_json::to_value(&archive.manifest).map_err(|e| ImportError::Failed(e.to_string()))?,
+        archive.signature,
+        serde_json::to_value(&archive.files).map_err(|e| ImportError::Failed(e.to_string()))?,
+        admin_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let entry = serde_json::json!({
+        "election_id": election.id,
+        "key_fingerprint": archive.manifest.key_fingerprint,
+        "merkle_root": election.merkle_root,
+        "ballot_count": bundle.ballots.len(),
+        "root_timestamp_verified": timestamp_verified,
+    });
+    audit_log::append(&mut tx, audit_key, admin_id, "IMPORT_ELECTION", entry).await?;
+
+    tx.commit().await?;
+    Ok(election.id)
+}
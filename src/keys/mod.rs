@@ -0,0 +1,504 @@
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use curve25519_dalek::{ristretto::RistrettoPoint, scalar::Scalar};
+use ed25519_dalek::SigningKey;
+use rand::rngs::OsRng;
+use serde::Serialize;
+use sqlx::{PgExecutor, PgPool, Postgres, Transaction};
+use std::fmt;
+use uuid::Uuid;
+
+use crate::crypto::{self, credential, elgamal};
+
+const NONCE_LEN: usize = 24;
+
+/// Server-wide key that encrypts every election private key at rest
+pub struct MasterKey(XChaCha20Poly1305);
+
+impl MasterKey {
+    /// Parses a hex-encoded 32-byte key (the `MASTER_KEY` setting)
+    pub fn from_hex(hex_key: &str) -> Option<Self> {
+        let bytes: [u8; 32] = hex::decode(hex_key.trim()).ok()?.try_into().ok()?;
+        Some(MasterKey(XChaCha20Poly1305::new(&bytes.into())))
+    }
+
+    /// Returns hex(nonce || ciphertext). `aad` binds the ciphertext to its database row.
+    fn encrypt(&self, plaintext: &[u8], aad: &[u8]) -> String {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .0
+            .encrypt(&nonce, Payload { msg: plaintext, aad })
+            .expect("encryption with a valid key cannot fail");
+
+        let mut out = nonce.to_vec();
+        out.extend(ciphertext);
+        hex::encode(out)
+    }
+
+    fn decrypt(&self, encoded: &str, aad: &[u8]) -> Result<Vec<u8>, KeyError> {
+        let bytes = hex::decode(encoded).map_err(|e| KeyError::Corrupt(e.to_string()))?;
+        if bytes.len() < NONCE_LEN {
+            return Err(KeyError::Corrupt("ciphertext too short".to_string()));
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+
+        self.0
+            .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+            .map_err(|_| KeyError::Corrupt("wrong master key or tampered key row".to_string()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyPurpose {
+    Signing,
+    Encryption,
+    Credential,
+}
+
+impl KeyPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyPurpose::Signing => "SIGNING",
+            KeyPurpose::Encryption => "ENCRYPTION",
+            KeyPurpose::Credential => "CREDENTIAL",
+        }
+    }
+
+    fn algorithm(&self) -> &'static str {
+        match self {
+            KeyPurpose::Signing => "Ed25519",
+            KeyPurpose::Encryption => "ElGamal-Ristretto255",
+            KeyPurpose::Credential => credential::CREDENTIAL_SCHEME,
+        }
+    }
+
+    /// Fresh (private key bytes, hex public key) pair for this purpose
+    fn generate_pair(&self) -> (Vec<u8>, String) {
+        match self {
+            KeyPurpose::Signing => {
+                let key = SigningKey::generate(&mut OsRng);
+                (key.to_bytes().to_vec(), crypto::public_key_hex(&key))
+            }
+            KeyPurpose::Encryption => {
+                let secret = elgamal::random_scalar(&mut OsRng);
+                (
+                    secret.to_bytes().to_vec(),
+                    elgamal::point_to_hex(&elgamal::public_key(&secret)),
+                )
+            }
+            KeyPurpose::Credential => {
+                credential::generate_keypair().expect("RSA key generation with fixed parameters")
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum KeyError {
+    NoActiveKey,
+    NotFound,
+    Conflict(String),
+    Corrupt(String),
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::NoActiveKey => write!(f, "Election has no active key for this purpose"),
+            KeyError::NotFound => write!(f, "Key not found"),
+            KeyError::Conflict(e) => write!(f, "{}", e),
+            KeyError::Corrupt(e) => write!(f, "Could not decrypt election key: {}", e),
+            KeyError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for KeyError {
+    fn from(e: sqlx::Error) -> Self {
+        KeyError::Database(e)
+    }
+}
+
+/// Public view of an election key, safe to publish
+#[derive(Debug, Clone, Serialize)]
+pub struct ElectionKey {
+    pub id: Uuid,
+    pub election_id: Uuid,
+    pub purpose: String,
+    pub algorithm: String,
+    pub public_key: String,
+    pub fingerprint: String,
+    pub status: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub retired_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// An active signing key, decrypted for use
+pub struct ActiveSigningKey {
+    pub fingerprint: String,
+    pub public_key: String,
+    pub key: SigningKey,
+}
+
+fn row_aad(key_id: Uuid, election_id: Uuid, purpose: KeyPurpose) -> Vec<u8> {
+    format!("{}|{}|{}", key_id, election_id, purpose.as_str()).into_bytes()
+}
+
+/// Generates a new active key for the election and stores the private half encrypted.
+/// Fails on the unique index if an active key for this purpose already exists.
+pub async fn generate<'e>(
+    executor: impl PgExecutor<'e>,
+    master: &MasterKey,
+    election_id: Uuid,
+    purpose: KeyPurpose,
+) -> Result<String, KeyError> {
+    let key_id = Uuid::new_v4();
+    // RSA key generation takes long enough to stall the async runtime
+    let (private_key, public_key) = tokio::task::spawn_blocking(move || purpose.generate_pair())
+        .await
+        .expect("key generation task panicked");
+    let fingerprint = crypto::key_fingerprint(&public_key);
+    let encrypted = master.encrypt(&private_key, &row_aad(key_id, election_id, purpose));
+
+    sqlx::query!(
+        "INSERT INTO election_keys (id, election_id, purpose, algorithm, public_key, fingerprint, encrypted_private_key) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        key_id,
+        election_id,
+        purpose.as_str(),
+        purpose.algorithm(),
+        public_key,
+        fingerprint,
+        encrypted
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(fingerprint)
+}
+
+/// The active credential key, which blind-signs voter tokens, as (hex public key, DER secret)
+pub async fn credential_secret(
+    pool: &PgPool,
+    master: &MasterKey,
+    election_id: Uuid,
+) -> Result<(String, Vec<u8>), KeyError> {
+    let purpose = KeyPurpose::Credential;
+    let rec = sqlx::query!(
+        r#"SELECT id, public_key, encrypted_private_key as "encrypted_private_key?" FROM election_keys WHERE election_id = $1 AND purpose = $2 AND status = 'ACTIVE'"#,
+        election_id,
+        purpose.as_str()
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(KeyError::NoActiveKey)?;
+    let encrypted = rec.encrypted_private_key.ok_or(KeyError::NoActiveKey)?;
+    let secret = master.decrypt(&encrypted, &row_aad(rec.id, election_id, purpose))?;
+
+    Ok((rec.public_key, secret))
+}
+
+/// Public halves of every credential key the election has used. Credentials
+/// issued before a rotation stay spendable.
+pub async fn credential_public_keys<'e>(
+    executor: impl PgExecutor<'e>,
+    election_id: Uuid,
+) -> Result<Vec<String>, KeyError> {
+    let recs = sqlx::query!(
+        "SELECT public_key FROM election_keys WHERE election_id = $1 AND purpose = $2 ORDER BY created_at ASC",
+        election_id,
+        KeyPurpose::Credential.as_str()
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(recs.into_iter().map(|r| r.public_key).collect())
+}
+
+/// Records a key whose private half the server never sees (e.g. a trustee joint key)
+pub async fn register_public<'e>(
+    executor: impl PgExecutor<'e>,
+    election_id: Uuid,
+    purpose: KeyPurpose,
+    algorithm: &str,
+    public_key: &str,
+) -> Result<String, KeyError> {
+    let fingerprint = crypto::key_fingerprint(public_key);
+
+    sqlx::query!(
+        "INSERT INTO election_keys (election_id, purpose, algorithm, public_key, fingerprint) VALUES ($1, $2, $3, $4, $5)",
+        election_id,
+        purpose.as_str(),
+        algorithm,
+        public_key,
+        fingerprint
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(fingerprint)
+}
+
+/// Deletes every key of `purpose`. Only safe before the election opens, while
+/// nothing has been signed or encrypted under them.
+pub async fn discard<'e>(
+    executor: impl PgExecutor<'e>,
+    election_id: Uuid,
+    purpose: KeyPurpose,
+) -> Result<(), KeyError> {
+    sqlx::query!(
+        "DELETE FROM election_keys WHERE election_id = $1 AND purpose = $2",
+        election_id,
+        purpose.as_str()
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Decrypts the election's active signing key. Elections created before key
+/// management existed get their first key generated here.
+pub async fn active_signing_key(
+    pool: &PgPool,
+    master: &MasterKey,
+    election_id: Uuid,
+) -> Result<ActiveSigningKey, KeyError> {
+    let purpose = KeyPurpose::Signing;
+
+    for _ in 0..2 {
+        let rec = sqlx::query!(
+            r#"SELECT id, public_key, fingerprint, encrypted_private_key as "encrypted_private_key!" FROM election_keys WHERE election_id = $1 AND purpose = $2 AND status = 'ACTIVE' AND encrypted_private_key IS NOT NULL"#,
+            election_id,
+            purpose.as_str()
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        if let Some(rec) = rec {
+            let seed: [u8; 32] = master
+                .decrypt(
+                    &rec.encrypted_private_key,
+                    &row_aad(rec.id, election_id, purpose),
+                )?
+                .try_into()
+                .map_err(|_| KeyError::Corrupt("unexpected key length".to_string()))?;
+
+            return Ok(ActiveSigningKey {
+                fingerprint: rec.fingerprint,
+                public_key: rec.public_key,
+                key: SigningKey::from_bytes(&seed),
+            });
+        }
+
+        let existing = sqlx::query!(
+            "SELECT COUNT(*) as count FROM election_keys WHERE election_id = $1 AND purpose = $2",
+            election_id,
+            purpose.as_str()
+        )
+        .fetch_one(pool)
+        .await?;
+
+        if existing.count.unwrap_or(0) > 0 {
+            // Keys were deliberately retired; never resurrect signing silently
+            return Err(KeyError::NoActiveKey);
+        }
+
+        // A concurrent caller may win the unique index; the retry then reads its key
+        let _ = generate(pool, master, election_id, purpose).await;
+    }
+
+    Err(KeyError::NoActiveKey)
+}
+
+/// The election's ElGamal secret. Ballots are bound to the key they were
+/// encrypted under, so this is never rotated and is read regardless of status.
+/// Threshold elections have no secret here; their trustees hold it in shares.
+pub async fn encryption_secret(
+    pool: &PgPool,
+    master: &MasterKey,
+    election_id: Uuid,
+) -> Result<Scalar, KeyError> {
+    let purpose = KeyPurpose::Encryption;
+    let rec = sqlx::query!(
+        r#"SELECT id, encrypted_private_key as "encrypted_private_key?" FROM election_keys WHERE election_id = $1 AND purpose = $2 ORDER BY created_at ASC LIMIT 1"#,
+        election_id,
+        purpose.as_str()
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(KeyError::NotFound)?;
+    let encrypted = rec.encrypted_private_key.ok_or(KeyError::NotFound)?;
+
+    let bytes = master.decrypt(&encrypted, &row_aad(rec.id, election_id, purpose))?;
+    elgamal::scalar_from_bytes(&bytes)
+        .ok_or_else(|| KeyError::Corrupt("invalid ElGamal secret".to_string()))
+}
+
+/// The key ballots are encrypted under: the election's first encryption key
+pub async fn encryption_public_key<'e>(
+    executor: impl PgExecutor<'e>,
+    election_id: Uuid,
+) -> Result<RistrettoPoint, KeyError> {
+    let rec = sqlx::query!(
+        "SELECT public_key FROM election_keys WHERE election_id = $1 AND purpose = $2 ORDER BY created_at ASC LIMIT 1",
+        election_id,
+        KeyPurpose::Encryption.as_str()
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or(KeyError::NotFound)?;
+
+    elgamal::point_from_hex(&rec.public_key)
+        .ok_or_else(|| KeyError::Corrupt("invalid ElGamal public key".to_string()))
+}
+
+/// Every key the election has ever had, oldest first
+pub async fn list_public(pool: &PgPool, election_id: Uuid) -> Result<Vec<ElectionKey>, KeyError> {
+    let recs = sqlx::query!(
+        "SELECT id, election_id, purpose, algorithm, public_key, fingerprint, status::text as status, created_at, retired_at FROM election_keys WHERE election_id = $1 ORDER BY created_at ASC",
+        election_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(recs
+        .into_iter()
+        .map(|r| ElectionKey {
+            id: r.id,
+            election_id: r.election_id,
+            purpose: r.purpose,
+            algorithm: r.algorithm,
+            public_key: r.public_key,
+            fingerprint: r.fingerprint,
+            status: r.status.unwrap_or_default(),
+            created_at: r.created_at,
+            retired_at: r.retired_at,
+        })
+        .collect())
+}
+
+/// Retires the active key for `purpose` and replaces it with a fresh one
+pub async fn rotate(
+    pool: &PgPool,
+    master: &MasterKey,
+    election_id: Uuid,
+    purpose: KeyPurpose,
+) -> Result<String, KeyError> {
+    let mut tx = pool.begin().await?;
+    lock_for_key_change(&mut tx, election_id).await?;
+
+    sqlx::query!(
+        "UPDATE election_keys SET status = 'RETIRED', retired_at = NOW() WHERE election_id = $1 AND purpose = $2 AND status = 'ACTIVE'",
+        election_id,
+        purpose.as_str()
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let fingerprint = generate(&mut *tx, master, election_id, purpose).await?;
+    tx.commit().await?;
+
+    Ok(fingerprint)
+}
+
+/// Stops a key from signing anything further. Its public half stays published.
+/// The last active signing key cannot be retired; rotate it instead.
+pub async fn retire(pool: &PgPool, election_id: Uuid, key_id: Uuid) -> Result<(), KeyError> {
+    let mut tx = pool.begin().await?;
+    lock_for_key_change(&mut tx, election_id).await?;
+
+    let key = sqlx::query!(
+        "SELECT purpose FROM election_keys WHERE id = $1 AND election_id = $2 AND status = 'ACTIVE'",
+        key_id,
+        election_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(KeyError::NotFound)?;
+
+    if key.purpose == KeyPurpose::Signing.as_str() {
+        let active = sqlx::query_scalar!(
+            r#"SELECT COUNT(*) as "count!" FROM election_keys WHERE election_id = $1 AND purpose = $2 AND status = 'ACTIVE'"#,
+            election_id,
+            KeyPurpose::Signing.as_str()
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        if active <= 1 {
+            return Err(KeyError::Conflict(
+                "The last active signing key cannot be retired; rotate it instead".to_string(),
+            ));
+        }
+    }
+
+    sqlx::query!(
+        "UPDATE election_keys SET status = 'RETIRED', retired_at = NOW() WHERE id = $1",
+        key_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Locks the election row for the rest of `tx` and refuses key changes once sealing
+/// has begun: the sealed root, the receipts and the archive are all signed by the
+/// key that was active when the election moved to CLOSING
+async fn lock_for_key_change(tx: &mut Transaction<'_, Postgres>, election_id: Uuid) -> Result<(), KeyError> {
+    let status = sqlx::query_scalar!(
+        r#"SELECT status::text as "status!" FROM elections WHERE id = $1 FOR UPDATE"#,
+        election_id
+    )
+    .fetch_optional(&mut **tx)
+    .await?
+    .ok_or(KeyError::NotFound)?;
+
+    match status.as_str() {
+        "CLOSING" | "SEALED" | "ARCHIVED" => Err(KeyError::Conflict(
+            "Election keys cannot change once the election is being sealed".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn master_key() -> MasterKey {
+        MasterKey::from_hex(&hex::encode(rand::random::<[u8; 32]>())).unwrap()
+    }
+
+    #[test]
+    fn a_key_row_decrypts_under_its_own_aad() {
+        let master = master_key();
+        let aad = row_aad(Uuid::new_v4(), Uuid::new_v4(), KeyPurpose::Signing);
+        let encrypted = master.encrypt(b"election secret", &aad);
+        assert_eq!(master.decrypt(&encrypted, &aad).unwrap(), b"election secret");
+    }
+
+    #[test]
+    fn another_master_key_cannot_decrypt() {
+        let aad = row_aad(Uuid::new_v4(), Uuid::new_v4(), KeyPurpose::Signing);
+        let encrypted = master_key().encrypt(b"election secret", &aad);
+        assert!(matches!(master_key().decrypt(&encrypted, &aad), Err(KeyError::Corrupt(_))));
+    }
+
+    #[test]
+    fn a_key_row_moved_to_another_election_or_purpose_is_corrupt() {
+        let master = master_key();
+        let (key_id, election_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let encrypted = master.encrypt(b"election secret", &row_aad(key_id, election_id, KeyPurpose::Signing));
+
+        for aad in [
+            row_aad(key_id, Uuid::new_v4(), KeyPurpose::Signing),
+            row_aad(key_id, election_id, KeyPurpose::Encryption),
+            row_aad(Uuid::new_v4(), election_id, KeyPurpose::Signing),
+        ] {
+            assert!(matches!(master.decrypt(&encrypted, &aad), Err(KeyError::Corrupt(_))));
+        }
+    }
+}
@@ -1,13 +1,22 @@
 mod api;
-mod crypto;
+mod audit_log;
+mod ballot_log;
+mod export;
 mod identity;
+mod import;
+mod keys;
 mod scheduler;
+mod spent_set;
+mod trustees;
+mod tsa;
 
 use dotenvy::dotenv;
+use ed25519_dalek::SigningKey;
+use solesigner::crypto;
 use sqlx::postgres::PgPoolOptions;
 use std::env;
 use std::net::SocketAddr;
-use tracing_subscriber;
+use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -26,26 +35,111 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("✅ Connected to Database");
 
+    // Ballot logs written before the node table need indexing once
+    let indexed = ballot_log::backfill_nodes(&pool).await?;
+    if indexed > 0 {
+        println!("✅ Indexed the ballot logs of {} elections", indexed);
+    }
+
     // 4. Run Migrations (Optional, simplifies setup)
     // sqlx::migrate!("./migrations").run(&pool).await?;
     // println!("✅ Migrations Applied");
 
-    // 5. Start Scheduler
+    // 5. Load the master key that protects election keys at rest
+    // Without it election keys written under another key could not be read back, so there is no fallback
+    let master_key = env::var("MASTER_KEY").expect("MASTER_KEY must be set");
+    let master_key = keys::MasterKey::from_hex(&master_key).expect("MASTER_KEY must be 32 bytes in hex");
+    let master_key = Arc::new(master_key);
+
+    // 6. Load the secrets that voter nullifiers and whitelist entries are keyed with
+    // A fresh secret after a restart would let voters validate and vote again, so both are required
+    let nullifier_key = env::var("NULLIFIER_SECRET").expect("NULLIFIER_SECRET must be set");
+    let nullifier_key =
+        crypto::NullifierKey::from_hex(&nullifier_key).expect("NULLIFIER_SECRET must be 32 bytes in hex");
+    let nullifier_key = Arc::new(nullifier_key);
+
+    let whitelist_pepper = env::var("WHITELIST_PEPPER").expect("WHITELIST_PEPPER must be set");
+    let whitelist_pepper = crypto::whitelist::WhitelistPepper::from_hex(&whitelist_pepper)
+        .expect("WHITELIST_PEPPER must be 32 bytes in hex");
+    let whitelist_pepper = Arc::new(whitelist_pepper);
+
+    // 7. Load the key that signs the admin audit log
+    // Entries signed by a throwaway key could not be verified after a restart, so it is required
+    let audit_seed = env::var("AUDIT_SIGNING_KEY").expect("AUDIT_SIGNING_KEY must be set");
+    let audit_seed: [u8; 32] = hex::decode(audit_seed.trim())
+        .ok()
+        .and_then(|seed| seed.try_into().ok())
+        .expect("AUDIT_SIGNING_KEY must be a 32-byte Ed25519 seed in hex");
+    let audit_key = SigningKey::from_bytes(&audit_seed);
+    let audit_key = Arc::new(audit_key);
+
+    // 8. Configure the TSA that timestamps sealed roots
+    // Its tokens are only checked against a pinned key, so a URL without one is refused
+    let tsa = match env::var("TSA_URL") {
+        Ok(url) if !url.trim().is_empty() => {
+            let tsa_key = env::var("TSA_PUBLIC_KEY").expect("TSA_PUBLIC_KEY must be set when TSA_URL is");
+            crypto::timestamp::parse_trusted_key(&tsa_key)
+                .expect("TSA_PUBLIC_KEY must be a SubjectPublicKeyInfo in hex DER");
+            println!("✅ Sealed roots will be timestamped by {}", url.trim());
+            Some(Arc::new(tsa::TsaClient::new(url.trim().to_string(), tsa_key.trim().to_string())))
+        }
+        _ => {
+            println!("⚠️  TSA_URL not set, sealed roots will not be timestamped");
+            None
+        }
+    };
+
+    // `solesigner export <election_id> <file>` writes a sealed election's signed archive and
+    // `solesigner import <file>` stores one from another deployment as ARCHIVED; both then exit
+    let args: Vec<String> = env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("export") => {
+            let (Some(election_id), Some(path)) = (args.get(2).and_then(|id| id.parse().ok()), args.get(3)) else {
+                eprintln!("Usage: solesigner export <election_id> <output_file>");
+                std::process::exit(1);
+            };
+            let archive = export::archive(&pool, &master_key, &audit_key, election_id)
+                .await
+                .map_err(|e| e.to_string())?;
+            std::fs::write(path, serde_json::to_string_pretty(&archive)?)?;
+            println!("✅ Archive of election {} written to {}", election_id, path);
+            return Ok(());
+        }
+        Some("import") => {
+            let Some(path) = args.get(2) else {
+                eprintln!("Usage: solesigner import <archive_file>");
+                std::process::exit(1);
+            };
+            let archive = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+            let tsa_key = tsa.as_deref().map(tsa::TsaClient::public_key);
+            let election_id = import::import(&pool, &audit_key, tsa_key, None, &archive)
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("✅ Election {} imported as ARCHIVED", election_id);
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    // 9. Start Scheduler
     let pool_for_scheduler = pool.clone();
+    let key_for_scheduler = master_key.clone();
+    let tsa_for_scheduler = tsa.clone();
     tokio::spawn(async move {
-        scheduler::start_scheduler(pool_for_scheduler).await;
+        scheduler::start_scheduler(pool_for_scheduler, key_for_scheduler, tsa_for_scheduler).await;
     });
 
     println!("✅ Scheduler Started");
 
-    // 6. Start API Server
+    // 10. Start API Server
     let cors = tower_http::cors::CorsLayer::new()
         .allow_origin(tower_http::cors::Any)
         .allow_methods(tower_http::cors::Any)
         .allow_headers(tower_http::cors::Any);
 
-    let router = api::router(pool).layer(cors);
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+    let router = api::router(pool, master_key, nullifier_key, whitelist_pepper, audit_key, tsa).layer(cors);
+    let port = env::var("PORT").ok().and_then(|port| port.parse().ok()).unwrap_or(8080);
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
     let listener = tokio::net::TcpListener::bind(addr).await?;
 
     println!("🚀 Server listening on {}", addr);
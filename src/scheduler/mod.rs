@@ -1,19 +1,48 @@
-use crate::crypto::MerkleTree;
+use crate::ballot_log;
+use crate::crypto::{self, ballot, elgamal::ProofContext, MerkleTree, MERKLE_TREE_VERSION};
+use crate::keys::{self, MasterKey};
+use crate::spent_set;
+use crate::trustees;
+use crate::tsa::{RootTimestamp, TsaClient};
+use rand::rngs::OsRng;
+use serde_json::Value;
 use sqlx::PgPool;
 use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
 use tokio_cron_scheduler::{Job, JobScheduler}; // Assuming exposed
 
-pub async fn start_scheduler(pool: PgPool) {
+const TSA_ATTEMPTS: u32 = 3;
+
+pub async fn start_scheduler(pool: PgPool, master_key: Arc<MasterKey>, tsa: Option<Arc<TsaClient>>) {
     let sched = JobScheduler::new().await.unwrap();
     let pool = Arc::new(pool);
 
     // Job to close elections
     let pool_clone = pool.clone();
+    let key_clone = master_key.clone();
     let job = Job::new_async("0 * * * * *", move |_uuid, _l| {
         // Every minute
         let pool = pool_clone.clone();
+        let key = key_clone.clone();
+        let tsa = tsa.clone();
         Box::pin(async move {
-            close_expired_elections(pool).await;
+            close_expired_elections(pool, key, tsa).await;
+        })
+    })
+    .unwrap();
+
+    sched.add(job).await.unwrap();
+
+    // Job to publish signed tree heads for open elections
+    let pool_clone = pool.clone();
+    let key_clone = master_key.clone();
+    let job = Job::new_async("30 * * * * *", move |_uuid, _l| {
+        // Every minute, offset from the closing job
+        let pool = pool_clone.clone();
+        let key = key_clone.clone();
+        Box::pin(async move {
+            publish_tree_heads(pool, key).await;
         })
     })
     .unwrap();
@@ -22,7 +51,46 @@ pub async fn start_scheduler(pool: PgPool) {
     sched.start().await.unwrap();
 }
 
-async fn close_expired_elections(pool: Arc<PgPool>) {
+async fn publish_tree_heads(pool: Arc<PgPool>, master_key: Arc<MasterKey>) {
+    let records = sqlx::query!("SELECT id FROM elections WHERE status = 'OPEN'")
+        .fetch_all(&*pool)
+        .await;
+
+    if let Ok(elections) = records {
+        for election in elections {
+            let signer = match keys::active_signing_key(&pool, &master_key, election.id).await {
+                Ok(signer) => signer,
+                Err(e) => {
+                    println!("Cannot sign tree head for {}: {}", election.id, e);
+                    continue;
+                }
+            };
+
+            match ballot_log::publish_head(&pool, &signer.key, election.id).await {
+                Ok(Some(head)) => println!(
+                    "Published tree head for {}: size {} root {}",
+                    election.id, head.tree_size, head.root_hash
+                ),
+                Ok(None) => {}
+                Err(e) => println!("Failed to publish tree head for {}: {}", election.id, e),
+            }
+        }
+    }
+}
+
+async fn close_expired_elections(pool: Arc<PgPool>, master_key: Arc<MasterKey>, tsa: Option<Arc<TsaClient>>) {
+    // Retry elections whose seal failed on an earlier run, e.g. while the TSA was unreachable
+    let stuck = sqlx::query!("SELECT id FROM elections WHERE status = 'CLOSING'")
+        .fetch_all(&*pool)
+        .await;
+    if let Ok(elections) = stuck {
+        for election in elections {
+            if let Err(e) = seal_election(&pool, &master_key, tsa.as_deref(), election.id).await {
+                println!("{}", e);
+            }
+        }
+    }
+
     // 1. Find elections to close
     // Note: sqlx query! macros might be tricky with enums if not careful, using simple string query or checking compatibility.
     // Casting status to text for comparison if needed or ensuring custom types work.
@@ -39,38 +107,230 @@ async fn close_expired_elections(pool: Arc<PgPool>) {
         for election in elections {
             println!("Closing election: {}", election.title);
 
-            // 2. Lock / Set status to PROCESSING/CLOSING
-            let _ = sqlx::query!(
-                "UPDATE elections SET status = 'CLOSING' WHERE id = $1",
+            // 2. Lock / Set status to CLOSING, unless an admin closed it meanwhile
+            let claimed = sqlx::query!(
+                "UPDATE elections SET status = 'CLOSING' WHERE id = $1 AND status = 'OPEN'",
                 election.id
             )
             .execute(&*pool)
             .await;
 
-            // 3. Seal the election (Compute Merkle Tree)
-            let ballots = sqlx::query!(
-                "SELECT ballot_hash FROM ballots WHERE election_id = $1 ORDER BY created_at ASC",
-                election.id
+            match claimed {
+                Ok(res) if res.rows_affected() == 0 => continue,
+                Ok(_) => {}
+                Err(e) => {
+                    println!("Failed to close {}: {}", election.id, e);
+                    continue;
+                }
+            }
+
+            if let Err(e) = seal_election(&pool, &master_key, tsa.as_deref(), election.id).await {
+                println!("{}", e);
+            }
+        }
+    }
+}
+
+/// Seals an election already moved to CLOSING: builds the Merkle tree, signs
+/// the root, timestamps it with the TSA if one is configured, tallies the
+/// encrypted ballots and publishes the final tree head. On error the election
+/// stays CLOSING and the scheduler retries it.
+pub async fn seal_election(
+    pool: &PgPool,
+    master_key: &MasterKey,
+    tsa: Option<&TsaClient>,
+    election_id: Uuid,
+) -> Result<(), String> {
+    // 1. Compute the Merkle Tree in ballot log order
+    let ballots = sqlx::query!(
+        "SELECT leaf_index, ballot_hash, encrypted_choices, created_at FROM ballots WHERE election_id = $1 ORDER BY leaf_index ASC",
+        election_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("Failed to load the ballots of {}: {}", election_id, e))?;
+
+    let suite = ballot_log::hash_suite(pool, election_id)
+        .await
+        .map_err(|e| format!("Failed to load the hash suite for {}: {}", election_id, e))?;
+    let leaves: Vec<String> = ballots.iter().map(|b| b.ballot_hash.clone()).collect();
+    let tree = MerkleTree::new(suite, leaves);
+
+    let root = tree.root.clone();
+
+    // The spent-credential set is sealed with it, so voter counts can be checked against ballots
+    let spent = spent_set::tree(pool, election_id)
+        .await
+        .map_err(|e| format!("Failed to build the spent set for {}: {}", election_id, e))?;
+    let spent_count = spent.size() as i64;
+
+    // 2. Sign both roots with the election key. Sealing is final, so without the key
+    // the election stays CLOSING and the next run tries again
+    let signer = keys::active_signing_key(pool, master_key, election_id)
+        .await
+        .map_err(|e| format!("Failed to load the signing key of {}: {}", election_id, e))?;
+    let root_signature = crypto::sign_message(
+        &signer.key,
+        &crypto::sealed_root_message(&election_id, &root, MERKLE_TREE_VERSION),
+    );
+    let spent_root_signature = crypto::sign_message(
+        &signer.key,
+        &crypto::spent_root_message(&election_id, &spent.root, spent_count),
+    );
+
+    // 3. Have the TSA vouch for when the root was sealed; with a TSA configured, an
+    // election is never sealed without its timestamp
+    let mut root_timestamp = None;
+    if let Some(tsa) = tsa {
+        let message = crypto::sealed_root_message(&election_id, &root, MERKLE_TREE_VERSION);
+        root_timestamp = Some(
+            timestamp_with_retries(tsa, &message)
+                .await
+                .map_err(|e| format!("Failed to timestamp the root of {}: {}", election_id, e))?,
+        );
+    }
+
+    // 4. Keep each ballot's proof against the sealed root, with a receipt signed by the same key,
+    // so voters can fetch a verifiable receipt after the election closes
+    let leaf_indexes: Vec<i64> = ballots.iter().map(|b| b.leaf_index).collect();
+    let proofs: Vec<Value> = ballots
+        .iter()
+        .map(|b| serde_json::to_value(tree.get_proof(b.leaf_index as usize)).unwrap_or(Value::Null))
+        .collect();
+    let receipt_signatures: Vec<String> = ballots
+        .iter()
+        .map(|b| {
+            crypto::sign_message(
+                &signer.key,
+                &crypto::receipt_message(&election_id, &b.ballot_hash, b.created_at.timestamp_millis()),
             )
-            .fetch_all(&*pool)
+        })
+        .collect();
+    sqlx::query!(
+        r#"
+        UPDATE ballots b SET merkle_proof = u.proof, receipt_signature = u.signature
+        FROM UNNEST($2::BIGINT[], $3::JSONB[], $4::VARCHAR[]) AS u(leaf_index, proof, signature)
+        WHERE b.election_id = $1 AND b.leaf_index = u.leaf_index
+        "#,
+        election_id,
+        &leaf_indexes,
+        &proofs,
+        &receipt_signatures
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to store the ballot proofs of {}: {}", election_id, e))?;
+
+    // 5. Tally encrypted ballots homomorphically; a tally that cannot count every ballot stops the seal
+    let election = sqlx::query!(
+        "SELECT form_config, ballot_scheme, proof_version FROM elections WHERE id = $1",
+        election_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("Failed to load election {}: {}", election_id, e))?;
+
+    if election.ballot_scheme != "PLAINTEXT" {
+        let choices: Vec<_> = ballots.iter().map(|b| b.encrypted_choices.clone()).collect();
+        tally_election(pool, master_key, election_id, election.proof_version, &election.form_config, &choices)
             .await
-            .unwrap_or(vec![]);
+            .map_err(|e| format!("Failed to tally {}: {}", election_id, e))?;
+    }
 
-            let leaves: Vec<String> = ballots.into_iter().map(|b| b.ballot_hash).collect();
-            let tree = MerkleTree::new(leaves);
+    // 6. Update Election with the signed Root and set to SEALED
+    let sealed = sqlx::query!(
+        "UPDATE elections SET status = 'SEALED', merkle_root = $1, merkle_tree_version = $2, root_signature = $3, root_key_fingerprint = $4, spent_root = $5, spent_count = $6, spent_root_signature = $7, root_timestamp = $8, root_timestamp_tsa = $9, root_timestamped_at = $10 WHERE id = $11 AND status = 'CLOSING'",
+        root,
+        MERKLE_TREE_VERSION,
+        root_signature,
+        signer.fingerprint,
+        spent.root,
+        spent_count,
+        spent_root_signature,
+        root_timestamp.as_ref().map(|stamp| stamp.token.clone()),
+        root_timestamp.as_ref().map(|stamp| stamp.tsa.clone()),
+        root_timestamp.as_ref().map(|stamp| stamp.gen_time),
+        election_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| format!("Failed to seal {}: {}", election_id, e))?;
+    if sealed.rows_affected() == 0 {
+        return Err(format!("Election {} is no longer CLOSING, not sealed", election_id));
+    }
 
-            let root = tree.root;
+    // 7. Publish the final tree head so the log ends at the sealed root
+    if let Err(e) = ballot_log::publish_head(pool, &signer.key, election_id).await {
+        println!("Failed to publish final tree head for {}: {}", election_id, e);
+    }
 
-            // 4. Update Election with Root and set to SEALED
-            let _ = sqlx::query!(
-                "UPDATE elections SET status = 'SEALED', merkle_root = $1 WHERE id = $2",
-                root,
-                election.id
-            )
-            .execute(&*pool)
-            .await;
+    println!("Election {} sealed. Root: {}", election_id, root);
+    Ok(())
+}
 
-            println!("Election {} sealed. Root: {}", election.title, root);
+/// Asks the TSA up to `TSA_ATTEMPTS` times, backing off between attempts
+async fn timestamp_with_retries(tsa: &TsaClient, message: &[u8]) -> Result<RootTimestamp, String> {
+    let mut attempt = 1;
+    loop {
+        match tsa.timestamp_root(message).await {
+            Ok(stamp) => return Ok(stamp),
+            Err(e) if attempt >= TSA_ATTEMPTS => return Err(e),
+            Err(e) => {
+                println!("TSA attempt {} failed: {}", attempt, e);
+                tokio::time::sleep(Duration::from_secs(2u64.pow(attempt))).await;
+                attempt += 1;
+            }
         }
     }
 }
+
+/// Sums the encrypted ballots and stores the total. With a server-held key the
+/// total is decrypted with proofs right away; trustee-held keys wait for partial decryptions.
+async fn tally_election(
+    pool: &PgPool,
+    master_key: &MasterKey,
+    election_id: Uuid,
+    proof_version: i16,
+    form_config: &Value,
+    choices: &[Value],
+) -> Result<(), String> {
+    let layout = ballot::ballot_layout(form_config);
+    // Ballots were validated on submission, so one that no longer parses means the
+    // stored log is damaged; counting the rest would publish an undercount
+    let parsed = choices
+        .iter()
+        .enumerate()
+        .map(|(index, c)| ballot::parse_ballot(c, &layout).map_err(|e| format!("ballot {} does not parse: {}", index, e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let encrypted_tally = ballot::aggregate(&parsed, &layout);
+
+    let decrypted = if trustees::is_threshold(pool, election_id)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        None
+    } else {
+        let secret = keys::encryption_secret(pool, master_key, election_id)
+            .await
+            .map_err(|e| e.to_string())?;
+        let decrypted = ballot::decrypt_tally(
+            &ProofContext::for_version(election_id, proof_version),
+            &secret,
+            &encrypted_tally, parsed.len() as u64, &mut OsRng)
+            .ok_or("tally exceeds the number of ballots")?;
+        Some(serde_json::to_value(&decrypted).map_err(|e| e.to_string())?)
+    };
+
+    sqlx::query!(
+        "INSERT INTO election_tallies (election_id, ballot_count, encrypted_tally, decrypted_tally) VALUES ($1, $2, $3, $4) ON CONFLICT (election_id) DO NOTHING",
+        election_id,
+        parsed.len() as i64,
+        serde_json::to_value(&encrypted_tally).map_err(|e| e.to_string())?,
+        decrypted
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
@@ -0,0 +1,30 @@
+use crate::ballot_log;
+use crate::crypto::smt::SparseMerkleTree;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Every credential spent in the election: nullifiers in `voter_registry` for
+/// elections on the NULLIFIER scheme, blind-signed token hashes otherwise
+pub async fn entries(pool: &PgPool, election_id: Uuid) -> Result<Vec<String>, sqlx::Error> {
+    let rows = sqlx::query_scalar!(
+        r#"
+        SELECT v.nullifier_hash as "entry!" FROM voter_registry v JOIN elections e ON e.id = v.election_id
+        WHERE v.election_id = $1 AND e.credential_scheme = 'NULLIFIER'
+        UNION ALL
+        SELECT s.token_hash FROM spent_credentials s JOIN elections e ON e.id = s.election_id
+        WHERE s.election_id = $1 AND e.credential_scheme <> 'NULLIFIER'
+        "#,
+        election_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Rebuilds the sparse Merkle tree over the spent set
+pub async fn tree(pool: &PgPool, election_id: Uuid) -> Result<SparseMerkleTree, sqlx::Error> {
+    let suite = ballot_log::hash_suite(pool, election_id).await?;
+    let entries = entries(pool, election_id).await?;
+    Ok(SparseMerkleTree::new(suite, entries))
+}
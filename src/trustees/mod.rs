@@ -0,0 +1,628 @@
+use chrono::{DateTime, Utc};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::fmt;
+use uuid::Uuid;
+
+use crate::crypto::{
+    self,
+    ballot::{self, EncryptedBallot, PartialTally},
+    elgamal::{self, ProofContext},
+    threshold::{self, Complaint, Dealing},
+};
+use crate::keys::{self, KeyError, KeyPurpose};
+
+pub const JOINT_KEY_ALGORITHM: &str = "ElGamal-Ristretto255-Threshold";
+
+const DEALING: &str = "DEALING";
+const COMPLAINT: &str = "COMPLAINT";
+const PARTIAL_DECRYPTION: &str = "PARTIAL_DECRYPTION";
+
+/// Complaint window used when the setup request does not pick one
+pub const DEFAULT_COMPLAINT_WINDOW_SECS: u32 = 24 * 60 * 60;
+
+#[derive(Debug)]
+pub enum CeremonyError {
+    NotFound,
+    /// The request does not fit the ceremony's or election's current state
+    Conflict(String),
+    /// The submission is malformed or its proofs do not hold
+    Invalid(String),
+    BadSignature,
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for CeremonyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CeremonyError::NotFound => write!(f, "Trustee ceremony not found"),
+            CeremonyError::Conflict(e) | CeremonyError::Invalid(e) => write!(f, "{}", e),
+            CeremonyError::BadSignature => write!(f, "Signature does not match the trustee's key"),
+            CeremonyError::Database(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for CeremonyError {
+    fn from(e: sqlx::Error) -> Self {
+        CeremonyError::Database(e)
+    }
+}
+
+impl From<KeyError> for CeremonyError {
+    fn from(e: KeyError) -> Self {
+        match e {
+            KeyError::Database(e) => CeremonyError::Database(e),
+            other => CeremonyError::Conflict(other.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewTrustee {
+    pub name: String,
+    pub auth_public_key: String,
+    pub share_public_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trustee {
+    pub trustee_index: u32,
+    pub name: String,
+    pub auth_public_key: String,
+    pub share_public_key: String,
+    pub disqualified: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ceremony {
+    pub election_id: Uuid,
+    pub threshold: u32,
+    pub trustee_count: u32,
+    pub status: String,
+    pub joint_public_key: Option<String>,
+    pub complaint_window_secs: u32,
+    /// When the window after the last dealing closes; finalizing waits for it
+    pub complaints_close_at: Option<DateTime<Utc>>,
+    /// The `elections.proof_version` trustees must prove their messages under
+    pub proof_version: i16,
+    pub trustees: Vec<Trustee>,
+}
+
+impl Ceremony {
+    pub fn proof_context(&self) -> ProofContext {
+        ProofContext::for_version(self.election_id, self.proof_version)
+    }
+}
+
+/// A trustee-signed ceremony message as submitted to the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedSubmission<T> {
+    pub trustee_index: u32,
+    pub payload: T,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptMessage {
+    pub trustee_index: u32,
+    pub kind: String,
+    pub payload: String,
+    pub signature: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Everything needed to re-run the ceremony's checks offline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub ceremony: Ceremony,
+    /// Trustee index -> public verification key of its decryption share, once complete
+    pub verification_keys: BTreeMap<u32, String>,
+    pub messages: Vec<TranscriptMessage>,
+}
+
+/// Where decryption stands after a partial decryption is accepted
+#[derive(Debug, Clone, Serialize)]
+pub struct DecryptionProgress {
+    pub partials_received: u32,
+    pub threshold: u32,
+    pub decrypted: bool,
+}
+
+pub async fn ceremony(pool: &PgPool, election_id: Uuid) -> Result<Ceremony, CeremonyError> {
+    let rec = sqlx::query!(
+        r#"
+        SELECT c.threshold, c.trustee_count, c.status, c.joint_public_key, c.complaint_window_secs, e.proof_version,
+               (SELECT MAX(m.created_at) FROM trustee_messages m WHERE m.election_id = c.election_id AND m.kind = $2) as last_dealing
+        FROM trustee_ceremonies c JOIN elections e ON e.id = c.election_id WHERE c.election_id = $1
+        "#,
+        election_id,
+        DEALING
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(CeremonyError::NotFound)?;
+
+    let trustees = sqlx::query!(
+        "SELECT trustee_index, name, auth_public_key, share_public_key, disqualified FROM trustees WHERE election_id = $1 ORDER BY trustee_index ASC",
+        election_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Ceremony {
+        election_id,
+        threshold: rec.threshold as u32,
+        trustee_count: rec.trustee_count as u32,
+        status: rec.status,
+        joint_public_key: rec.joint_public_key,
+        complaint_window_secs: rec.complaint_window_secs as u32,
+        complaints_close_at: rec
+            .last_dealing
+            .map(|last| last + chrono::Duration::seconds(rec.complaint_window_secs.into())),
+        proof_version: rec.proof_version,
+        trustees: trustees
+            .into_iter()
+            .map(|t| Trustee {
+                trustee_index: t.trustee_index as u32,
+                name: t.name,
+                auth_public_key: t.auth_public_key,
+                share_public_key: t.share_public_key,
+                disqualified: t.disqualified,
+            })
+            .collect(),
+    })
+}
+
+/// Whether the election's decryption key is held by trustees
+pub async fn is_threshold(pool: &PgPool, election_id: Uuid) -> Result<bool, sqlx::Error> {
+    let rec = sqlx::query!(
+        "SELECT COUNT(*) as count FROM trustee_ceremonies WHERE election_id = $1",
+        election_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(rec.count.unwrap_or(0) > 0)
+}
+
+/// Starts a ceremony for a DRAFT election. The server-generated encryption key
+/// is discarded; the election cannot open until the trustees complete the DKG.
+pub async fn setup(
+    pool: &PgPool,
+    election_id: Uuid,
+    threshold: u32,
+    complaint_window_secs: u32,
+    trustees: &[NewTrustee],
+) -> Result<Ceremony, CeremonyError> {
+    if threshold == 0 || threshold as usize > trustees.len() {
+        return Err(CeremonyError::Invalid(
+            "Threshold must be between 1 and the number of trustees".to_string(),
+        ));
+    }
+    if i32::try_from(complaint_window_secs).is_err() {
+        return Err(CeremonyError::Invalid("Complaint window is too long".to_string()));
+    }
+    for trustee in trustees {
+        let auth_key_valid = hex::decode(&trustee.auth_public_key)
+            .ok()
+            .and_then(|b| <[u8; 32]>::try_from(b).ok())
+            .is_some_and(|b| ed25519_dalek::VerifyingKey::from_bytes(&b).is_ok());
+        if !auth_key_valid || elgamal::point_from_hex(&trustee.share_public_key).is_none() {
+            return Err(CeremonyError::Invalid(format!(
+                "Trustee {} has an invalid public key",
+                trustee.name
+            )));
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let election = sqlx::query!(
+        "SELECT status::text as status, ballot_scheme FROM elections WHERE id = $1 FOR UPDATE",
+        election_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or(CeremonyError::NotFound)?;
+
+    if election.status.as_deref() != Some("DRAFT") || election.ballot_scheme == "PLAINTEXT" {
+        return Err(CeremonyError::Conflict(
+            "Trustees can only be appointed for encrypted elections still in DRAFT".to_string(),
+        ));
+    }
+
+    let created = sqlx::query!(
+        "INSERT INTO trustee_ceremonies (election_id, threshold, trustee_count, complaint_window_secs) VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING",
+        election_id,
+        threshold as i32,
+        trustees.len() as i32,
+        complaint_window_secs as i32
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if created.rows_affected() == 0 {
+        return Err(CeremonyError::Conflict(
+            "Election already has a trustee ceremony".to_string(),
+        ));
+    }
+
+    for (i, trustee) in trustees.iter().enumerate() {
+        sqlx::query!(
+            "INSERT INTO trustees (election_id, trustee_index, name, auth_public_key, share_public_key) VALUES ($1, $2, $3, $4, $5)",
+            election_id,
+            i as i32 + 1,
+            trustee.name,
+            trustee.auth_public_key,
+            trustee.share_public_key
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    keys::discard(&mut *tx, election_id, KeyPurpose::Encryption).await?;
+    tx.commit().await?;
+
+    ceremony(pool, election_id).await
+}
+
+/// Checks the trustee's signature and returns the exact payload JSON it covers
+fn authenticate<T: Serialize>(
+    ceremony: &Ceremony,
+    kind: &str,
+    submission: &SignedSubmission<T>,
+) -> Result<(Trustee, String), CeremonyError> {
+    let trustee = ceremony
+        .trustees
+        .iter()
+        .find(|t| t.trustee_index == submission.trustee_index)
+        .ok_or_else(|| CeremonyError::Invalid("Unknown trustee index".to_string()))?;
+
+    let payload = serde_json::to_string(&submission.payload)
+        .map_err(|e| CeremonyError::Invalid(e.to_string()))?;
+    let message = crypto::trustee_message(kind, &ceremony.election_id, trustee.trustee_index, &payload);
+
+    if !crypto::verify_signature(&trustee.auth_public_key, &message, &submission.signature) {
+        return Err(CeremonyError::BadSignature);
+    }
+    Ok((trustee.clone(), payload))
+}
+
+async fn record(
+    pool: &PgPool,
+    election_id: Uuid,
+    trustee_index: u32,
+    kind: &str,
+    payload: &str,
+    signature: &str,
+) -> Result<(), CeremonyError> {
+    let inserted = sqlx::query!(
+        "INSERT INTO trustee_messages (election_id, trustee_index, kind, payload, signature) VALUES ($1, $2, $3, $4, $5) ON CONFLICT DO NOTHING",
+        election_id,
+        trustee_index as i32,
+        kind,
+        payload,
+        signature
+    )
+    .execute(pool)
+    .await?;
+
+    if inserted.rows_affected() == 0 {
+        return Err(CeremonyError::Conflict(format!(
+            "Trustee {} already submitted a {} message",
+            trustee_index, kind
+        )));
+    }
+    Ok(())
+}
+
+async fn messages(
+    pool: &PgPool,
+    election_id: Uuid,
+    kind: &str,
+) -> Result<Vec<TranscriptMessage>, CeremonyError> {
+    let recs = sqlx::query!(
+        "SELECT trustee_index, kind, payload, signature, created_at FROM trustee_messages WHERE election_id = $1 AND kind = $2 ORDER BY created_at ASC",
+        election_id,
+        kind
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(recs
+        .into_iter()
+        .map(|r| TranscriptMessage {
+            trustee_index: r.trustee_index as u32,
+            kind: r.kind,
+            payload: r.payload,
+            signature: r.signature,
+            created_at: r.created_at,
+        })
+        .collect())
+}
+
+/// Dealings of every trustee not disqualified, by dealer index
+async fn qualified_dealings(
+    pool: &PgPool,
+    ceremony: &Ceremony,
+) -> Result<BTreeMap<u32, Dealing>, CeremonyError> {
+    let mut dealings = BTreeMap::new();
+    for message in messages(pool, ceremony.election_id, DEALING).await? {
+        let disqualified = ceremony
+            .trustees
+            .iter()
+            .any(|t| t.trustee_index == message.trustee_index && t.disqualified);
+        if disqualified {
+            continue;
+        }
+
+        let dealing: Dealing = serde_json::from_str(&message.payload)
+            .map_err(|e| CeremonyError::Invalid(format!("Stored dealing is corrupt: {}", e)))?;
+        dealings.insert(message.trustee_index, dealing);
+    }
+    Ok(dealings)
+}
+
+pub async fn submit_dealing(
+    pool: &PgPool,
+    election_id: Uuid,
+    submission: &SignedSubmission<Dealing>,
+) -> Result<(), CeremonyError> {
+    let ceremony = ceremony(pool, election_id).await?;
+    if ceremony.status != "DEALING" {
+        return Err(CeremonyError::Conflict("Dealing phase is over".to_string()));
+    }
+
+    let (trustee, payload) = authenticate(&ceremony, DEALING, submission)?;
+
+    if !threshold::verify_dealing(
+        &ceremony.proof_context(),
+        &submission.payload,
+        ceremony.threshold as usize,
+        ceremony.trustee_count,
+    ) {
+        return Err(CeremonyError::Invalid(
+            "Dealing must commit to a polynomial of degree threshold - 1, prove its constant term and carry one share per trustee".to_string(),
+        ));
+    }
+
+    record(pool, election_id, trustee.trustee_index, DEALING, &payload, &submission.signature).await
+}
+
+/// Upholds a complaint by opening the disputed share. A dealer whose share
+/// does not match its commitments is disqualified from the joint key.
+pub async fn submit_complaint(
+    pool: &PgPool,
+    election_id: Uuid,
+    submission: &SignedSubmission<Complaint>,
+) -> Result<(), CeremonyError> {
+    let ceremony = ceremony(pool, election_id).await?;
+    if ceremony.status != "DEALING" {
+        return Err(CeremonyError::Conflict("Dealing phase is over".to_string()));
+    }
+
+    let (trustee, payload) = authenticate(&ceremony, COMPLAINT, submission)?;
+    let complaint = &submission.payload;
+
+    let dealing = qualified_dealings(pool, &ceremony)
+        .await?
+        .remove(&complaint.against)
+        .ok_or_else(|| {
+            CeremonyError::Conflict("No qualified dealing from that trustee".to_string())
+        })?;
+
+    let recipient_key = elgamal::point_from_hex(&trustee.share_public_key)
+        .ok_or_else(|| CeremonyError::Invalid("Trustee share key is corrupt".to_string()))?;
+
+    if !threshold::complaint_is_valid(&ceremony.proof_context(), complaint, trustee.trustee_index, &recipient_key, &dealing) {
+        return Err(CeremonyError::Invalid(
+            "Complaint does not hold: the disputed share matches the dealer's commitments".to_string(),
+        ));
+    }
+
+    record(pool, election_id, trustee.trustee_index, COMPLAINT, &payload, &submission.signature).await?;
+
+    sqlx::query!(
+        "UPDATE trustees SET disqualified = TRUE WHERE election_id = $1 AND trustee_index = $2",
+        election_id,
+        complaint.against as i32
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Closes the dealing phase and publishes the joint key as the election's encryption key.
+/// Only possible once the complaint window after the last dealing has closed, so no
+/// trustee is cut off from complaining about a share; trustees who never dealt are left out.
+pub async fn finalize(pool: &PgPool, election_id: Uuid) -> Result<Ceremony, CeremonyError> {
+    let ceremony = ceremony(pool, election_id).await?;
+    if ceremony.status != "DEALING" {
+        return Err(CeremonyError::Conflict("Ceremony is already complete".to_string()));
+    }
+    match ceremony.complaints_close_at {
+        Some(close_at) if close_at <= Utc::now() => {}
+        Some(close_at) => {
+            return Err(CeremonyError::Conflict(format!(
+                "Trustees can still complain about the dealings until {}",
+                close_at.to_rfc3339()
+            )))
+        }
+        None => return Err(CeremonyError::Conflict("No trustee has dealt yet".to_string())),
+    }
+
+    let dealings = qualified_dealings(pool, &ceremony).await?;
+    if dealings.len() < ceremony.threshold as usize {
+        return Err(CeremonyError::Conflict(format!(
+            "{} qualified dealings, at least {} needed",
+            dealings.len(),
+            ceremony.threshold
+        )));
+    }
+
+    let joint_key = elgamal::point_to_hex(&threshold::joint_public_key(dealings.values()));
+
+    let mut tx = pool.begin().await?;
+
+    let updated = sqlx::query!(
+        "UPDATE trustee_ceremonies SET status = 'COMPLETE', joint_public_key = $1, completed_at = NOW() WHERE election_id = $2 AND status = 'DEALING'",
+        joint_key,
+        election_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if updated.rows_affected() == 0 {
+        return Err(CeremonyError::Conflict("Ceremony is already complete".to_string()));
+    }
+
+    keys::register_public(
+        &mut *tx,
+        election_id,
+        KeyPurpose::Encryption,
+        JOINT_KEY_ALGORITHM,
+        &joint_key,
+    )
+    .await?;
+    tx.commit().await?;
+
+    self::ceremony(pool, election_id).await
+}
+
+/// Verification keys of every trustee's decryption share
+async fn verification_keys(
+    pool: &PgPool,
+    ceremony: &Ceremony,
+) -> Result<BTreeMap<u32, RistrettoPoint>, CeremonyError> {
+    if ceremony.status != "COMPLETE" {
+        return Ok(BTreeMap::new());
+    }
+
+    let dealings = qualified_dealings(pool, ceremony).await?;
+    Ok(ceremony
+        .trustees
+        .iter()
+        .map(|t| {
+            (
+                t.trustee_index,
+                threshold::verification_key(dealings.values(), t.trustee_index),
+            )
+        })
+        .collect())
+}
+
+/// Accepts a trustee's proven partial decryption of the sealed tally, and
+/// combines the tally once `threshold` trustees have contributed
+pub async fn submit_partial(
+    pool: &PgPool,
+    election_id: Uuid,
+    submission: &SignedSubmission<PartialTally>,
+) -> Result<DecryptionProgress, CeremonyError> {
+    let ceremony = ceremony(pool, election_id).await?;
+    if ceremony.status != "COMPLETE" {
+        return Err(CeremonyError::Conflict("Ceremony is not complete".to_string()));
+    }
+
+    let tally = sqlx::query!(
+        "SELECT ballot_count, encrypted_tally, decrypted_tally IS NOT NULL as \"decrypted!\" FROM election_tallies WHERE election_id = $1",
+        election_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| CeremonyError::Conflict("Election has not been sealed yet".to_string()))?;
+
+    if tally.decrypted {
+        return Err(CeremonyError::Conflict("Tally is already decrypted".to_string()));
+    }
+
+    let encrypted: EncryptedBallot = serde_json::from_value(tally.encrypted_tally)
+        .map_err(|e| CeremonyError::Invalid(format!("Stored tally is corrupt: {}", e)))?;
+
+    let (trustee, payload) = authenticate(&ceremony, PARTIAL_DECRYPTION, submission)?;
+    let verification_keys = verification_keys(pool, &ceremony).await?;
+    let vk = verification_keys[&trustee.trustee_index];
+
+    if !ballot::verify_partial_tally(&ceremony.proof_context(), &vk, trustee.trustee_index, &encrypted, &submission.payload) {
+        return Err(CeremonyError::Invalid(
+            "Partial decryption does not match the sealed tally or the trustee's verification key".to_string(),
+        ));
+    }
+
+    record(
+        pool,
+        election_id,
+        trustee.trustee_index,
+        PARTIAL_DECRYPTION,
+        &payload,
+        &submission.signature,
+    )
+    .await?;
+
+    let partials: Vec<PartialTally> = messages(pool, election_id, PARTIAL_DECRYPTION)
+        .await?
+        .iter()
+        .filter_map(|m| serde_json::from_str(&m.payload).ok())
+        .collect();
+
+    let mut progress = DecryptionProgress {
+        partials_received: partials.len() as u32,
+        threshold: ceremony.threshold,
+        decrypted: false,
+    };
+
+    if partials.len() >= ceremony.threshold as usize {
+        let decrypted = ballot::combine_tally(
+            &encrypted,
+            &partials[..ceremony.threshold as usize],
+            tally.ballot_count as u64,
+        )
+        .ok_or_else(|| CeremonyError::Invalid("Combined tally exceeds the number of ballots".to_string()))?;
+
+        sqlx::query!(
+            "UPDATE election_tallies SET decrypted_tally = $1 WHERE election_id = $2 AND decrypted_tally IS NULL",
+            serde_json::to_value(&decrypted).map_err(|e| CeremonyError::Invalid(e.to_string()))?,
+            election_id
+        )
+        .execute(pool)
+        .await?;
+        progress.decrypted = true;
+    }
+
+    Ok(progress)
+}
+
+/// The full ceremony record for auditors
+pub async fn transcript(pool: &PgPool, election_id: Uuid) -> Result<Transcript, CeremonyError> {
+    let ceremony = ceremony(pool, election_id).await?;
+    let verification_keys = verification_keys(pool, &ceremony)
+        .await?
+        .iter()
+        .map(|(index, vk)| (*index, elgamal::point_to_hex(vk)))
+        .collect();
+
+    let recs = sqlx::query!(
+        "SELECT trustee_index, kind, payload, signature, created_at FROM trustee_messages WHERE election_id = $1 ORDER BY created_at ASC",
+        election_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(Transcript {
+        ceremony,
+        verification_keys,
+        messages: recs
+            .into_iter()
+            .map(|r| TranscriptMessage {
+                trustee_index: r.trustee_index as u32,
+                kind: r.kind,
+                payload: r.payload,
+                signature: r.signature,
+                created_at: r.created_at,
+            })
+            .collect(),
+    })
+}
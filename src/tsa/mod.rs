@@ -0,0 +1,72 @@
+use crate::crypto::timestamp;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+/// RFC 3161 client for the TSA configured with `TSA_URL`
+pub struct TsaClient {
+    url: String,
+    trusted_key: String, // hex SubjectPublicKeyInfo DER, from TSA_PUBLIC_KEY
+    http: reqwest::Client,
+}
+
+/// A verified token over a sealed root
+pub struct RootTimestamp {
+    pub token: String, // hex DER
+    pub tsa: String,
+    pub gen_time: DateTime<Utc>,
+}
+
+impl TsaClient {
+    pub fn new(url: String, trusted_key: String) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .expect("TLS backend is available");
+        TsaClient {
+            url,
+            trusted_key,
+            http,
+        }
+    }
+
+    /// The pinned TSA key every token is checked against
+    pub fn public_key(&self) -> &str {
+        &self.trusted_key
+    }
+
+    /// Requests a token over `sealed_root_message` and checks it before it is stored,
+    /// so a misbehaving TSA cannot leave an unverifiable token on the election
+    pub async fn timestamp_root(&self, sealed_root_message: &[u8]) -> Result<RootTimestamp, String> {
+        let imprint = timestamp::root_imprint(sealed_root_message);
+        let nonce = rand::random::<u64>();
+        let request = timestamp::build_request(&imprint, nonce)?;
+
+        let response = self
+            .http
+            .post(&self.url)
+            .header("Content-Type", "application/timestamp-query")
+            .body(request)
+            .send()
+            .await
+            .map_err(|e| format!("TSA request failed: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("TSA answered {}", response.status()));
+        }
+        let body = response
+            .bytes()
+            .await
+            .map_err(|e| format!("TSA response could not be read: {}", e))?;
+
+        let token = timestamp::parse_response(&body)?;
+        let info = timestamp::verify_token(&token, &imprint, &self.trusted_key)?;
+        if timestamp::token_nonce(&token) != Some(nonce) {
+            return Err("TSA token does not echo the request nonce".to_string());
+        }
+
+        Ok(RootTimestamp {
+            token: hex::encode(token),
+            tsa: self.url.clone(),
+            gen_time: info.gen_time,
+        })
+    }
+}
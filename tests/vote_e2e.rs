@@ -0,0 +1,289 @@
+//! Casts a ballot on an election created with the default schemes, the way the
+//! web ballot page does, against a real server process and database.
+//!
+//! Needs DATABASE_URL (or a `.env`) and the server secrets (MASTER_KEY,
+//! NULLIFIER_SECRET, WHITELIST_PEPPER, AUDIT_SIGNING_KEY) of that database, so
+//! the tests are ignored by default; run them with `cargo test -- --ignored`.
+
+use serde_json::{json, Value};
+use curve25519_dalek::ristretto::RistrettoPoint;
+use solesigner::crypto::credential::{self, Credential};
+use solesigner::crypto::{ballot, elgamal, elgamal::ProofContext};
+use solesigner_verify::receipt::{verify_receipt, BoardSnapshot, VoteReceipt};
+use rand::rngs::OsRng;
+use std::collections::BTreeMap;
+use std::env;
+use std::net::TcpListener;
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+use uuid::Uuid;
+
+const SECRETS: [&str; 4] = ["MASTER_KEY", "NULLIFIER_SECRET", "WHITELIST_PEPPER", "AUDIT_SIGNING_KEY"];
+
+struct Server {
+    process: Child,
+    url: String,
+    http: reqwest::Client,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+impl Server {
+    async fn start() -> Server {
+        dotenvy::dotenv().ok();
+        if let Some(missing) = ["DATABASE_URL"].iter().chain(&SECRETS).find(|name| env::var(name).is_err()) {
+            panic!("the end-to-end tests need {} to be set", missing);
+        }
+
+        let port = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("a free port")
+            .port();
+        let process = Command::new(env!("CARGO_BIN_EXE_solesigner"))
+            .env("PORT", port.to_string())
+            .env_remove("TSA_URL")
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("server binary starts");
+        let server = Server {
+            process,
+            url: format!("http://127.0.0.1:{}", port),
+            http: reqwest::Client::new(),
+        };
+
+        for _ in 0..100 {
+            if server.http.get(format!("{}/elections", server.url)).send().await.is_ok() {
+                return server;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("server did not start listening on port {}", port);
+    }
+
+    async fn request(&self, method: reqwest::Method, path: &str, token: Option<&str>, body: Option<Value>) -> (u16, String) {
+        let mut request = self.http.request(method, format!("{}{}", self.url, path));
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(body) = body {
+            request = request.header("Content-Type", "application/json").body(body.to_string());
+        }
+        let response = request.send().await.expect("request is answered");
+        let status = response.status().as_u16();
+        (status, response.text().await.expect("body is readable"))
+    }
+
+    async fn post(&self, path: &str, token: Option<&str>, body: Value) -> Value {
+        let (status, text) = self.request(reqwest::Method::POST, path, token, Some(body)).await;
+        assert!((200..300).contains(&status), "POST {} answered {}: {}", path, status, text);
+        serde_json::from_str(&text).unwrap_or(Value::Null)
+    }
+
+    async fn get(&self, path: &str) -> Value {
+        let (status, text) = self.request(reqwest::Method::GET, path, None, None).await;
+        assert_eq!(status, 200, "GET {} answered {}: {}", path, status, text);
+        serde_json::from_str(&text).expect("response is JSON")
+    }
+
+    /// Registers a fresh admin and returns its token
+    async fn admin_token(&self) -> String {
+        let credentials = json!({ "username": format!("e2e-{}", Uuid::new_v4()), "password": "e2e-password" });
+        self.post("/auth/register", None, credentials.clone()).await;
+        let login = self.post("/auth/login", None, credentials).await;
+        login["token"].as_str().expect("login returns a token").to_string()
+    }
+
+    /// Creates a public election with one radio question; `schemes` overrides the defaults
+    async fn create_election(&self, token: &str, schemes: Value) -> String {
+        let mut request = json!({
+            "title": "End-to-end",
+            "form_config": { "questions": [{ "id": "q1", "text": "Q", "type": "radio", "options": ["A", "B"] }] },
+            "start_date": "2026-01-01T00:00:00Z",
+            "end_date": "2099-01-01T00:00:00Z",
+            "access_type": "PUBLIC",
+        });
+        if let (Some(request), Some(schemes)) = (request.as_object_mut(), schemes.as_object()) {
+            request.extend(schemes.clone());
+        }
+        let created = self.post("/elections/create", Some(token), request).await;
+        created["id"].as_str().expect("create returns an id").to_string()
+    }
+
+    /// Validates a new voter's identity like the ballot page, with `blinded_token` if given
+    async fn validate_identity(&self, election_id: &str, blinded_token: Option<&str>) -> Value {
+        let mut identity = json!({
+            "election_id": election_id,
+            "selfie_base64": "",
+            "document_base64": "",
+            "latitude": 0.0,
+            "longitude": 0.0,
+            "document_number": Uuid::new_v4().to_string(),
+        });
+        if let Some(blinded_token) = blinded_token {
+            identity["blinded_token"] = json!(blinded_token);
+        }
+        self.post("/vote/validate-identity", None, identity).await
+    }
+
+    /// A new voter's nullifier, on an election that uses them
+    async fn nullifier(&self, election_id: &str) -> String {
+        let identity = self.validate_identity(election_id, None).await;
+        identity["nullifier"].as_str().expect("identity validation returns a nullifier").to_string()
+    }
+
+    /// A new voter's credential, blinded and unblinded the way `blindToken` and
+    /// `finalizeCredential` do it on the ballot page
+    async fn credential(&self, election: &Value) -> Credential {
+        let public_key = election["credential_public_key"].as_str().expect("the election has a credential key");
+        let blinding = credential::blind(public_key).expect("the token blinds");
+        let identity = self
+            .validate_identity(election["id"].as_str().unwrap(), Some(&blinding.blinded_token))
+            .await;
+        assert!(identity["nullifier"].is_null(), "the identity nullifier never reaches the voter");
+        credential::finalize(
+            identity["credential_public_key"].as_str().expect("identity validation returns the key"),
+            &blinding,
+            identity["blind_signature"].as_str().expect("identity validation returns a blind signature"),
+        )
+        .expect("the blind signature unblinds")
+    }
+}
+
+/// The election's proof context and key, as `encryptBallot` takes them from `GET /elections/:id`
+fn encryption_context(election: &Value) -> (ProofContext, RistrettoPoint) {
+    let pk = election["encryption_public_key"]
+        .as_str()
+        .and_then(elgamal::point_from_hex)
+        .expect("the election has an encryption key");
+    let context = ProofContext::for_version(
+        election["id"].as_str().and_then(|id| id.parse().ok()).expect("the election has an id"),
+        election["proof_version"].as_i64().expect("the election has a proof version") as i16,
+    );
+    (context, pk)
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and the server secrets"]
+async fn a_default_election_accepts_a_ballot_from_the_web_page() {
+    let server = Server::start().await;
+
+    // An admin creates an election without choosing any scheme
+    let token = server.admin_token().await;
+    let token = token.as_str();
+    let election_id = server.create_election(token, Value::Null).await;
+
+    let election = server.get(&format!("/elections/{}", election_id)).await;
+    assert_eq!(election["ballot_scheme"], "ELGAMAL_RISTRETTO255");
+    assert_eq!(election["credential_scheme"], credential::CREDENTIAL_SCHEME);
+    server.post(&format!("/elections/{}/start", election_id), Some(token), Value::Null).await;
+
+    // The voter goes through the same requests as frontend/app/vote/[election_id]/page.tsx,
+    // which blinds its credential and encrypts the choices with solesigner-verify
+    let credential = server.credential(&election).await;
+    let (context, pk) = encryption_context(&election);
+    let layout = ballot::ballot_layout(&election["form_config"]);
+    let selections = BTreeMap::from([("q1".to_string(), vec!["A".to_string()])]);
+
+    let vote = |request_id: Uuid| {
+        let (choices, proofs, _) = ballot::encrypt_ballot(&context, &pk, &layout, &selections, &mut OsRng).unwrap();
+        json!({
+            "election_id": election_id,
+            "choices": choices,
+            "proofs": proofs,
+            "credential": credential,
+            "request_id": request_id,
+        })
+    };
+    let receipt = server.post("/vote/submit", None, vote(Uuid::new_v4())).await;
+    let receipt: VoteReceipt = serde_json::from_value(receipt).expect("submit returns a receipt");
+    let board = BoardSnapshot {
+        election_id: Some(receipt.election_id),
+        ..Default::default()
+    };
+    verify_receipt(&receipt, &board, |_| {}).expect("the receipt verifies");
+
+    // The credential is spent
+    let (status, _) = server
+        .request(reqwest::Method::POST, "/vote/submit", None, Some(vote(Uuid::new_v4())))
+        .await;
+    assert_eq!(status, 409);
+
+    // Sealing counts the ballot
+    server.post(&format!("/elections/{}/close", election_id), Some(token), Value::Null).await;
+    let results = server.get(&format!("/elections/{}/results", election_id)).await;
+    assert_eq!(results["totals"]["q1"]["A"], 1);
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL and the server secrets"]
+async fn only_the_committing_voter_can_cast_or_challenge_a_ballot() {
+    let server = Server::start().await;
+
+    let token = server.admin_token().await;
+    let election_id = server
+        .create_election(&token, json!({ "credential_scheme": "NULLIFIER" }))
+        .await;
+    server.post(&format!("/elections/{}/start", election_id), Some(&token), Value::Null).await;
+
+    let election = server.get(&format!("/elections/{}", election_id)).await;
+    let (context, pk) = encryption_context(&election);
+    let layout = ballot::ballot_layout(&election["form_config"]);
+    let selections = BTreeMap::from([("q1".to_string(), vec!["A".to_string()])]);
+
+    let voter = server.nullifier(&election_id).await;
+    let other_voter = server.nullifier(&election_id).await;
+    let commit = |nullifier: &str| {
+        let (choices, proofs, randomness) = ballot::encrypt_ballot(&context, &pk, &layout, &selections, &mut OsRng).unwrap();
+        let request = json!({
+            "election_id": election_id,
+            "choices": choices,
+            "proofs": proofs,
+            "nullifier": nullifier,
+        });
+        (request, randomness)
+    };
+
+    // A commitment without a credential is refused
+    let (mut request, _) = commit(&voter);
+    request.as_object_mut().unwrap().remove("nullifier");
+    let (status, _) = server.request(reqwest::Method::POST, "/vote/commit", None, Some(request)).await;
+    assert_eq!(status, 422);
+
+    // Another voter can neither challenge nor cast the voter's committed ballot
+    let (request, randomness) = commit(&voter);
+    let committed = server.post("/vote/commit", None, request).await;
+    let commitment_id = committed["commitment_id"].as_str().expect("commit returns an id");
+    let challenge = |nullifier: &str| {
+        json!({ "commitment_id": commitment_id, "randomness": randomness, "nullifier": nullifier })
+    };
+    let cast = |nullifier: &str| json!({ "commitment_id": commitment_id, "nullifier": nullifier });
+    let (status, _) = server
+        .request(reqwest::Method::POST, "/vote/challenge", None, Some(challenge(&other_voter)))
+        .await;
+    assert_eq!(status, 403);
+    let (status, _) = server.request(reqwest::Method::POST, "/vote/cast", None, Some(cast(&other_voter))).await;
+    assert_eq!(status, 403);
+    let (status, _) = server
+        .request(reqwest::Method::POST, "/vote/cast", None, Some(json!({ "commitment_id": commitment_id })))
+        .await;
+    assert_eq!(status, 422);
+
+    // The voter who committed it can, and the other voter's credential is still unspent
+    let receipt = server.post("/vote/cast", None, cast(&voter)).await;
+    assert_eq!(receipt["ballot_hash"], committed["ballot_hash"]);
+    let (request, randomness) = commit(&other_voter);
+    let committed = server.post("/vote/commit", None, request).await;
+    let opened = server
+        .post(
+            "/vote/challenge",
+            None,
+            json!({ "commitment_id": committed["commitment_id"], "randomness": randomness, "nullifier": other_voter }),
+        )
+        .await;
+    assert_eq!(opened["selections"]["q1"], json!(["A"]));
+}
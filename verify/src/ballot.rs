@@ -0,0 +1,665 @@
+use curve25519_dalek::{constants::RISTRETTO_BASEPOINT_POINT as G, ristretto::RistrettoPoint, scalar::Scalar};
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::elgamal::{self, Ciphertext, DisjunctiveProof, DleqProof, ProofContext};
+use super::threshold::{self, PartialDecryption};
+
+/// Question id -> option -> ciphertext. Used both for single ballots (each
+/// entry encrypts 0 or 1) and for the homomorphic sum of all ballots.
+pub type EncryptedBallot = BTreeMap<String, BTreeMap<String, Ciphertext>>;
+
+/// A question that can be tallied, with the number of options a voter may select
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Question {
+    pub id: String,
+    pub options: Vec<String>,
+    pub min_selections: u64,
+    pub max_selections: u64,
+}
+
+impl Question {
+    /// Every total the question's selections may add up to
+    pub fn allowed_totals(&self) -> Vec<u64> {
+        (self.min_selections..=self.max_selections).collect()
+    }
+}
+
+/// Questions from `form_config` that can be tallied. `min_selections` defaults to 0;
+/// `max_selections` defaults to 1 for radio questions and to every option otherwise.
+pub fn ballot_layout(form_config: &Value) -> Vec<Question> {
+    form_config["questions"]
+        .as_array()
+        .map(|questions| {
+            questions
+                .iter()
+                .filter_map(|q| {
+                    let id = q["id"].as_str()?.to_string();
+                    let options: Vec<String> = q["options"]
+                        .as_array()?
+                        .iter()
+                        .filter_map(|o| o.as_str().map(str::to_string))
+                        .collect();
+                    if options.is_empty() {
+                        return None;
+                    }
+
+                    let default_max = if q["type"] == "radio" { 1 } else { options.len() as u64 };
+                    let max_selections = q["max_selections"]
+                        .as_u64()
+                        .unwrap_or(default_max)
+                        .min(options.len() as u64);
+                    let min_selections = q["min_selections"].as_u64().unwrap_or(0);
+
+                    Some(Question {
+                        id,
+                        options,
+                        min_selections,
+                        max_selections,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Checks that every question `ballot_layout` reads has `min_selections` <=
+/// `max_selections` <= its number of options, as written in `form_config`
+/// (`ballot_layout` itself clamps `max_selections` to the options)
+pub fn validate_layout(form_config: &Value) -> Result<(), String> {
+    let questions = form_config["questions"].as_array().map(Vec::as_slice).unwrap_or_default();
+    for q in questions {
+        let (Some(id), Some(options)) = (q["id"].as_str(), q["options"].as_array()) else {
+            continue;
+        };
+        let limit = |name: &str| match &q[name] {
+            Value::Null => Ok(None),
+            value => value
+                .as_u64()
+                .map(Some)
+                .ok_or_else(|| format!("Question {}: {} must be a non-negative integer", id, name)),
+        };
+        let (min_selections, max_selections) = (limit("min_selections")?, limit("max_selections")?);
+
+        if let Some(max) = max_selections.filter(|&max| max > options.len() as u64) {
+            return Err(format!("Question {}: max_selections {} exceeds its {} options", id, max, options.len()));
+        }
+        let max = max_selections.unwrap_or(if q["type"] == "radio" { 1 } else { options.len() as u64 });
+        if let Some(min) = min_selections.filter(|&min| min > max) {
+            return Err(format!("Question {}: min_selections {} exceeds max_selections {}", id, min, max));
+        }
+    }
+    Ok(())
+}
+
+/// Parses an encrypted ballot and checks it has exactly one ciphertext per option of every question
+pub fn parse_ballot(choices: &Value, layout: &[Question]) -> Result<EncryptedBallot, String> {
+    let ballot: EncryptedBallot = serde_json::from_value(choices.clone())
+        .map_err(|e| format!("Malformed encrypted ballot: {}", e))?;
+
+    if ballot.len() != layout.len() {
+        return Err("Ballot must answer every question exactly once".to_string());
+    }
+
+    for question in layout {
+        let answers = ballot
+            .get(&question.id)
+            .ok_or_else(|| format!("Missing question {}", question.id))?;
+
+        if answers.len() != question.options.len()
+            || question.options.iter().any(|o| !answers.contains_key(o))
+        {
+            return Err(format!(
+                "Question {} must carry one ciphertext per option",
+                question.id
+            ));
+        }
+    }
+
+    Ok(ballot)
+}
+
+const SELECTION_DOMAIN: &str = "solesigner-selection-v1";
+const SELECTION_TOTAL_DOMAIN: &str = "solesigner-selection-total-v1";
+
+/// Well-formedness proofs for one question: each selection encrypts 0 or 1,
+/// and their homomorphic sum lies within the question's selection limits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuestionProof {
+    pub selections: BTreeMap<String, DisjunctiveProof>,
+    pub total: DisjunctiveProof,
+}
+
+/// Question id -> proofs, stored next to the ballot so anyone can re-verify it
+pub type BallotProofs = BTreeMap<String, QuestionProof>;
+
+/// The randomness `r` a selection was encrypted with. Revealing it opens the ciphertext.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Nonce(#[serde(with = "elgamal::scalar_hex")] pub Scalar);
+
+/// Question id -> option -> randomness, kept by the encrypting device until the
+/// ballot is cast or challenged
+pub type BallotRandomness = BTreeMap<String, BTreeMap<String, Nonce>>;
+
+/// Why a ballot's well-formedness proofs were rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProofError {
+    Malformed(String),
+    MissingProof(String),
+    InvalidSelection { question: String, option: String },
+    InvalidTotal { question: String, min: u64, max: u64 },
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofError::Malformed(e) => write!(f, "Malformed ballot proofs: {}", e),
+            ProofError::MissingProof(question) => {
+                write!(f, "Missing well-formedness proof for question {}", question)
+            }
+            ProofError::InvalidSelection { question, option } => write!(
+                f,
+                "Selection {} of question {} is not proven to encrypt 0 or 1",
+                option, question
+            ),
+            ProofError::InvalidTotal { question, min, max } => write!(
+                f,
+                "Question {} is not proven to select between {} and {} options",
+                question, min, max
+            ),
+        }
+    }
+}
+
+pub fn parse_proofs(proofs: &Value) -> Result<BallotProofs, ProofError> {
+    serde_json::from_value(proofs.clone()).map_err(|e| ProofError::Malformed(e.to_string()))
+}
+
+/// Checks every selection is 0 or 1 and every question total is within its limits
+pub fn verify_ballot(
+    context: &ProofContext,
+    pk: &RistrettoPoint,
+    ballot: &EncryptedBallot,
+    proofs: &BallotProofs,
+    layout: &[Question],
+) -> Result<(), ProofError> {
+    if proofs.len() != layout.len() {
+        return Err(ProofError::Malformed(
+            "expected exactly one proof per question".to_string(),
+        ));
+    }
+
+    for question in layout {
+        let (Some(answers), Some(proof)) = (ballot.get(&question.id), proofs.get(&question.id))
+        else {
+            return Err(ProofError::MissingProof(question.id.clone()));
+        };
+
+        if proof.selections.len() != answers.len() {
+            return Err(ProofError::MissingProof(question.id.clone()));
+        }
+        for (option, ct) in answers {
+            let valid = proof.selections.get(option).is_some_and(|p| {
+                elgamal::verify_disjunctive(SELECTION_DOMAIN, context, pk, ct, &[0, 1], p)
+            });
+            if !valid {
+                return Err(ProofError::InvalidSelection {
+                    question: question.id.clone(),
+                    option: option.clone(),
+                });
+            }
+        }
+
+        let total = answers
+            .values()
+            .fold(Ciphertext::zero(), |acc, ct| acc.add(ct));
+        if !elgamal::verify_disjunctive(
+            SELECTION_TOTAL_DOMAIN,
+            context,
+            pk,
+            &total,
+            &question.allowed_totals(),
+            &proof.total,
+        ) {
+            return Err(ProofError::InvalidTotal {
+                question: question.id.clone(),
+                min: question.min_selections,
+                max: question.max_selections,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Client side: encrypts the chosen options of every question with well-formedness proofs.
+/// `selections` maps question id -> chosen options.
+pub fn encrypt_ballot<R: RngCore + CryptoRng>(
+    context: &ProofContext,
+    pk: &RistrettoPoint,
+    layout: &[Question],
+    selections: &BTreeMap<String, Vec<String>>,
+    rng: &mut R,
+) -> Result<(EncryptedBallot, BallotProofs, BallotRandomness), String> {
+    let mut ballot = EncryptedBallot::new();
+    let mut proofs = BallotProofs::new();
+    let mut randomness = BallotRandomness::new();
+
+    for question in layout {
+        let chosen = selections.get(&question.id).map(Vec::as_slice).unwrap_or(&[]);
+        if let Some(unknown) = chosen.iter().find(|o| !question.options.contains(o)) {
+            return Err(format!("Question {} has no option {}", question.id, unknown));
+        }
+
+        let mut answers = BTreeMap::new();
+        let mut selection_proofs = BTreeMap::new();
+        let mut nonces = BTreeMap::new();
+        let mut total_r = Scalar::ZERO;
+        for option in &question.options {
+            let m = chosen.contains(option) as u64;
+            let (ct, r) = elgamal::encrypt(pk, m, rng);
+            let proof = elgamal::prove_disjunctive(SELECTION_DOMAIN, context, pk, &r, m, &[0, 1], rng)
+                .expect("selections are 0 or 1");
+            total_r += r;
+            answers.insert(option.clone(), ct);
+            selection_proofs.insert(option.clone(), proof);
+            nonces.insert(option.clone(), Nonce(r));
+        }
+
+        // The sum of the selections is the encryption of `count` under `total_r`
+        let count = question.options.iter().filter(|o| chosen.contains(o)).count() as u64;
+        let total_proof = elgamal::prove_disjunctive(
+            SELECTION_TOTAL_DOMAIN,
+            context,
+            pk,
+            &total_r,
+            count,
+            &question.allowed_totals(),
+            rng,
+        )
+        .ok_or_else(|| {
+            format!(
+                "Question {} takes between {} and {} selections",
+                question.id, question.min_selections, question.max_selections
+            )
+        })?;
+
+        ballot.insert(question.id.clone(), answers);
+        randomness.insert(question.id.clone(), nonces);
+        proofs.insert(
+            question.id.clone(),
+            QuestionProof {
+                selections: selection_proofs,
+                total: total_proof,
+            },
+        );
+    }
+
+    Ok((ballot, proofs, randomness))
+}
+
+/// Opens a challenged ballot with its revealed randomness, returning the options
+/// selected in each question. Fails unless every ciphertext is exactly an
+/// encryption of 0 or 1 under that randomness.
+pub fn open_ballot(
+    pk: &RistrettoPoint,
+    ballot: &EncryptedBallot,
+    randomness: &BallotRandomness,
+    layout: &[Question],
+) -> Result<BTreeMap<String, Vec<String>>, String> {
+    let mut selections = BTreeMap::new();
+
+    for question in layout {
+        let mut chosen = Vec::new();
+        for option in &question.options {
+            let ct = ballot
+                .get(&question.id)
+                .and_then(|answers| answers.get(option))
+                .ok_or_else(|| format!("Ballot has no selection {} for question {}", option, question.id))?;
+            let r = randomness
+                .get(&question.id)
+                .and_then(|nonces| nonces.get(option))
+                .ok_or_else(|| format!("Missing randomness for option {} of question {}", option, question.id))?;
+
+            match (0..=1).find(|m| elgamal::encrypt_with(pk, *m, &r.0) == *ct) {
+                Some(1) => chosen.push(option.clone()),
+                Some(_) => {}
+                None => {
+                    return Err(format!(
+                        "Randomness does not open option {} of question {}",
+                        option, question.id
+                    ))
+                }
+            }
+        }
+        selections.insert(question.id.clone(), chosen);
+    }
+
+    Ok(selections)
+}
+
+/// Homomorphically sums ballots into one ciphertext per option
+pub fn aggregate<'a>(
+    ballots: impl IntoIterator<Item = &'a EncryptedBallot>,
+    layout: &[Question],
+) -> EncryptedBallot {
+    let mut tally: EncryptedBallot = layout
+        .iter()
+        .map(|question| {
+            (
+                question.id.clone(),
+                question
+                    .options
+                    .iter()
+                    .map(|o| (o.clone(), Ciphertext::zero()))
+                    .collect(),
+            )
+        })
+        .collect();
+
+    for ballot in ballots {
+        for (question_id, answers) in ballot {
+            let Some(totals) = tally.get_mut(question_id) else {
+                continue;
+            };
+            for (option, ct) in answers {
+                if let Some(total) = totals.get_mut(option) {
+                    *total = total.add(ct);
+                }
+            }
+        }
+    }
+
+    tally
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TallyEntry {
+    pub count: u64,
+    pub ciphertext: Ciphertext,
+    /// Decryption proof under a single election key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof: Option<DleqProof>,
+    /// Trustee partial decryptions that were combined, for threshold keys
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub partials: Vec<PartialDecryption>,
+}
+
+/// Question id -> option -> decrypted count with its proof of correct decryption
+pub type DecryptedTally = BTreeMap<String, BTreeMap<String, TallyEntry>>;
+
+/// Decrypts every aggregate, assuming no count exceeds `max_count` (the number of ballots)
+pub fn decrypt_tally<R: RngCore + CryptoRng>(
+    context: &ProofContext,
+    secret: &Scalar,
+    tally: &EncryptedBallot,
+    max_count: u64,
+    rng: &mut R,
+) -> Option<DecryptedTally> {
+    let mut result = DecryptedTally::new();
+
+    for (question_id, totals) in tally {
+        let mut entries = BTreeMap::new();
+        for (option, ct) in totals {
+            let (count, proof) = elgamal::prove_decryption(context, secret, ct, max_count, rng)?;
+            entries.insert(
+                option.clone(),
+                TallyEntry {
+                    count,
+                    ciphertext: *ct,
+                    proof: Some(proof),
+                    partials: vec![],
+                },
+            );
+        }
+        result.insert(question_id.clone(), entries);
+    }
+
+    Some(result)
+}
+
+/// Checks every decryption proof of a published tally against the election public key
+pub fn verify_tally(context: &ProofContext, pk: &RistrettoPoint, tally: &DecryptedTally) -> bool {
+    tally.values().flat_map(|totals| totals.values()).all(|entry| {
+        entry.proof.as_ref().is_some_and(|proof| {
+            elgamal::verify_decryption(context, pk, &entry.ciphertext, entry.count, proof)
+        })
+    })
+}
+
+/// One trustee's partial decryption of every aggregate, question id -> option -> share
+pub type PartialTally = BTreeMap<String, BTreeMap<String, PartialDecryption>>;
+
+pub fn partial_decrypt_tally<R: RngCore + CryptoRng>(
+    context: &ProofContext,
+    trustee_index: u32,
+    key_share: &Scalar,
+    tally: &EncryptedBallot,
+    rng: &mut R,
+) -> PartialTally {
+    tally
+        .iter()
+        .map(|(question_id, totals)| {
+            (
+                question_id.clone(),
+                totals
+                    .iter()
+                    .map(|(option, ct)| {
+                        (
+                            option.clone(),
+                            threshold::partial_decrypt(context, trustee_index, key_share, ct, rng),
+                        )
+                    })
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+/// Checks a trustee's partial tally covers exactly `tally` and every share is proven
+pub fn verify_partial_tally(
+    context: &ProofContext,
+    verification_key: &RistrettoPoint,
+    trustee_index: u32,
+    tally: &EncryptedBallot,
+    partial: &PartialTally,
+) -> bool {
+    partial.len() == tally.len()
+        && tally.iter().all(|(question_id, totals)| {
+            partial.get(question_id).is_some_and(|shares| {
+                shares.len() == totals.len()
+                    && totals.iter().all(|(option, ct)| {
+                        shares.get(option).is_some_and(|share| {
+                            share.trustee_index == trustee_index
+                                && threshold::verify_partial(context, verification_key, ct, share)
+                        })
+                    })
+            })
+        })
+}
+
+/// Combines verified partial tallies from distinct trustees (at least the threshold)
+pub fn combine_tally(
+    tally: &EncryptedBallot,
+    partials: &[PartialTally],
+    max_count: u64,
+) -> Option<DecryptedTally> {
+    let mut result = DecryptedTally::new();
+
+    for (question_id, totals) in tally {
+        let mut entries = BTreeMap::new();
+        for (option, ct) in totals {
+            let shares: Vec<PartialDecryption> = partials
+                .iter()
+                .map(|p| p.get(question_id)?.get(option).cloned())
+                .collect::<Option<_>>()?;
+            let count = elgamal::discrete_log(&threshold::combine(ct, &shares), max_count)?;
+            entries.insert(
+                option.clone(),
+                TallyEntry {
+                    count,
+                    ciphertext: *ct,
+                    proof: None,
+                    partials: shares,
+                },
+            );
+        }
+        result.insert(question_id.clone(), entries);
+    }
+
+    Some(result)
+}
+
+/// Checks a threshold tally: every entry combines at least `required` proven
+/// partial decryptions from distinct trustees into its published count
+pub fn verify_threshold_tally(
+    context: &ProofContext,
+    verification_keys: &BTreeMap<u32, RistrettoPoint>,
+    required: usize,
+    tally: &DecryptedTally,
+) -> bool {
+    tally.values().flat_map(|totals| totals.values()).all(|entry| {
+        let mut indices: Vec<u32> = entry.partials.iter().map(|p| p.trustee_index).collect();
+        indices.sort_unstable();
+        indices.dedup();
+
+        indices.len() == entry.partials.len()
+            && indices.len() >= required
+            && entry.partials.iter().all(|p| {
+                verification_keys
+                    .get(&p.trustee_index)
+                    .is_some_and(|vk| threshold::verify_partial(context, vk, &entry.ciphertext, p))
+            })
+            && threshold::combine(&entry.ciphertext, &entry.partials)
+                == Scalar::from(entry.count) * G
+    })
+}
+
+/// Plain counts of a decrypted tally, question id -> option -> count
+pub fn tally_counts(tally: &DecryptedTally) -> BTreeMap<String, BTreeMap<String, u64>> {
+    tally
+        .iter()
+        .map(|(question_id, totals)| {
+            (
+                question_id.clone(),
+                totals
+                    .iter()
+                    .map(|(option, entry)| (option.clone(), entry.count))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    fn layout() -> Vec<Question> {
+        ballot_layout(&json!({
+            "questions": [
+                { "id": "q1", "type": "radio", "options": ["A", "B"] },
+                { "id": "q2", "type": "checkbox", "options": ["X", "Y", "Z"], "max_selections": 2 }
+            ]
+        }))
+    }
+
+    fn selections(q1: &str, q2: &[&str]) -> BTreeMap<String, Vec<String>> {
+        BTreeMap::from([
+            ("q1".to_string(), vec![q1.to_string()]),
+            ("q2".to_string(), q2.iter().map(|o| o.to_string()).collect()),
+        ])
+    }
+
+    #[test]
+    fn encrypted_ballots_verify_and_decrypt_to_the_tally() {
+        let context = ProofContext::new(Uuid::new_v4());
+        let secret = elgamal::random_scalar(&mut OsRng);
+        let pk = elgamal::public_key(&secret);
+        let layout = layout();
+
+        let ballots: Vec<EncryptedBallot> = [selections("A", &["X", "Y"]), selections("B", &["Y"]), selections("A", &[])]
+            .iter()
+            .map(|chosen| {
+                let (encrypted, proofs, randomness) = encrypt_ballot(&context, &pk, &layout, chosen, &mut OsRng).unwrap();
+                verify_ballot(&context, &pk, &encrypted, &proofs, &layout).unwrap();
+                assert_eq!(open_ballot(&pk, &encrypted, &randomness, &layout).unwrap(), *chosen);
+                encrypted
+            })
+            .collect();
+
+        let tally = decrypt_tally(&context, &secret, &aggregate(&ballots, &layout), ballots.len() as u64, &mut OsRng).unwrap();
+        assert!(verify_tally(&context, &pk, &tally));
+        assert_eq!(
+            tally_counts(&tally),
+            BTreeMap::from([
+                ("q1".to_string(), BTreeMap::from([("A".to_string(), 2), ("B".to_string(), 1)])),
+                (
+                    "q2".to_string(),
+                    BTreeMap::from([("X".to_string(), 1), ("Y".to_string(), 2), ("Z".to_string(), 0)])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn tampered_or_replayed_proofs_are_rejected() {
+        let context = ProofContext::new(Uuid::new_v4());
+        let secret = elgamal::random_scalar(&mut OsRng);
+        let pk = elgamal::public_key(&secret);
+        let layout = layout();
+        let (encrypted, proofs, _) = encrypt_ballot(&context, &pk, &layout, &selections("A", &["Z"]), &mut OsRng).unwrap();
+
+        let mut tampered = proofs.clone();
+        tampered.get_mut("q1").unwrap().selections.get_mut("A").unwrap().branches[0].response += Scalar::ONE;
+        assert!(verify_ballot(&context, &pk, &encrypted, &tampered, &layout).is_err());
+
+        let mut tampered = proofs.clone();
+        tampered.get_mut("q2").unwrap().total.branches[1].challenge += Scalar::ONE;
+        assert!(verify_ballot(&context, &pk, &encrypted, &tampered, &layout).is_err());
+
+        // The same proofs do not carry over to another election or another key
+        let other_election = ProofContext::new(Uuid::new_v4());
+        assert!(verify_ballot(&other_election, &pk, &encrypted, &proofs, &layout).is_err());
+        let other_key = elgamal::public_key(&elgamal::random_scalar(&mut OsRng));
+        assert!(verify_ballot(&context, &other_key, &encrypted, &proofs, &layout).is_err());
+
+        // A decryption proof no longer holds once the published count is changed
+        let mut tally = decrypt_tally(&context, &secret, &aggregate([&encrypted], &layout), 1, &mut OsRng).unwrap();
+        assert!(verify_tally(&context, &pk, &tally));
+        assert!(!verify_tally(&other_election, &pk, &tally));
+        tally.get_mut("q1").unwrap().get_mut("B").unwrap().count = 1;
+        assert!(!verify_tally(&context, &pk, &tally));
+    }
+
+    #[test]
+    fn selection_limits_must_fit_the_options() {
+        let config = |question: Value| json!({ "questions": [question] });
+        let checkbox = |limits: Value| {
+            let mut question = json!({ "id": "q1", "type": "checkbox", "options": ["X", "Y", "Z"] });
+            question.as_object_mut().unwrap().extend(limits.as_object().unwrap().clone());
+            config(question)
+        };
+
+        assert!(validate_layout(&checkbox(json!({}))).is_ok());
+        assert!(validate_layout(&checkbox(json!({ "min_selections": 3, "max_selections": 3 }))).is_ok());
+        assert!(validate_layout(&checkbox(json!({ "min_selections": 3 }))).is_ok());
+
+        assert!(validate_layout(&checkbox(json!({ "min_selections": 2, "max_selections": 1 }))).is_err());
+        assert!(validate_layout(&checkbox(json!({ "max_selections": 4 }))).is_err());
+        assert!(validate_layout(&checkbox(json!({ "min_selections": 4 }))).is_err());
+        assert!(validate_layout(&checkbox(json!({ "min_selections": -1 }))).is_err());
+        assert!(validate_layout(&checkbox(json!({ "max_selections": "2" }))).is_err());
+
+        // A radio question allows one selection unless it says otherwise
+        let radio = json!({ "id": "q1", "type": "radio", "options": ["A", "B"], "min_selections": 2 });
+        assert!(validate_layout(&config(radio)).is_err());
+    }
+}
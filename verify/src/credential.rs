@@ -0,0 +1,187 @@
+//! Blind-signed voting credentials (RFC 9474, RSABSSA-SHA384-PSS-Randomized).
+//!
+//! During identity validation the voter has a random token signed blindly.
+//! At the ballot box the voter presents the unblinded token and signature,
+//! which the server cannot link back to the blinded request it signed.
+
+use blind_rsa_signatures::{
+    BlindMessage, BlindSignature, BlindingResult, DefaultRng, KeyPairSha384PSSRandomized,
+    MessageRandomizer, PublicKeySha384PSSRandomized as PublicKey,
+    SecretKeySha384PSSRandomized as SecretKey, Secret, Signature,
+};
+use serde::{Deserialize, Serialize};
+
+pub const CREDENTIAL_SCHEME: &str = "RSABSSA-SHA384-PSS-Randomized";
+pub const TOKEN_LEN: usize = 32;
+const MODULUS_BITS: usize = 2048;
+
+/// What a voter presents with a ballot, all hex encoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credential {
+    pub token: String,
+    pub randomizer: String,
+    pub signature: String,
+}
+
+/// Client-side state kept between blinding and finalizing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlindingState {
+    pub token: String,
+    pub secret: String,
+    pub randomizer: String,
+    pub blinded_token: String,
+}
+
+/// Fresh key pair as (secret key DER, hex public key DER)
+pub fn generate_keypair() -> Result<(Vec<u8>, String), String> {
+    let pair = KeyPairSha384PSSRandomized::generate(&mut DefaultRng, MODULUS_BITS)
+        .map_err(|e| e.to_string())?;
+    let secret = pair.sk.to_der().map_err(|e| e.to_string())?;
+    let public = pair.pk.to_der().map_err(|e| e.to_string())?;
+    Ok((secret, hex::encode(public)))
+}
+
+fn public_key(public_hex: &str) -> Option<PublicKey> {
+    PublicKey::from_der(&hex::decode(public_hex).ok()?).ok()
+}
+
+/// Server side: signs a blinded token without learning it
+pub fn blind_sign(secret_der: &[u8], blinded_token: &str) -> Result<String, String> {
+    let key = SecretKey::from_der(secret_der).map_err(|e| e.to_string())?;
+    let blinded = hex::decode(blinded_token).map_err(|_| "Blinded token must be hex".to_string())?;
+    let signature = key
+        .blind_sign(BlindMessage(blinded))
+        .map_err(|e| format!("Cannot sign blinded token: {}", e))?;
+    Ok(hex::encode(signature.0))
+}
+
+/// Client side: picks a random token and blinds it for the election's credential key
+pub fn blind(public_hex: &str) -> Result<BlindingState, String> {
+    let key = public_key(public_hex).ok_or("Invalid credential public key")?;
+    let token: [u8; TOKEN_LEN] = rand::random();
+
+    let result = key.blind(&mut DefaultRng, token).map_err(|e| e.to_string())?;
+    let randomizer = result.msg_randomizer.ok_or("Randomized blinding produced no randomizer")?;
+
+    Ok(BlindingState {
+        token: hex::encode(token),
+        secret: hex::encode(result.secret.0),
+        randomizer: hex::encode(randomizer.0),
+        blinded_token: hex::encode(result.blind_message.0),
+    })
+}
+
+/// Client side: unblinds the server's signature into a credential, checking it verifies
+pub fn finalize(
+    public_hex: &str,
+    state: &BlindingState,
+    blind_signature: &str,
+) -> Result<Credential, String> {
+    let key = public_key(public_hex).ok_or("Invalid credential public key")?;
+    let decode = |field: &str| hex::decode(field).map_err(|_| "Blinding state must be hex".to_string());
+    let randomizer: [u8; 32] = decode(&state.randomizer)?
+        .try_into()
+        .map_err(|_| "Randomizer must be 32 bytes".to_string())?;
+
+    let blinding = BlindingResult {
+        blind_message: BlindMessage(decode(&state.blinded_token)?),
+        secret: Secret(decode(&state.secret)?),
+        msg_randomizer: Some(MessageRandomizer(randomizer)),
+    };
+
+    let signature = key
+        .finalize(
+            &BlindSignature(decode(blind_signature)?),
+            &blinding,
+            decode(&state.token)?,
+        )
+        .map_err(|e| format!("Blind signature does not verify: {}", e))?;
+
+    Ok(Credential {
+        token: state.token.clone(),
+        randomizer: state.randomizer.clone(),
+        signature: hex::encode(signature.0),
+    })
+}
+
+pub fn verify_credential(public_hex: &str, credential: &Credential) -> bool {
+    let parsed = (|| {
+        let token = hex::decode(&credential.token).ok()?;
+        let randomizer: [u8; 32] = hex::decode(&credential.randomizer).ok()?.try_into().ok()?;
+        let signature = hex::decode(&credential.signature).ok()?;
+        (token.len() == TOKEN_LEN).then_some((token, randomizer, signature))
+    })();
+    let (Some(key), Some((token, randomizer, signature))) = (public_key(public_hex), parsed) else {
+        return false;
+    };
+
+    key.verify(&Signature(signature), Some(MessageRandomizer(randomizer)), token)
+        .is_ok()
+}
+
+/// What the server records once a credential is spent. Hex case is normalized
+/// so one token cannot be spent twice under different spellings.
+pub fn spent_token_hash(suite: crate::HashSuite, credential: &Credential) -> String {
+    suite.hash_hex(&credential.token.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::OnceLock;
+
+    /// Two credential keys as (secret key DER, hex public key DER). RSA key
+    /// generation is slow, so the tests share them.
+    fn keys() -> &'static [(Vec<u8>, String); 2] {
+        static KEYS: OnceLock<[(Vec<u8>, String); 2]> = OnceLock::new();
+        KEYS.get_or_init(|| [generate_keypair().unwrap(), generate_keypair().unwrap()])
+    }
+
+    fn issue(secret: &[u8], public: &str) -> Credential {
+        let state = blind(public).unwrap();
+        let blind_signature = blind_sign(secret, &state.blinded_token).unwrap();
+        finalize(public, &state, &blind_signature).unwrap()
+    }
+
+    /// `field` with its first byte flipped
+    fn flip(field: &str) -> String {
+        let mut bytes = hex::decode(field).unwrap();
+        bytes[0] ^= 0x01;
+        hex::encode(bytes)
+    }
+
+    #[test]
+    fn an_unblinded_credential_verifies() {
+        let (secret, public) = &keys()[0];
+        let credential = issue(secret, public);
+        assert!(verify_credential(public, &credential));
+        assert_eq!(credential.token.len(), TOKEN_LEN * 2);
+    }
+
+    #[test]
+    fn a_credential_with_a_flipped_byte_is_rejected() {
+        let (secret, public) = &keys()[0];
+        let credential = issue(secret, public);
+
+        for tampered in [
+            Credential { token: flip(&credential.token), ..credential.clone() },
+            Credential { randomizer: flip(&credential.randomizer), ..credential.clone() },
+            Credential { signature: flip(&credential.signature), ..credential.clone() },
+        ] {
+            assert!(!verify_credential(public, &tampered));
+        }
+    }
+
+    #[test]
+    fn a_signature_from_another_key_is_rejected() {
+        let [(secret, public), (_, other_public)] = keys();
+        let credential = issue(secret, public);
+        assert!(!verify_credential(other_public, &credential));
+
+        // Nor does a blind signature from another key unblind
+        let state = blind(other_public).unwrap();
+        let finalized = blind_sign(secret, &state.blinded_token)
+            .and_then(|blind_signature| finalize(other_public, &state, &blind_signature));
+        assert!(finalized.is_err());
+    }
+}
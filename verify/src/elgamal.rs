@@ -0,0 +1,368 @@
+//! Exponential ElGamal over Ristretto255.
+//!
+//! A selection `m` is encrypted as `(r·G, m·G + r·PK)`, so adding ciphertexts adds
+//! the plaintexts. Decryption yields `m·G`; the small tally `m` is then recovered
+//! by a bounded discrete-log search.
+
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_POINT as G,
+    ristretto::{CompressedRistretto, RistrettoPoint},
+    scalar::Scalar,
+    traits::Identity,
+};
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use uuid::Uuid;
+
+/// `elections.proof_version` of proofs whose challenges bind the election and key
+pub const PROOF_VERSION: i16 = 2;
+
+pub fn public_key(secret: &Scalar) -> RistrettoPoint {
+    secret * G
+}
+
+pub fn random_scalar<R: RngCore + CryptoRng>(rng: &mut R) -> Scalar {
+    Scalar::random(rng)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ciphertext {
+    #[serde(with = "point_hex")]
+    pub c1: RistrettoPoint,
+    #[serde(with = "point_hex")]
+    pub c2: RistrettoPoint,
+}
+
+impl Ciphertext {
+    /// The trivial encryption of zero, the neutral element for `add`
+    pub fn zero() -> Self {
+        Ciphertext {
+            c1: RistrettoPoint::identity(),
+            c2: RistrettoPoint::identity(),
+        }
+    }
+
+    /// Homomorphic addition of the underlying plaintexts
+    pub fn add(&self, other: &Ciphertext) -> Ciphertext {
+        Ciphertext {
+            c1: self.c1 + other.c1,
+            c2: self.c2 + other.c2,
+        }
+    }
+}
+
+/// Encrypts `m` with explicit randomness `r`
+pub fn encrypt_with(pk: &RistrettoPoint, m: u64, r: &Scalar) -> Ciphertext {
+    Ciphertext {
+        c1: r * G,
+        c2: Scalar::from(m) * G + r * pk,
+    }
+}
+
+pub fn encrypt<R: RngCore + CryptoRng>(
+    pk: &RistrettoPoint,
+    m: u64,
+    rng: &mut R,
+) -> (Ciphertext, Scalar) {
+    let r = random_scalar(rng);
+    (encrypt_with(pk, m, &r), r)
+}
+
+/// Strips the key from `ct`, leaving `m·G`
+pub fn decrypt_point(secret: &Scalar, ct: &Ciphertext) -> RistrettoPoint {
+    ct.c2 - secret * ct.c1
+}
+
+/// Finds `m <= max` with `m·G == point`
+pub fn discrete_log(point: &RistrettoPoint, max: u64) -> Option<u64> {
+    let mut candidate = RistrettoPoint::identity();
+    for m in 0..=max {
+        if candidate == *point {
+            return Some(m);
+        }
+        candidate += G;
+    }
+    None
+}
+
+/// Non-interactive Chaum-Pedersen proof that `log_G(h1) == log_base(h2)`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DleqProof {
+    #[serde(with = "point_hex")]
+    pub a: RistrettoPoint,
+    #[serde(with = "point_hex")]
+    pub b: RistrettoPoint,
+    #[serde(with = "scalar_hex")]
+    pub response: Scalar,
+}
+
+/// What a proof is made for. Binding the election into every challenge keeps a
+/// proof from one election from being replayed in another under the same key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofContext {
+    election_id: Option<Uuid>, // None for proofs made before PROOF_VERSION 2
+}
+
+impl ProofContext {
+    pub fn new(election_id: Uuid) -> Self {
+        ProofContext {
+            election_id: Some(election_id),
+        }
+    }
+
+    /// The context of an election whose proofs were made under `proof_version`
+    pub fn for_version(election_id: Uuid, proof_version: i16) -> Self {
+        if proof_version >= PROOF_VERSION {
+            ProofContext::new(election_id)
+        } else {
+            ProofContext { election_id: None }
+        }
+    }
+}
+
+/// Fiat-Shamir challenge over a domain tag, the election, the public key the
+/// statement is about and the transcript points
+pub fn challenge(
+    domain: &str,
+    context: &ProofContext,
+    public_key: &RistrettoPoint,
+    points: &[&RistrettoPoint],
+) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(domain.as_bytes());
+    if let Some(election_id) = &context.election_id {
+        hasher.update(election_id.as_bytes());
+        hasher.update(public_key.compress().as_bytes());
+    }
+    for point in points {
+        hasher.update(point.compress().as_bytes());
+    }
+    Scalar::from_hash(hasher)
+}
+
+/// Proves knowledge of `secret` with `h1 = secret·G` and `h2 = secret·base`; `h1` is the public key
+pub fn prove_dleq<R: RngCore + CryptoRng>(
+    domain: &str,
+    context: &ProofContext,
+    secret: &Scalar,
+    base: &RistrettoPoint,
+    rng: &mut R,
+) -> DleqProof {
+    let h1 = secret * G;
+    let h2 = secret * base;
+    let w = random_scalar(rng);
+    let a = w * G;
+    let b = w * base;
+    let e = challenge(domain, context, &h1, &[&h1, base, &h2, &a, &b]);
+
+    DleqProof {
+        a,
+        b,
+        response: w + e * secret,
+    }
+}
+
+pub fn verify_dleq(
+    domain: &str,
+    context: &ProofContext,
+    h1: &RistrettoPoint,
+    base: &RistrettoPoint,
+    h2: &RistrettoPoint,
+    proof: &DleqProof,
+) -> bool {
+    let e = challenge(domain, context, h1, &[h1, base, h2, &proof.a, &proof.b]);
+    proof.response * G == proof.a + e * h1 && proof.response * base == proof.b + e * h2
+}
+
+const DECRYPTION_DOMAIN: &str = "solesigner-decryption-v1";
+
+/// Decrypts `ct` (searching up to `max`) and proves the result is correct
+pub fn prove_decryption<R: RngCore + CryptoRng>(
+    context: &ProofContext,
+    secret: &Scalar,
+    ct: &Ciphertext,
+    max: u64,
+    rng: &mut R,
+) -> Option<(u64, DleqProof)> {
+    let m = discrete_log(&decrypt_point(secret, ct), max)?;
+    Some((m, prove_dleq(DECRYPTION_DOMAIN, context, secret, &ct.c1, rng)))
+}
+
+/// Checks that `ct` decrypts to `m` under `pk` without knowing the secret key
+pub fn verify_decryption(
+    context: &ProofContext,
+    pk: &RistrettoPoint,
+    ct: &Ciphertext,
+    m: u64,
+    proof: &DleqProof,
+) -> bool {
+    // The key share removed from c2 must equal pk's discrete log applied to c1
+    let shared = ct.c2 - Scalar::from(m) * G;
+    verify_dleq(DECRYPTION_DOMAIN, context, pk, &ct.c1, &shared, proof)
+}
+
+/// One branch of a disjunctive proof; the real branch is indistinguishable from the simulated ones
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofBranch {
+    #[serde(with = "scalar_hex")]
+    pub challenge: Scalar,
+    #[serde(with = "scalar_hex")]
+    pub response: Scalar,
+}
+
+/// Non-interactive disjunctive Chaum-Pedersen proof that a ciphertext encrypts
+/// one of a list of values, with one branch per value in order
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisjunctiveProof {
+    pub branches: Vec<ProofBranch>,
+}
+
+/// Commitments `(a, b)` that make branch `value` verify for the given challenge and response
+fn branch_commitments(
+    pk: &RistrettoPoint,
+    ct: &Ciphertext,
+    value: u64,
+    branch: &ProofBranch,
+) -> (RistrettoPoint, RistrettoPoint) {
+    // Branch `value` claims log_G(c1) == log_pk(c2 - value·G)
+    let shared = ct.c2 - Scalar::from(value) * G;
+    (
+        branch.response * G - branch.challenge * ct.c1,
+        branch.response * pk - branch.challenge * shared,
+    )
+}
+
+fn disjunctive_challenge(
+    domain: &str,
+    context: &ProofContext,
+    pk: &RistrettoPoint,
+    ct: &Ciphertext,
+    allowed: &[u64],
+    commitments: &[(RistrettoPoint, RistrettoPoint)],
+) -> Scalar {
+    let values: Vec<RistrettoPoint> = allowed.iter().map(|v| Scalar::from(*v) * G).collect();
+    let mut points = vec![pk, &ct.c1, &ct.c2];
+    points.extend(values.iter());
+    for (a, b) in commitments {
+        points.push(a);
+        points.push(b);
+    }
+    challenge(domain, context, pk, &points)
+}
+
+/// Proves that `encrypt_with(pk, m, r)` encrypts some value in `allowed` without
+/// revealing which. Returns `None` if `m` is not allowed.
+pub fn prove_disjunctive<R: RngCore + CryptoRng>(
+    domain: &str,
+    context: &ProofContext,
+    pk: &RistrettoPoint,
+    r: &Scalar,
+    m: u64,
+    allowed: &[u64],
+    rng: &mut R,
+) -> Option<DisjunctiveProof> {
+    let real = allowed.iter().position(|v| *v == m)?;
+    let ct = &encrypt_with(pk, m, r);
+
+    // Simulate every other branch, then commit honestly on the real one
+    let w = random_scalar(rng);
+    let mut branches: Vec<ProofBranch> = allowed
+        .iter()
+        .map(|_| ProofBranch {
+            challenge: random_scalar(rng),
+            response: random_scalar(rng),
+        })
+        .collect();
+    let commitments: Vec<_> = allowed
+        .iter()
+        .zip(&branches)
+        .enumerate()
+        .map(|(i, (value, branch))| {
+            if i == real {
+                (w * G, w * pk)
+            } else {
+                branch_commitments(pk, ct, *value, branch)
+            }
+        })
+        .collect();
+
+    let e = disjunctive_challenge(domain, context, pk, ct, allowed, &commitments);
+    let simulated: Scalar = branches
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != real)
+        .map(|(_, b)| b.challenge)
+        .sum();
+    branches[real].challenge = e - simulated;
+    branches[real].response = w + branches[real].challenge * r;
+
+    Some(DisjunctiveProof { branches })
+}
+
+pub fn verify_disjunctive(
+    domain: &str,
+    context: &ProofContext,
+    pk: &RistrettoPoint,
+    ct: &Ciphertext,
+    allowed: &[u64],
+    proof: &DisjunctiveProof,
+) -> bool {
+    if allowed.is_empty() || proof.branches.len() != allowed.len() {
+        return false;
+    }
+
+    let commitments: Vec<_> = allowed
+        .iter()
+        .zip(&proof.branches)
+        .map(|(value, branch)| branch_commitments(pk, ct, *value, branch))
+        .collect();
+    let total: Scalar = proof.branches.iter().map(|b| b.challenge).sum();
+
+    total == disjunctive_challenge(domain, context, pk, ct, allowed, &commitments)
+}
+
+pub fn point_to_hex(point: &RistrettoPoint) -> String {
+    hex::encode(point.compress().as_bytes())
+}
+
+pub fn point_from_hex(encoded: &str) -> Option<RistrettoPoint> {
+    let bytes: [u8; 32] = hex::decode(encoded).ok()?.try_into().ok()?;
+    CompressedRistretto(bytes).decompress()
+}
+
+pub fn scalar_from_bytes(bytes: &[u8]) -> Option<Scalar> {
+    let bytes: [u8; 32] = bytes.try_into().ok()?;
+    Scalar::from_canonical_bytes(bytes).into()
+}
+
+pub mod point_hex {
+    use curve25519_dalek::ristretto::RistrettoPoint;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(point: &RistrettoPoint, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&super::point_to_hex(point))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<RistrettoPoint, D::Error> {
+        let encoded = String::deserialize(d)?;
+        super::point_from_hex(&encoded).ok_or_else(|| D::Error::custom("invalid Ristretto point"))
+    }
+}
+
+pub mod scalar_hex {
+    use curve25519_dalek::scalar::Scalar;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(scalar: &Scalar, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&hex::encode(scalar.as_bytes()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Scalar, D::Error> {
+        let encoded = String::deserialize(d)?;
+        hex::decode(&encoded)
+            .ok()
+            .and_then(|bytes| super::scalar_from_bytes(&bytes))
+            .ok_or_else(|| D::Error::custom("invalid scalar"))
+    }
+}
@@ -0,0 +1,111 @@
+//! Per-election choice of hash function.
+//!
+//! The suite is fixed when an election is created and recorded in
+//! `elections.hash_suite`. It drives ballot hashes, nullifiers, spent-credential
+//! hashes and both Merkle trees; everything else (signatures, the admin audit
+//! log, ballot proof challenges) keeps its own fixed hash.
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sha3::Sha3_256;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum HashSuite {
+    #[default]
+    #[serde(rename = "SHA-256")]
+    Sha256,
+    #[serde(rename = "SHA3-256")]
+    Sha3_256,
+    #[serde(rename = "BLAKE3")]
+    Blake3,
+}
+
+impl HashSuite {
+    /// Name stored in `elections.hash_suite` and advertised to verifiers
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashSuite::Sha256 => "SHA-256",
+            HashSuite::Sha3_256 => "SHA3-256",
+            HashSuite::Blake3 => "BLAKE3",
+        }
+    }
+
+    /// `elections.nullifier_scheme` for nullifiers keyed under this suite
+    pub fn nullifier_scheme(&self) -> &'static str {
+        match self {
+            HashSuite::Sha256 => "HMAC-SHA256",
+            HashSuite::Sha3_256 => "HMAC-SHA3-256",
+            HashSuite::Blake3 => "BLAKE3-KEYED",
+        }
+    }
+
+    /// 32-byte digest over the concatenation of `parts`
+    pub fn digest(&self, parts: &[&[u8]]) -> [u8; 32] {
+        match self {
+            HashSuite::Sha256 => {
+                let mut hasher = Sha256::new();
+                parts.iter().for_each(|part| hasher.update(part));
+                hasher.finalize().into()
+            }
+            HashSuite::Sha3_256 => {
+                let mut hasher = Sha3_256::new();
+                parts.iter().for_each(|part| hasher.update(part));
+                hasher.finalize().into()
+            }
+            HashSuite::Blake3 => {
+                let mut hasher = blake3::Hasher::new();
+                parts.iter().for_each(|part| {
+                    hasher.update(part);
+                });
+                hasher.finalize().into()
+            }
+        }
+    }
+
+    /// Hex digest of a string's UTF-8 bytes
+    pub fn hash_hex(&self, data: &str) -> String {
+        hex::encode(self.digest(&[data.as_bytes()]))
+    }
+
+    /// Keyed digest: HMAC for the SHA families, BLAKE3's own keyed mode otherwise
+    pub fn mac(&self, key: &[u8; 32], message: &[u8]) -> [u8; 32] {
+        match self {
+            HashSuite::Sha256 => {
+                let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+                mac.update(message);
+                mac.finalize().into_bytes().into()
+            }
+            HashSuite::Sha3_256 => {
+                let mut mac = Hmac::<Sha3_256>::new_from_slice(key).expect("HMAC accepts any key length");
+                mac.update(message);
+                mac.finalize().into_bytes().into()
+            }
+            HashSuite::Blake3 => blake3::keyed_hash(key, message).into(),
+        }
+    }
+}
+
+impl fmt::Display for HashSuite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HashSuite {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "SHA-256" => Ok(HashSuite::Sha256),
+            "SHA3-256" => Ok(HashSuite::Sha3_256),
+            "BLAKE3" => Ok(HashSuite::Blake3),
+            other => Err(format!(
+                "Unknown hash suite {}; expected SHA-256, SHA3-256 or BLAKE3",
+                other
+            )),
+        }
+    }
+}
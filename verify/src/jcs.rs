@@ -0,0 +1,220 @@
+//! JSON Canonicalization Scheme (RFC 8785).
+//!
+//! Ballots are hashed over this encoding, so any client that implements JCS
+//! (e.g. the `canonicalize` npm package next to js-sha256) arrives at the same
+//! bytes regardless of the key order or number spelling it sent.
+
+use serde_json::Value;
+use std::fmt::Write;
+
+/// RFC 8785 serialization of `value`
+pub fn canonicalize(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => {
+            // JCS numbers are IEEE 754 doubles, so large integers round like they do in JavaScript
+            let n = n.as_f64().expect("serde_json numbers are finite");
+            write_number(out, n);
+        }
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            // Keys sort by their UTF-16 code units, not by UTF-8 bytes
+            let mut entries: Vec<(Vec<u16>, &String, &Value)> = map
+                .iter()
+                .map(|(key, value)| (key.encode_utf16().collect(), key, value))
+                .collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+            out.push('{');
+            for (i, (_, key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, value);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{0C}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if (c as u32) < 0x20 => {
+                write!(out, "\\u{:04x}", c as u32).expect("writing to a String cannot fail");
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// ECMAScript Number::toString, which RFC 8785 section 3.2.2.3 adopts
+fn write_number(out: &mut String, n: f64) {
+    if n == 0.0 {
+        out.push('0'); // Also for -0
+        return;
+    }
+    if n < 0.0 {
+        out.push('-');
+    }
+
+    let (digits, point) = shortest_digits(n.abs());
+    let k = digits.len() as i32;
+
+    if k <= point && point <= 21 {
+        out.push_str(&digits);
+        out.extend(std::iter::repeat_n('0', (point - k) as usize));
+    } else if 0 < point && point <= 21 {
+        out.push_str(&digits[..point as usize]);
+        out.push('.');
+        out.push_str(&digits[point as usize..]);
+    } else if -6 < point && point <= 0 {
+        out.push_str("0.");
+        out.extend(std::iter::repeat_n('0', (-point) as usize));
+        out.push_str(&digits);
+    } else {
+        out.push_str(&digits[..1]);
+        if k > 1 {
+            out.push('.');
+            out.push_str(&digits[1..]);
+        }
+        let e = point - 1;
+        write!(out, "e{}{}", if e < 0 { '-' } else { '+' }, e.abs())
+            .expect("writing to a String cannot fail");
+    }
+}
+
+/// Shortest round-tripping digits of `x` and the position of the decimal point
+/// relative to them, so that x = 0.digits × 10^point
+fn shortest_digits(x: f64) -> (String, i32) {
+    // Rust's `{:e}` gives the shortest digits that round-trip, as ECMAScript requires
+    let (digits, point) = scientific_digits(&format!("{:e}", x));
+
+    // When two candidates of that length are equally close, ECMAScript takes the even one
+    // and Rust does not promise to. A tie means the exact value ends in a 5 right after them.
+    let k = digits.len();
+    let (exact, exact_point) = scientific_digits(&format!("{:.1100e}", x));
+    if exact_point != point || exact.len() <= k || !exact[k..].trim_end_matches('0').eq("5") {
+        return (digits, point);
+    }
+    let lower: u64 = exact[..k].parse().expect("at most 17 digits");
+    let even = if lower.is_multiple_of(2) { lower } else { lower + 1 };
+    let candidate = even.to_string();
+    if candidate.len() != k || format!("{}e{}", candidate, point - k as i32).parse::<f64>() != Ok(x) {
+        return (digits, point);
+    }
+    (candidate.trim_end_matches('0').to_string(), point)
+}
+
+fn scientific_digits(scientific: &str) -> (String, i32) {
+    let (mantissa, exponent) = scientific.split_once('e').expect("{:e} has an exponent");
+    let digits = mantissa.chars().filter(|c| *c != '.').collect();
+    (digits, exponent.parse::<i32>().expect("{:e} exponent is an integer") + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn number(n: f64) -> String {
+        let mut out = String::new();
+        write_number(&mut out, n);
+        out
+    }
+
+    #[test]
+    fn numbers_match_the_rfc_8785_appendix_b_vectors() {
+        let vectors: [(u64, &str); 24] = [
+            (0x0000000000000000, "0"),
+            (0x8000000000000000, "0"),
+            (0x0000000000000001, "5e-324"),
+            (0x8000000000000001, "-5e-324"),
+            (0x7fefffffffffffff, "1.7976931348623157e+308"),
+            (0xffefffffffffffff, "-1.7976931348623157e+308"),
+            (0x4340000000000000, "9007199254740992"),
+            (0xc340000000000000, "-9007199254740992"),
+            (0x4430000000000000, "295147905179352830000"),
+            (0x44b52d02c7e14af5, "9.999999999999997e+22"),
+            (0x44b52d02c7e14af6, "1e+23"),
+            (0x44b52d02c7e14af7, "1.0000000000000001e+23"),
+            (0x444b1ae4d6e2ef4e, "999999999999999700000"),
+            (0x444b1ae4d6e2ef4f, "999999999999999900000"),
+            (0x444b1ae4d6e2ef50, "1e+21"),
+            (0x3eb0c6f7a0b5ed8c, "9.999999999999997e-7"),
+            (0x3eb0c6f7a0b5ed8d, "0.000001"),
+            (0x41b3de4355555553, "333333333.3333332"),
+            (0x41b3de4355555554, "333333333.33333325"),
+            (0x41b3de4355555555, "333333333.3333333"),
+            (0x41b3de4355555556, "333333333.3333334"),
+            (0x41b3de4355555557, "333333333.33333343"),
+            (0xbecbf647612f3696, "-0.0000033333333333333333"),
+            (0x43143ff3c1cb0959, "1424953923781206.2"),
+        ];
+        for (bits, expected) in vectors {
+            assert_eq!(number(f64::from_bits(bits)), expected, "{:016x}", bits);
+        }
+    }
+
+    #[test]
+    fn number_spellings_from_json_canonicalize_like_javascript() {
+        assert_eq!(canonicalize(&json!([1e21, 1e-7, -0.0, 9007199254740993u64])), "[1e+21,1e-7,0,9007199254740992]");
+        let parsed: Value = serde_json::from_str("[1E21, 0.0000001, -0, 9007199254740993, 1.50]").unwrap();
+        assert_eq!(canonicalize(&parsed), "[1e+21,1e-7,0,9007199254740992,1.5]");
+    }
+
+    #[test]
+    fn ties_round_to_the_even_digit() {
+        // Rust's shortest form of this double ends in ...063; both it and ...062 are
+        // equally close, and ECMAScript picks the even one
+        let x = f64::from_bits(0x43143ff3c1cb0959);
+        assert_eq!(format!("{:e}", x), "1.4249539237812063e15");
+        assert_eq!(shortest_digits(x), ("14249539237812062".to_string(), 16));
+    }
+
+    #[test]
+    fn keys_sort_by_utf_16_code_units() {
+        // RFC 8785 section 3.2.3: the emoji's surrogate pair sorts before U+FB33,
+        // although its code point is higher
+        let value: Value = serde_json::from_str(
+            r#"{"\u20ac":"Euro Sign","\r":"Carriage Return","\ufb33":"Hebrew Letter Dalet With Dagesh","1":"One","\ud83d\ude00":"Emoji: Grinning Face","\u0080":"Control","\u00f6":"Latin Small Letter O With Diaeresis"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            canonicalize(&value),
+            concat!(
+                "{\"\\r\":\"Carriage Return\",\"1\":\"One\",\"\u{80}\":\"Control\",",
+                "\"\u{f6}\":\"Latin Small Letter O With Diaeresis\",\"\u{20ac}\":\"Euro Sign\",",
+                "\"\u{1f600}\":\"Emoji: Grinning Face\",\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}",
+            )
+        );
+    }
+}
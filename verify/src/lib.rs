@@ -0,0 +1,134 @@
+//! Verification shared by the SoleSigner server, the `verify_receipt` CLI and
+//! the browser (built for `wasm32-unknown-unknown`, see `wasm`).
+//!
+//! Hashing, Merkle proofs, Ed25519 signature checks, the exact bytes that get
+//! signed, the ElGamal ballots with their proofs and the blind-signed
+//! credentials live here, so the browser encrypts ballots and unblinds
+//! credentials with the same code the server verifies them with. Anything
+//! needing a server secret or a database stays in the server crate.
+
+pub mod ballot;
+pub mod credential;
+pub mod elgamal;
+pub mod hash;
+pub mod jcs;
+pub mod merkle;
+pub mod receipt;
+pub mod threshold;
+#[cfg(target_arch = "wasm32")]
+pub mod wasm;
+
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+pub use hash::HashSuite;
+pub use merkle::{
+    append_leaf, completed_by, frontier, hash_leaf, hash_node, verify_consistency, verify_multiproof,
+    verify_proof, ConsistencyProof, MerkleMultiproof, MerkleProof, MerkleTree, NodeId, ProofStep, Side,
+    StoredTree, MERKLE_TREE_VERSION,
+};
+
+/// Ballot tracker: H(ballot_id || JCS(choices)) under the election's suite.
+/// Canonicalizing first makes the hash independent of how the client ordered keys.
+pub fn ballot_hash(suite: HashSuite, ballot_id: &Uuid, choices: &serde_json::Value) -> String {
+    suite.hash_hex(&format!("{}{}", ballot_id, jcs::canonicalize(choices)))
+}
+
+// --- Signatures ---
+
+/// Verifies a hex-encoded Ed25519 signature against a hex-encoded public key
+pub fn verify_signature(public_key: &str, message: &[u8], signature: &str) -> bool {
+    let Ok(Ok(key_bytes)) = hex::decode(public_key).map(<[u8; 32]>::try_from) else {
+        return false;
+    };
+    let Ok(Ok(sig_bytes)) = hex::decode(signature).map(<[u8; 64]>::try_from) else {
+        return false;
+    };
+    let Ok(key) = VerifyingKey::from_bytes(&key_bytes) else {
+        return false;
+    };
+
+    key.verify(message, &Signature::from_bytes(&sig_bytes)).is_ok()
+}
+
+/// Fingerprint of a hex-encoded public key: SHA256 over the raw key bytes
+pub fn key_fingerprint(public_key: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(hex::decode(public_key).unwrap_or_default());
+    hex::encode(hasher.finalize())
+}
+
+/// Canonical bytes signed for a vote receipt
+pub fn receipt_message(election_id: &Uuid, ballot_hash: &str, timestamp: i64) -> Vec<u8> {
+    format!(
+        "solesigner-receipt-v1\n{}\n{}\n{}",
+        election_id, ballot_hash, timestamp
+    )
+    .into_bytes()
+}
+
+/// Canonical bytes signed when a ballot is committed for cast-or-challenge.
+/// `credential_hash` binds the commitment to the voter who may cast or challenge it.
+pub fn commitment_message(election_id: &Uuid, ballot_hash: &str, credential_hash: &str, timestamp: i64) -> Vec<u8> {
+    format!(
+        "solesigner-commitment-v2\n{}\n{}\n{}\n{}",
+        election_id, ballot_hash, credential_hash, timestamp
+    )
+    .into_bytes()
+}
+
+/// Canonical bytes signed when an election is sealed
+pub fn sealed_root_message(election_id: &Uuid, merkle_root: &str, tree_version: i16) -> Vec<u8> {
+    format!(
+        "solesigner-sealed-root-v1\n{}\n{}\n{}",
+        election_id, merkle_root, tree_version
+    )
+    .into_bytes()
+}
+
+/// Canonical bytes signed over the spent-credential set when an election is sealed
+pub fn spent_root_message(election_id: &Uuid, spent_root: &str, spent_count: i64) -> Vec<u8> {
+    format!(
+        "solesigner-spent-root-v1\n{}\n{}\n{}",
+        election_id, spent_root, spent_count
+    )
+    .into_bytes()
+}
+
+/// Canonical bytes a trustee signs for each ceremony message. `payload` is the
+/// exact JSON submitted, so the transcript can be re-verified as stored.
+pub fn trustee_message(kind: &str, election_id: &Uuid, trustee_index: u32, payload: &str) -> Vec<u8> {
+    format!(
+        "solesigner-trustee-v1\n{}\n{}\n{}\n{}",
+        kind, election_id, trustee_index, payload
+    )
+    .into_bytes()
+}
+
+/// A ballot log root committed to by the election signing key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedTreeHead {
+    pub election_id: Uuid,
+    pub tree_size: usize,
+    pub root_hash: String,
+    pub timestamp: i64, // Unix milliseconds
+    pub key_fingerprint: String,
+    pub signature: String,
+}
+
+impl SignedTreeHead {
+    /// The exact bytes covered by the signature
+    pub fn message(&self) -> Vec<u8> {
+        format!(
+            "solesigner-tree-head-v1\n{}\n{}\n{}\n{}\n{}",
+            self.election_id, self.tree_size, self.root_hash, self.timestamp, self.key_fingerprint
+        )
+        .into_bytes()
+    }
+
+    pub fn verify(&self, public_key: &str) -> bool {
+        verify_signature(public_key, &self.message(), &self.signature)
+    }
+}
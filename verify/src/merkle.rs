@@ -0,0 +1,845 @@
+//! RFC 6962 Merkle trees over ballot hashes: building, inclusion, multi-leaf
+//! and consistency proofs, and their verification. `StoredTree` answers the
+//! same proofs from the complete nodes an append-only log keeps as it grows.
+
+use crate::hash::HashSuite;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Which side of the running hash a sibling sits on when combining
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofStep {
+    pub hash: String,
+    pub side: Side,
+}
+
+/// Version tag stored with every sealed root. Version 1 was the original
+/// `SHA256(left_hex + right_hex)` tree with odd nodes duplicated; version 2 is
+/// the RFC 6962 layout built by `MerkleTree` below.
+pub const MERKLE_TREE_VERSION: i16 = 2;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// RFC 6962 leaf hash: H(0x00 || leaf). The leaf entry is taken as its UTF-8 bytes.
+pub fn hash_leaf(suite: HashSuite, leaf: &str) -> String {
+    hex::encode(suite.digest(&[&[LEAF_PREFIX], leaf.as_bytes()]))
+}
+
+/// RFC 6962 interior node hash: H(0x01 || left || right) over the raw digests.
+/// Malformed hex never matches a real root, so it is hashed as empty input.
+pub fn hash_node(suite: HashSuite, left: &str, right: &str) -> String {
+    let left = hex::decode(left).unwrap_or_default();
+    let right = hex::decode(right).unwrap_or_default();
+    hex::encode(suite.digest(&[&[NODE_PREFIX], &left, &right]))
+}
+
+/// Inclusion proof for a single leaf: its position, the size of the tree it was
+/// taken from, and one sibling per level where the node has one, leaf to root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub leaf_index: usize,
+    pub tree_size: usize,
+    pub siblings: Vec<ProofStep>,
+}
+
+/// Inclusion proof for several leaves at once. `hashes` holds only the nodes
+/// the verifier cannot compute itself, level by level from the leaves up and
+/// left to right within a level, so shared interior nodes appear once.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleMultiproof {
+    pub leaf_indices: Vec<usize>, // Strictly increasing
+    pub tree_size: usize,
+    pub hashes: Vec<String>,
+}
+
+/// A node of the tree as built level by level, i.e. `levels[level][index]` of a
+/// `MerkleTree`. It covers leaves `index << level` up to `(index + 1) << level`,
+/// cut short at the end of the tree for the last node of a level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId {
+    pub level: u32,
+    pub index: usize,
+}
+
+impl NodeId {
+    /// Whether all `2^level` leaves under the node exist in a tree of `size`.
+    /// A complete node's hash never changes as the tree grows.
+    pub fn is_complete(&self, size: usize) -> bool {
+        (self.index + 1) << self.level <= size
+    }
+
+    /// The node over exactly `start..start + len`, for the ranges RFC 6962 splits a tree into
+    fn covering(start: usize, len: usize) -> Self {
+        let level = len.next_power_of_two().trailing_zeros();
+        NodeId {
+            level,
+            index: start >> level,
+        }
+    }
+}
+
+/// The complete nodes a tree of `size` leaves decomposes into, largest first.
+/// The root, and every other node on the right edge, is a fold of these.
+pub fn frontier(size: usize) -> Vec<NodeId> {
+    (0..usize::BITS)
+        .rev()
+        .filter(|level| size >> level & 1 == 1)
+        .map(|level| NodeId {
+            level,
+            index: (size >> level) - 1,
+        })
+        .collect()
+}
+
+/// The leaf at `index` followed by every parent that appending it completes.
+/// Each of their left children is in `frontier(index)`.
+pub fn completed_by(index: usize) -> Vec<NodeId> {
+    let mut nodes = vec![NodeId { level: 0, index }];
+    let mut node = nodes[0];
+    while node.index & 1 == 1 {
+        node = NodeId {
+            level: node.level + 1,
+            index: node.index >> 1,
+        };
+        nodes.push(node);
+    }
+    nodes
+}
+
+/// Hashes of `completed_by(index)` when `leaf` is appended at `index`, given the
+/// hashes of `frontier(index)`, or `None` if one of those is missing
+pub fn append_leaf(
+    suite: HashSuite,
+    leaf: &str,
+    index: usize,
+    frontier: &HashMap<NodeId, String>,
+) -> Option<Vec<(NodeId, String)>> {
+    let completed = completed_by(index);
+    let mut hash = hash_leaf(suite, leaf);
+    let mut nodes = vec![(completed[0], hash.clone())];
+
+    for pair in completed.windows(2) {
+        let left = frontier.get(&NodeId {
+            level: pair[0].level,
+            index: pair[0].index - 1,
+        })?;
+        hash = hash_node(suite, left, &hash);
+        nodes.push((pair[1], hash.clone()));
+    }
+    Some(nodes)
+}
+
+/// Reads one node's hash for a proof, `None` if it is not available
+type NodeReader<'a> = dyn FnMut(NodeId) -> Option<String> + 'a;
+
+/// Number of nodes at `level` in a tree of `size` leaves (size > 0)
+fn level_len(size: usize, level: u32) -> usize {
+    ((size - 1) >> level) + 1
+}
+
+fn inclusion_proof(size: usize, index: usize, node: &mut NodeReader) -> Option<MerkleProof> {
+    if index >= size {
+        return None;
+    }
+
+    let mut siblings = Vec::new();
+    let mut current_index = index;
+    let mut level = 0;
+
+    // Stop below the root level
+    while level_len(size, level) > 1 {
+        if current_index & 1 == 1 {
+            siblings.push(ProofStep {
+                hash: node(NodeId { level, index: current_index - 1 })?,
+                side: Side::Left,
+            });
+        } else if current_index + 1 < level_len(size, level) {
+            siblings.push(ProofStep {
+                hash: node(NodeId { level, index: current_index + 1 })?,
+                side: Side::Right,
+            });
+        }
+        // Otherwise the node was promoted and contributes no step
+
+        current_index /= 2;
+        level += 1;
+    }
+
+    Some(MerkleProof {
+        leaf_index: index,
+        tree_size: size,
+        siblings,
+    })
+}
+
+fn multiproof(size: usize, indices: &[usize], node: &mut NodeReader) -> Option<MerkleMultiproof> {
+    let mut known: Vec<usize> = indices.to_vec();
+    known.sort_unstable();
+    known.dedup();
+    if known.is_empty() || *known.last()? >= size {
+        return None;
+    }
+
+    let leaf_indices = known.clone();
+    let mut hashes = Vec::new();
+    let mut level = 0;
+
+    while level_len(size, level) > 1 {
+        let mut i = 0;
+        while i < known.len() {
+            let index = known[i];
+            if index & 1 == 1 {
+                // A known left sibling would already have consumed this node
+                hashes.push(node(NodeId { level, index: index - 1 })?);
+            } else if known.get(i + 1) == Some(&(index + 1)) {
+                i += 1;
+            } else if index + 1 < level_len(size, level) {
+                hashes.push(node(NodeId { level, index: index + 1 })?);
+            }
+            // Otherwise the node is promoted and needs nothing
+            i += 1;
+        }
+
+        known = known.iter().map(|index| index / 2).collect();
+        known.dedup();
+        level += 1;
+    }
+
+    Some(MerkleMultiproof {
+        leaf_indices,
+        tree_size: size,
+        hashes,
+    })
+}
+
+fn consistency_proof(first_size: usize, second_size: usize, node: &mut NodeReader) -> Option<ConsistencyProof> {
+    if first_size == 0 || first_size > second_size {
+        return None;
+    }
+
+    let mut hashes = Vec::new();
+    subproof(first_size, 0, second_size, true, node, &mut hashes)?;
+
+    Some(ConsistencyProof {
+        first_size,
+        second_size,
+        hashes,
+    })
+}
+
+/// RFC 6962 Merkle tree. `levels[0]` holds the leaf hashes; an odd node at the
+/// end of a level is promoted unchanged rather than paired with itself.
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    pub suite: HashSuite,
+    pub leaves: Vec<String>,
+    pub root: String,
+    pub levels: Vec<Vec<String>>,
+}
+
+impl MerkleTree {
+    pub fn new(suite: HashSuite, leaves: Vec<String>) -> Self {
+        if leaves.is_empty() {
+            return MerkleTree {
+                suite,
+                leaves: vec![],
+                root: String::new(),
+                levels: vec![],
+            };
+        }
+
+        let mut current_level: Vec<String> = leaves.iter().map(|l| hash_leaf(suite, l)).collect();
+        let mut levels = vec![current_level.clone()];
+
+        while current_level.len() > 1 {
+            let next_level: Vec<String> = current_level
+                .chunks(2)
+                .map(|chunk| match chunk {
+                    [left, right] => hash_node(suite, left, right),
+                    [single] => single.clone(), // Promote, never duplicate
+                    _ => unreachable!(),
+                })
+                .collect();
+
+            levels.push(next_level.clone());
+            current_level = next_level;
+        }
+
+        MerkleTree {
+            suite,
+            leaves,
+            root: current_level[0].clone(),
+            levels,
+        }
+    }
+
+    fn node(&self, id: NodeId) -> Option<String> {
+        self.levels.get(id.level as usize)?.get(id.index).cloned()
+    }
+
+    /// Every complete node with its hash, i.e. what a log would have stored by now
+    pub fn complete_nodes(&self) -> Vec<(NodeId, String)> {
+        let size = self.leaves.len();
+        self.levels
+            .iter()
+            .enumerate()
+            .flat_map(|(level, hashes)| {
+                hashes.iter().enumerate().map(move |(index, hash)| {
+                    let id = NodeId {
+                        level: level as u32,
+                        index,
+                    };
+                    (id, hash.clone())
+                })
+            })
+            .filter(|(id, _)| id.is_complete(size))
+            .collect()
+    }
+
+    /// Builds the inclusion proof for the leaf at `index`, or `None` if it is out of range
+    pub fn get_proof(&self, index: usize) -> Option<MerkleProof> {
+        inclusion_proof(self.leaves.len(), index, &mut |id| self.node(id))
+    }
+
+    /// Builds one proof covering every leaf in `indices` (in any order, duplicates
+    /// ignored), or `None` if the set is empty or any index is out of range
+    pub fn get_multiproof(&self, indices: &[usize]) -> Option<MerkleMultiproof> {
+        multiproof(self.leaves.len(), indices, &mut |id| self.node(id))
+    }
+
+    /// RFC 6962 consistency proof showing this tree extends its own first
+    /// `first_size` leaves. `None` unless `0 < first_size <= len`.
+    pub fn get_consistency_proof(&self, first_size: usize) -> Option<ConsistencyProof> {
+        consistency_proof(first_size, self.leaves.len(), &mut |id| self.node(id))
+    }
+}
+
+/// A tree of `size` leaves known only through the complete nodes a log stored
+/// as it grew. Every query reads a handful of them, listed beforehand by
+/// `proof_nodes`, `multiproof_nodes` or `consistency_nodes`, so a log serves
+/// proofs without loading its leaves.
+#[derive(Debug, Clone)]
+pub struct StoredTree {
+    pub suite: HashSuite,
+    pub size: usize,
+    nodes: HashMap<NodeId, String>,
+}
+
+impl StoredTree {
+    pub fn new(suite: HashSuite, size: usize, nodes: HashMap<NodeId, String>) -> Self {
+        StoredTree { suite, size, nodes }
+    }
+
+    /// Folds the frontier of a tree of `size` leaves, smallest node first, keeping
+    /// only nodes below `level`
+    fn fold(&self, size: usize, level: u32) -> Option<String> {
+        let mut hash: Option<String> = None;
+        for id in frontier(size).iter().rev().filter(|id| id.level < level) {
+            let node = self.nodes.get(id)?;
+            hash = Some(match hash {
+                Some(right) => hash_node(self.suite, node, &right),
+                None => node.clone(),
+            });
+        }
+        hash
+    }
+
+    fn node(&self, id: NodeId) -> Option<String> {
+        if id.is_complete(self.size) {
+            self.nodes.get(&id).cloned()
+        } else {
+            // The last node of its level: whatever hangs below it on the right edge
+            self.fold(self.size, id.level)
+        }
+    }
+
+    /// Root of the whole tree
+    pub fn root(&self) -> Option<String> {
+        self.root_at(self.size)
+    }
+
+    /// Root of the tree's first `size` leaves, given that prefix's frontier
+    pub fn root_at(&self, size: usize) -> Option<String> {
+        if size == 0 || size > self.size {
+            return None;
+        }
+        self.fold(size, u32::MAX)
+    }
+
+    pub fn get_proof(&self, index: usize) -> Option<MerkleProof> {
+        inclusion_proof(self.size, index, &mut |id| self.node(id))
+    }
+
+    pub fn get_multiproof(&self, indices: &[usize]) -> Option<MerkleMultiproof> {
+        multiproof(self.size, indices, &mut |id| self.node(id))
+    }
+
+    pub fn get_consistency_proof(&self, first_size: usize) -> Option<ConsistencyProof> {
+        consistency_proof(first_size, self.size, &mut |id| self.node(id))
+    }
+
+    /// Complete nodes a query reads, besides the frontier for the root. Proof
+    /// shapes depend only on positions, so the query runs on placeholder hashes.
+    fn plan(size: usize, query: impl FnOnce(&mut NodeReader)) -> Vec<NodeId> {
+        let mut needed: BTreeSet<NodeId> = frontier(size).into_iter().collect();
+        query(&mut |id| {
+            if id.is_complete(size) {
+                needed.insert(id);
+            }
+            Some(String::new())
+        });
+        needed.into_iter().collect()
+    }
+
+    /// Nodes to load for the root of `size` leaves and the proof for `index`
+    pub fn proof_nodes(size: usize, index: usize) -> Vec<NodeId> {
+        Self::plan(size, |node| {
+            inclusion_proof(size, index, node);
+        })
+    }
+
+    /// Nodes to load for the root of `size` leaves and a multiproof for `indices`
+    pub fn multiproof_nodes(size: usize, indices: &[usize]) -> Vec<NodeId> {
+        Self::plan(size, |node| {
+            multiproof(size, indices, node);
+        })
+    }
+
+    /// Nodes to load for both roots and the consistency proof between them
+    pub fn consistency_nodes(first_size: usize, second_size: usize) -> Vec<NodeId> {
+        let mut needed = Self::plan(second_size, |node| {
+            consistency_proof(first_size, second_size, node);
+        });
+        needed.extend(frontier(first_size));
+        needed.sort_unstable();
+        needed.dedup();
+        needed
+    }
+}
+
+/// Proof that the tree of `second_size` leaves is an append-only extension of
+/// the tree of `first_size` leaves
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConsistencyProof {
+    pub first_size: usize,
+    pub second_size: usize,
+    pub hashes: Vec<String>,
+}
+
+/// Largest power of two strictly smaller than `n` (n > 1)
+fn split_point(n: usize) -> usize {
+    let mut k = 1;
+    while k * 2 < n {
+        k *= 2;
+    }
+    k
+}
+
+/// SUBPROOF(m, D[start..start + n], b) from RFC 6962 section 2.1.2
+fn subproof(
+    m: usize,
+    start: usize,
+    n: usize,
+    complete: bool,
+    node: &mut NodeReader,
+    out: &mut Vec<String>,
+) -> Option<()> {
+    if m == n {
+        if !complete {
+            out.push(node(NodeId::covering(start, n))?);
+        }
+        return Some(());
+    }
+
+    let k = split_point(n);
+    if m <= k {
+        subproof(m, start, k, complete, node, out)?;
+        out.push(node(NodeId::covering(start + k, n - k))?);
+    } else {
+        subproof(m - k, start + k, n - k, false, node, out)?;
+        out.push(node(NodeId::covering(start, k))?);
+    }
+    Some(())
+}
+
+/// Recomputes the root from `leaf` and `proof` and compares it with `root`,
+/// following the RFC 9162 inclusion verification algorithm. Each sibling's
+/// side must agree with the position implied by `leaf_index` and `tree_size`.
+pub fn verify_proof(suite: HashSuite, leaf: &str, proof: &MerkleProof, root: &str) -> bool {
+    if proof.leaf_index >= proof.tree_size {
+        return false;
+    }
+
+    let mut current_hash = hash_leaf(suite, leaf);
+    let mut index = proof.leaf_index;
+    let mut last = proof.tree_size - 1;
+
+    for step in &proof.siblings {
+        if last == 0 {
+            return false;
+        }
+
+        if index & 1 == 1 || index == last {
+            if step.side != Side::Left {
+                return false;
+            }
+            current_hash = hash_node(suite, &step.hash, &current_hash);
+            // Skip the levels where this node was promoted
+            while index & 1 == 0 && index != 0 {
+                index >>= 1;
+                last >>= 1;
+            }
+        } else {
+            if step.side != Side::Right {
+                return false;
+            }
+            current_hash = hash_node(suite, &current_hash, &step.hash);
+        }
+        index >>= 1;
+        last >>= 1;
+    }
+
+    last == 0 && current_hash == root
+}
+
+/// Checks many leaves against `root` in one pass. `leaves[i]` is the entry at
+/// `proof.leaf_indices[i]`; every proof hash must be used exactly once.
+pub fn verify_multiproof<S: AsRef<str>>(
+    suite: HashSuite,
+    leaves: &[S],
+    proof: &MerkleMultiproof,
+    root: &str,
+) -> bool {
+    let indices = &proof.leaf_indices;
+    if indices.is_empty()
+        || leaves.len() != indices.len()
+        || indices.windows(2).any(|pair| pair[0] >= pair[1])
+        || indices[indices.len() - 1] >= proof.tree_size
+    {
+        return false;
+    }
+
+    let mut current: Vec<(usize, String)> = indices
+        .iter()
+        .zip(leaves)
+        .map(|(index, leaf)| (*index, hash_leaf(suite, leaf.as_ref())))
+        .collect();
+    let mut supplied = proof.hashes.iter();
+    let mut level_size = proof.tree_size;
+
+    while level_size > 1 {
+        let mut next = Vec::with_capacity(current.len());
+        let mut i = 0;
+        while i < current.len() {
+            let (index, hash) = &current[i];
+            let parent = if index & 1 == 1 {
+                match supplied.next() {
+                    Some(left) => hash_node(suite, left, hash),
+                    None => return false,
+                }
+            } else if current.get(i + 1).map(|(next_index, _)| *next_index) == Some(index + 1) {
+                i += 1;
+                hash_node(suite, hash, &current[i].1)
+            } else if index + 1 < level_size {
+                match supplied.next() {
+                    Some(right) => hash_node(suite, hash, right),
+                    None => return false,
+                }
+            } else {
+                hash.clone()
+            };
+            next.push((index / 2, parent));
+            i += 1;
+        }
+
+        current = next;
+        level_size = level_size.div_ceil(2);
+    }
+
+    supplied.next().is_none() && current.len() == 1 && current[0].1 == root
+}
+
+/// Checks a consistency proof between two tree roots (RFC 9162 section 2.1.4.2)
+pub fn verify_consistency(
+    suite: HashSuite,
+    proof: &ConsistencyProof,
+    first_root: &str,
+    second_root: &str,
+) -> bool {
+    let (first, second) = (proof.first_size, proof.second_size);
+    if first == 0 || first > second {
+        return false;
+    }
+    if first == second {
+        return proof.hashes.is_empty() && first_root == second_root;
+    }
+
+    let mut path = proof.hashes.clone();
+    if first.is_power_of_two() {
+        path.insert(0, first_root.to_string());
+    }
+    if path.is_empty() {
+        return false;
+    }
+
+    let mut fn_ = first - 1;
+    let mut sn = second - 1;
+    while fn_ & 1 == 1 {
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+
+    let mut fr = path[0].clone();
+    let mut sr = path[0].clone();
+
+    for c in &path[1..] {
+        if sn == 0 {
+            return false;
+        }
+        if fn_ & 1 == 1 || fn_ == sn {
+            fr = hash_node(suite, c, &fr);
+            sr = hash_node(suite, c, &sr);
+            while fn_ & 1 == 0 && fn_ != 0 {
+                fn_ >>= 1;
+                sn >>= 1;
+            }
+        } else {
+            sr = hash_node(suite, &sr, c);
+        }
+        fn_ >>= 1;
+        sn >>= 1;
+    }
+
+    sn == 0 && fr == first_root && sr == second_root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUITES: [HashSuite; 3] = [HashSuite::Sha256, HashSuite::Sha3_256, HashSuite::Blake3];
+
+    fn leaves(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("ballot-{}", i)).collect()
+    }
+
+    #[test]
+    fn inclusion_round_trips_for_every_size() {
+        for suite in SUITES {
+            for n in 1..=40 {
+                let tree = MerkleTree::new(suite, leaves(n));
+                for (i, leaf) in tree.leaves.iter().enumerate() {
+                    let proof = tree.get_proof(i).unwrap();
+                    assert!(verify_proof(suite, leaf, &proof, &tree.root), "size {} leaf {}", n, i);
+                }
+                assert!(tree.get_proof(n).is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn inclusion_rejects_tampered_proofs() {
+        let suite = HashSuite::Sha256;
+        for n in 2..=40 {
+            let tree = MerkleTree::new(suite, leaves(n));
+            for (i, leaf) in tree.leaves.iter().enumerate() {
+                let proof = tree.get_proof(i).unwrap();
+                assert!(!verify_proof(suite, "forged", &proof, &tree.root));
+                assert!(!verify_proof(suite, leaf, &proof, &hash_leaf(suite, "other root")));
+
+                // Claiming another position must fail
+                let other = if i == 0 { 1 } else { i - 1 };
+                let moved = MerkleProof { leaf_index: other, ..proof.clone() };
+                assert!(!verify_proof(suite, leaf, &moved, &tree.root), "size {} leaf {} as {}", n, i, other);
+
+                // Swapping a sibling to the other side must fail
+                for s in 0..proof.siblings.len() {
+                    let mut swapped = proof.clone();
+                    swapped.siblings[s].side = match swapped.siblings[s].side {
+                        Side::Left => Side::Right,
+                        Side::Right => Side::Left,
+                    };
+                    assert!(!verify_proof(suite, leaf, &swapped, &tree.root));
+
+                    let mut altered = proof.clone();
+                    altered.siblings[s].hash = hash_leaf(suite, "tampered");
+                    assert!(!verify_proof(suite, leaf, &altered, &tree.root));
+                }
+
+                // Dropping or adding a step must fail
+                if let Some(shorter) = proof.siblings.split_last().map(|(_, rest)| rest.to_vec()) {
+                    let truncated = MerkleProof { siblings: shorter, ..proof.clone() };
+                    assert!(!verify_proof(suite, leaf, &truncated, &tree.root));
+                }
+                let mut longer = proof.clone();
+                longer.siblings.push(ProofStep { hash: tree.root.clone(), side: Side::Left });
+                assert!(!verify_proof(suite, leaf, &longer, &tree.root));
+            }
+        }
+    }
+
+    #[test]
+    fn consistency_holds_between_every_pair_of_sizes() {
+        let suite = HashSuite::Sha256;
+        let all = leaves(40);
+        let roots: Vec<String> = (1..=40).map(|n| MerkleTree::new(suite, all[..n].to_vec()).root).collect();
+
+        for n in 1..=40 {
+            let tree = MerkleTree::new(suite, all[..n].to_vec());
+            for m in 1..=n {
+                let proof = tree.get_consistency_proof(m).unwrap();
+                assert!(verify_consistency(suite, &proof, &roots[m - 1], &tree.root), "{} -> {}", m, n);
+
+                if m < n {
+                    // Another size's root, or a proof with a node replaced, must fail
+                    assert!(!verify_consistency(suite, &proof, &roots[m], &tree.root), "{} -> {}", m, n);
+                    for h in 0..proof.hashes.len() {
+                        let mut altered = proof.clone();
+                        altered.hashes[h] = hash_leaf(suite, "tampered");
+                        assert!(!verify_consistency(suite, &altered, &roots[m - 1], &tree.root), "{} -> {} hash {}", m, n, h);
+                    }
+                }
+            }
+            assert!(tree.get_consistency_proof(0).is_none());
+            assert!(tree.get_consistency_proof(n + 1).is_none());
+        }
+    }
+
+    #[test]
+    fn consistency_rejects_a_rewritten_history() {
+        let suite = HashSuite::Sha256;
+        let mut rewritten = leaves(12);
+        rewritten[3] = "replaced".to_string();
+        let tree = MerkleTree::new(suite, rewritten);
+        for m in 4..=7 {
+            let proof = tree.get_consistency_proof(m).unwrap();
+            let first_root = MerkleTree::new(suite, leaves(m)).root;
+            assert!(!verify_consistency(suite, &proof, &first_root, &tree.root), "{}", m);
+        }
+    }
+
+    #[test]
+    fn multiproofs_round_trip_and_reject_tampering() {
+        let suite = HashSuite::Sha256;
+        for n in 1..=40 {
+            let tree = MerkleTree::new(suite, leaves(n));
+            let subsets: Vec<Vec<usize>> = vec![
+                (0..n).collect(),
+                (0..n).step_by(2).collect(),
+                (0..n).step_by(3).collect(),
+                vec![n - 1],
+                vec![0, n - 1],
+            ];
+            for indices in subsets {
+                let mut unique = indices.clone();
+                unique.dedup();
+                let proof = tree.get_multiproof(&unique).unwrap();
+                let selected: Vec<&String> = proof.leaf_indices.iter().map(|i| &tree.leaves[*i]).collect();
+                assert!(verify_multiproof(suite, &selected, &proof, &tree.root), "size {} {:?}", n, unique);
+
+                let mut forged = selected.clone();
+                let fake = "forged".to_string();
+                forged[0] = &fake;
+                assert!(!verify_multiproof(suite, &forged, &proof, &tree.root));
+
+                for h in 0..proof.hashes.len() {
+                    let mut altered = proof.clone();
+                    altered.hashes[h] = hash_leaf(suite, "tampered");
+                    assert!(!verify_multiproof(suite, &selected, &altered, &tree.root));
+                }
+                let mut padded = proof.clone();
+                padded.hashes.push(tree.root.clone());
+                assert!(!verify_multiproof(suite, &selected, &padded, &tree.root));
+            }
+            assert!(tree.get_multiproof(&[]).is_none());
+            assert!(tree.get_multiproof(&[n]).is_none());
+        }
+    }
+
+    /// Appends `n` leaves one by one, keeping only complete nodes as a log would
+    fn grow(suite: HashSuite, n: usize) -> HashMap<NodeId, String> {
+        let mut stored = HashMap::new();
+        for (index, leaf) in leaves(n).iter().enumerate() {
+            let appended = append_leaf(suite, leaf, index, &stored).unwrap();
+            assert_eq!(appended.iter().map(|(id, _)| *id).collect::<Vec<_>>(), completed_by(index));
+            stored.extend(appended);
+        }
+        stored
+    }
+
+    fn load(suite: HashSuite, size: usize, stored: &HashMap<NodeId, String>, needed: &[NodeId]) -> StoredTree {
+        let nodes = needed.iter().map(|id| (*id, stored[id].clone())).collect();
+        StoredTree::new(suite, size, nodes)
+    }
+
+    #[test]
+    fn appends_store_exactly_the_complete_nodes() {
+        let suite = HashSuite::Blake3;
+        for n in 1..=40 {
+            let tree = MerkleTree::new(suite, leaves(n));
+            let stored = grow(suite, n);
+            let expected: HashMap<NodeId, String> = tree.complete_nodes().into_iter().collect();
+            assert_eq!(stored, expected, "size {}", n);
+            assert_eq!(load(suite, n, &stored, &frontier(n)).root().unwrap(), tree.root);
+        }
+    }
+
+    #[test]
+    fn stored_trees_serve_the_same_proofs() {
+        let suite = HashSuite::Sha256;
+        let stored = grow(suite, 40);
+        for n in 1..=40 {
+            let tree = MerkleTree::new(suite, leaves(n));
+            for i in 0..n {
+                let needed = StoredTree::proof_nodes(n, i);
+                assert!(needed.len() <= 2 * (usize::BITS - n.leading_zeros()) as usize);
+                let partial = load(suite, n, &stored, &needed);
+                assert_eq!(partial.root().unwrap(), tree.root);
+                assert_eq!(partial.get_proof(i), tree.get_proof(i), "size {} leaf {}", n, i);
+            }
+
+            let indices: Vec<usize> = (0..n).step_by(3).collect();
+            let partial = load(suite, n, &stored, &StoredTree::multiproof_nodes(n, &indices));
+            assert_eq!(partial.get_multiproof(&indices), tree.get_multiproof(&indices), "size {}", n);
+
+            for m in 1..=n {
+                let partial = load(suite, n, &stored, &StoredTree::consistency_nodes(m, n));
+                assert_eq!(partial.root_at(m).unwrap(), MerkleTree::new(suite, leaves(m)).root);
+                assert_eq!(partial.get_consistency_proof(m), tree.get_consistency_proof(m), "{} -> {}", m, n);
+            }
+        }
+    }
+
+    #[test]
+    fn stored_trees_report_missing_nodes() {
+        let suite = HashSuite::Sha256;
+        let stored = grow(suite, 12);
+        let partial = load(suite, 12, &stored, &frontier(12));
+        assert!(partial.get_proof(0).is_none());
+        assert!(append_leaf(suite, "late", 11, &HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn leaf_and_node_hashes_are_domain_separated() {
+        let suite = HashSuite::Sha256;
+        let a = hash_leaf(suite, "a");
+        let b = hash_leaf(suite, "b");
+        // A node can never pass for a leaf with the same bytes
+        let node = hash_node(suite, &a, &b);
+        let raw = String::from_utf8_lossy(&hex::decode(format!("{}{}", a, b)).unwrap()).to_string();
+        assert_ne!(node, hash_leaf(suite, &raw));
+        // RFC 6962 empty-prefix check: H(0x00 || "") for SHA-256
+        assert_eq!(
+            hash_leaf(suite, ""),
+            "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d"
+        );
+    }
+}
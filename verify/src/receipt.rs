@@ -0,0 +1,450 @@
+//! Vote receipt verification, as run by `verify_receipt` and the browser.
+//!
+//! A receipt proves inclusion only against a root the voter trusts, so the
+//! caller passes whatever it obtained independently of the receipt (a
+//! `BoardSnapshot`) and anything missing falls back to the receipt with a warning.
+
+use crate::hash::HashSuite;
+use crate::merkle::{verify_consistency, verify_proof, ConsistencyProof, MerkleProof, MERKLE_TREE_VERSION};
+use crate::{ballot_hash, receipt_message, sealed_root_message, verify_signature, SignedTreeHead};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use uuid::Uuid;
+
+#[derive(Deserialize, Debug)]
+pub struct VoteReceipt {
+    pub election_id: Uuid,
+    pub ballot_id: Option<Uuid>,
+    pub choices: Option<Value>, // Added by the voter: the exact choices they submitted
+    pub ballot_hash: String,
+    pub timestamp: i64,
+    pub merkle_path: MerkleProof,
+    pub merkle_tree_version: Option<i16>, // Missing means the receipt predates version tags
+    #[serde(default)]
+    pub hash_suite: HashSuite, // Missing means SHA-256, the only suite before elections could choose
+    pub root_hash: Option<String>, // The user must provide the known root hash or it's in the receipt (but verified against public board)
+    pub root_signature: Option<String>, // Copied from /audit/:election_id/verify together with root_hash
+    pub root_timestamp: Option<String>, // Likewise; hex RFC 3161 token over the sealed root
+    pub tree_head: Option<SignedTreeHead>, // Head returned with the receipt at voting time
+    pub public_key: String,
+    pub signature: String,
+}
+
+/// Bulletin-board snapshot obtained independently of the receipt, e.g. the saved
+/// response of /audit/:election_id/verify
+#[derive(Deserialize, Debug, Default)]
+pub struct BoardSnapshot {
+    pub election_id: Option<Uuid>,
+    #[serde(alias = "root_hash")]
+    pub merkle_root: Option<String>,
+    pub merkle_tree_version: Option<i16>,
+    pub hash_suite: Option<HashSuite>,
+    pub public_key: Option<String>,
+    pub root_signature: Option<String>,
+    pub root_timestamp: Option<String>,
+    /// Links a receipt's tree head to `merkle_root`: the `proof` of
+    /// /audit/:election_id/log/consistency?first=<tree_head.tree_size>
+    #[serde(default)]
+    pub consistency: Option<ConsistencyProof>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteLevel {
+    Passed,
+    Warning,
+    Info,
+}
+
+/// One line of progress, reported as verification goes
+#[derive(Debug, Clone, Serialize)]
+pub struct Note {
+    pub level: NoteLevel,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReceiptError {
+    WrongElection(String),
+    BadSignature,
+    BallotMismatch,
+    UnsupportedVersion(i16),
+    BadRoot(String),
+    BadProof,
+}
+
+impl ReceiptError {
+    /// Stable name for the failure, for callers that branch on it
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ReceiptError::WrongElection(_) => "wrong_election",
+            ReceiptError::BadSignature => "bad_signature",
+            ReceiptError::BallotMismatch => "ballot_mismatch",
+            ReceiptError::UnsupportedVersion(_) => "unsupported_version",
+            ReceiptError::BadRoot(_) => "bad_root",
+            ReceiptError::BadProof => "bad_proof",
+        }
+    }
+}
+
+impl fmt::Display for ReceiptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReceiptError::WrongElection(e) | ReceiptError::BadRoot(e) => write!(f, "{}", e),
+            ReceiptError::BadSignature => write!(f, "Receipt signature is not valid for this key."),
+            ReceiptError::BallotMismatch => write!(f, "ballot_hash does not match these choices."),
+            ReceiptError::UnsupportedVersion(v) => write!(
+                f,
+                "Receipt uses Merkle tree version {}, this tool verifies version {}.",
+                v, MERKLE_TREE_VERSION
+            ),
+            ReceiptError::BadProof => write!(f, "Proof does not lead to the expected root."),
+        }
+    }
+}
+
+/// What a valid receipt was checked against
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifiedReceipt {
+    pub root_hash: String,
+    pub merkle_tree_version: i16,
+    /// RFC 3161 token over the sealed root, left to the caller: checking it
+    /// needs X.509 and CMS, which this crate does not carry
+    pub root_timestamp: Option<String>,
+}
+
+/// Checks `receipt` against `board`, reporting each step to `note`.
+/// Values in `board` beat their counterparts in the receipt.
+pub fn verify_receipt(
+    receipt: &VoteReceipt,
+    board: &BoardSnapshot,
+    mut note: impl FnMut(Note),
+) -> Result<VerifiedReceipt, ReceiptError> {
+    let mut say = |level, message: String| note(Note { level, message });
+
+    // The receipt must belong to the election the voter is checking, not merely be well signed
+    match board.election_id {
+        Some(expected) if expected != receipt.election_id => {
+            return Err(ReceiptError::WrongElection(format!(
+                "Receipt is for election {}, expected {}.",
+                receipt.election_id, expected
+            )));
+        }
+        Some(_) => say(NoteLevel::Passed, format!("Receipt belongs to election {}.", receipt.election_id)),
+        None => say(
+            NoteLevel::Warning,
+            "No election given; pass --election or --bundle to bind the receipt to one.".to_string(),
+        ),
+    }
+    if let Some(suite) = board.hash_suite {
+        if suite != receipt.hash_suite {
+            return Err(ReceiptError::WrongElection(format!(
+                "Receipt uses {}, the election publishes {}.",
+                receipt.hash_suite, suite
+            )));
+        }
+    }
+
+    // A key obtained independently (e.g. from /audit/:election_id/verify) beats the one in the receipt
+    let public_key = match &board.public_key {
+        Some(key) => key.as_str(),
+        None => {
+            say(
+                NoteLevel::Warning,
+                "Using the public key embedded in the receipt; compare it with the one published for the election.".to_string(),
+            );
+            receipt.public_key.as_str()
+        }
+    };
+
+    if !verify_signature(
+        public_key,
+        &receipt_message(&receipt.election_id, &receipt.ballot_hash, receipt.timestamp),
+        &receipt.signature,
+    ) {
+        return Err(ReceiptError::BadSignature);
+    }
+    say(NoteLevel::Passed, "Receipt signature valid.".to_string());
+
+    // The receipt only commits to the hash; with the voter's own copy of the ballot it can be recomputed
+    if let (Some(ballot_id), Some(choices)) = (&receipt.ballot_id, &receipt.choices) {
+        if ballot_hash(receipt.hash_suite, ballot_id, choices) != receipt.ballot_hash {
+            return Err(ReceiptError::BallotMismatch);
+        }
+        say(NoteLevel::Passed, "Ballot hash matches the submitted choices.".to_string());
+    }
+
+    let version = receipt.merkle_tree_version.unwrap_or(1);
+    if version != MERKLE_TREE_VERSION {
+        return Err(ReceiptError::UnsupportedVersion(version));
+    }
+    if let Some(published) = board.merkle_tree_version {
+        if published != version {
+            return Err(ReceiptError::WrongElection(format!(
+                "Receipt uses Merkle tree version {}, the election publishes {}.",
+                version, published
+            )));
+        }
+    }
+    say(
+        NoteLevel::Info,
+        format!(
+            "Leaf index: {} of {} ({} proof steps, {})",
+            receipt.merkle_path.leaf_index,
+            receipt.merkle_path.tree_size,
+            receipt.merkle_path.siblings.len(),
+            receipt.hash_suite
+        ),
+    );
+
+    // An independently obtained root beats a root_hash in the receipt, which beats its tree head
+    let sealed = board.merkle_root.as_ref().or(receipt.root_hash.as_ref());
+    if let Some(root) = sealed {
+        match board.root_signature.as_ref().or(receipt.root_signature.as_ref()) {
+            Some(root_signature) => {
+                let message = sealed_root_message(&receipt.election_id, root, version);
+                if !verify_signature(public_key, &message, root_signature) {
+                    return Err(ReceiptError::BadRoot("Sealed root signature is not valid.".to_string()));
+                }
+                say(NoteLevel::Passed, "Sealed root signature valid.".to_string());
+            }
+            None if board.merkle_root.is_none() => say(
+                NoteLevel::Warning,
+                "The root_hash comes from the receipt itself and is unsigned; pass --root or --bundle.".to_string(),
+            ),
+            None => {}
+        }
+    }
+
+    let proves = |root: &str| verify_proof(receipt.hash_suite, &receipt.ballot_hash, &receipt.merkle_path, root);
+    let (root_hash, root_timestamp) = match (sealed, &receipt.tree_head) {
+        // A receipt fetched after sealing proves inclusion in the sealed tree itself
+        (Some(root), _) if proves(root) => {
+            let token = board.root_timestamp.as_ref().or(receipt.root_timestamp.as_ref());
+            (root.clone(), token.cloned())
+        }
+        // One issued at voting time proves inclusion in the head it came with, which
+        // must then be a prefix of the sealed tree
+        (Some(root), Some(head)) => {
+            verify_head(receipt, head, public_key)?;
+            if !proves(&head.root_hash) {
+                return Err(ReceiptError::BadProof);
+            }
+            say(NoteLevel::Passed, format!("Tree head signature valid (size {}).", head.tree_size));
+
+            let Some(consistency) = &board.consistency else {
+                return Err(ReceiptError::BadRoot(format!(
+                    "The receipt proves inclusion in the tree head of size {}; a consistency proof from it to the sealed root is needed (/audit/{}/log/consistency?first={}).",
+                    head.tree_size, receipt.election_id, head.tree_size
+                )));
+            };
+            if consistency.first_size != head.tree_size
+                || !verify_consistency(receipt.hash_suite, consistency, &head.root_hash, root)
+            {
+                return Err(ReceiptError::BadRoot(
+                    "The receipt's tree head is not consistent with the sealed root.".to_string(),
+                ));
+            }
+            say(
+                NoteLevel::Passed,
+                format!(
+                    "Tree head of size {} is consistent with the sealed root (size {}).",
+                    head.tree_size, consistency.second_size
+                ),
+            );
+            let token = board.root_timestamp.as_ref().or(receipt.root_timestamp.as_ref());
+            (root.clone(), token.cloned())
+        }
+        (Some(_), None) => return Err(ReceiptError::BadProof),
+        (None, Some(head)) => {
+            verify_head(receipt, head, public_key)?;
+            say(NoteLevel::Passed, format!("Tree head signature valid (size {}).", head.tree_size));
+            if !proves(&head.root_hash) {
+                return Err(ReceiptError::BadProof);
+            }
+            (head.root_hash.clone(), None)
+        }
+        (None, None) => {
+            return Err(ReceiptError::BadRoot(
+                "Receipt has neither a root_hash nor a tree_head.".to_string(),
+            ));
+        }
+    };
+
+    Ok(VerifiedReceipt {
+        root_hash,
+        merkle_tree_version: version,
+        root_timestamp,
+    })
+}
+
+/// The head must be signed for this election and cover exactly the tree the proof was taken from
+fn verify_head(receipt: &VoteReceipt, head: &SignedTreeHead, public_key: &str) -> Result<(), ReceiptError> {
+    if head.election_id != receipt.election_id || !head.verify(public_key) {
+        return Err(ReceiptError::BadRoot(
+            "Tree head signature is not valid for this election.".to_string(),
+        ));
+    }
+    if head.tree_size != receipt.merkle_path.tree_size {
+        return Err(ReceiptError::BadRoot(format!(
+            "Tree head covers {} ballots but the proof was taken from {}.",
+            head.tree_size, receipt.merkle_path.tree_size
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_fingerprint;
+    use crate::merkle::MerkleTree;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    const ELECTION: Uuid = Uuid::from_u128(0x5eed);
+
+    fn sign(key: &SigningKey, message: &[u8]) -> String {
+        hex::encode(key.sign(message).to_bytes())
+    }
+
+    fn public_key(key: &SigningKey) -> String {
+        hex::encode(key.verifying_key().to_bytes())
+    }
+
+    fn head(key: &SigningKey, tree: &MerkleTree) -> SignedTreeHead {
+        let mut head = SignedTreeHead {
+            election_id: ELECTION,
+            tree_size: tree.leaves.len(),
+            root_hash: tree.root.clone(),
+            timestamp: 1_700_000_000_000,
+            key_fingerprint: key_fingerprint(&public_key(key)),
+            signature: String::new(),
+        };
+        head.signature = sign(key, &head.message());
+        head
+    }
+
+    fn ballots(n: usize) -> Vec<(Uuid, Value, String)> {
+        (0..n)
+            .map(|i| {
+                let id = Uuid::from_u128(i as u128 + 1);
+                let choices = serde_json::json!({ "q1": if i % 2 == 0 { "A" } else { "B" } });
+                let hash = ballot_hash(HashSuite::Sha256, &id, &choices);
+                (id, choices, hash)
+            })
+            .collect()
+    }
+
+    fn tree(ballots: &[(Uuid, Value, String)]) -> MerkleTree {
+        MerkleTree::new(HashSuite::Sha256, ballots.iter().map(|b| b.2.clone()).collect())
+    }
+
+    /// The receipt handed out when ballot `index` was cast, against the head ending at it
+    fn receipt_at_vote(key: &SigningKey, ballots: &[(Uuid, Value, String)], index: usize) -> VoteReceipt {
+        let (id, choices, hash) = ballots[index].clone();
+        let log = tree(&ballots[..=index]);
+        VoteReceipt {
+            election_id: ELECTION,
+            ballot_id: Some(id),
+            choices: Some(choices),
+            timestamp: 1_700_000_000_000 + index as i64,
+            signature: sign(key, &receipt_message(&ELECTION, &hash, 1_700_000_000_000 + index as i64)),
+            ballot_hash: hash,
+            merkle_path: log.get_proof(index).unwrap(),
+            merkle_tree_version: Some(MERKLE_TREE_VERSION),
+            hash_suite: HashSuite::Sha256,
+            root_hash: None,
+            root_signature: None,
+            root_timestamp: None,
+            tree_head: Some(head(key, &log)),
+            public_key: public_key(key),
+        }
+    }
+
+    fn sealed_board(key: &SigningKey, sealed: &MerkleTree, consistency: Option<ConsistencyProof>) -> BoardSnapshot {
+        BoardSnapshot {
+            election_id: Some(ELECTION),
+            merkle_root: Some(sealed.root.clone()),
+            merkle_tree_version: Some(MERKLE_TREE_VERSION),
+            hash_suite: Some(HashSuite::Sha256),
+            public_key: Some(public_key(key)),
+            root_signature: Some(sign(key, &sealed_root_message(&ELECTION, &sealed.root, MERKLE_TREE_VERSION))),
+            root_timestamp: None,
+            consistency,
+        }
+    }
+
+    #[test]
+    fn early_receipts_verify_after_sealing() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let ballots = ballots(5);
+        let sealed = tree(&ballots);
+
+        for index in 0..ballots.len() {
+            let receipt = receipt_at_vote(&key, &ballots, index);
+            let board = sealed_board(&key, &sealed, sealed.get_consistency_proof(index + 1));
+            let verified = verify_receipt(&receipt, &board, |_| {}).unwrap_or_else(|e| panic!("leaf {}: {}", index, e));
+            assert_eq!(verified.root_hash, sealed.root);
+        }
+    }
+
+    #[test]
+    fn early_receipts_need_a_consistency_proof() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let ballots = ballots(5);
+        let sealed = tree(&ballots);
+        let receipt = receipt_at_vote(&key, &ballots, 0);
+
+        let missing = verify_receipt(&receipt, &sealed_board(&key, &sealed, None), |_| {});
+        assert_eq!(missing.unwrap_err().kind(), "bad_root");
+
+        // A proof for another starting size does not link this head
+        let other = sealed_board(&key, &sealed, sealed.get_consistency_proof(2));
+        assert_eq!(verify_receipt(&receipt, &other, |_| {}).unwrap_err().kind(), "bad_root");
+
+        // Nor does a sealed tree that dropped the ballot
+        let mut forked = ballots.clone();
+        forked[0].2 = ballot_hash(HashSuite::Sha256, &Uuid::from_u128(99), &serde_json::json!({ "q1": "B" }));
+        let forked = tree(&forked);
+        let board = sealed_board(&key, &forked, forked.get_consistency_proof(1));
+        assert_eq!(verify_receipt(&receipt, &board, |_| {}).unwrap_err().kind(), "bad_root");
+    }
+
+    #[test]
+    fn sealed_receipts_verify_directly() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let ballots = ballots(5);
+        let sealed = tree(&ballots);
+        let mut receipt = receipt_at_vote(&key, &ballots, 0);
+        receipt.merkle_path = sealed.get_proof(0).unwrap();
+        receipt.tree_head = None;
+
+        let verified = verify_receipt(&receipt, &sealed_board(&key, &sealed, None), |_| {}).unwrap();
+        assert_eq!(verified.root_hash, sealed.root);
+    }
+
+    #[test]
+    fn receipts_verify_against_their_head_before_sealing() {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let ballots = ballots(5);
+        let receipt = receipt_at_vote(&key, &ballots, 3);
+        let board = BoardSnapshot {
+            election_id: Some(ELECTION),
+            public_key: Some(public_key(&key)),
+            ..BoardSnapshot::default()
+        };
+        assert!(verify_receipt(&receipt, &board, |_| {}).is_ok());
+
+        let mut tampered = receipt_at_vote(&key, &ballots, 3);
+        tampered.tree_head.as_mut().unwrap().root_hash = tree(&ballots).root;
+        assert_eq!(verify_receipt(&tampered, &board, |_| {}).unwrap_err().kind(), "bad_root");
+
+        let wrong_key = SigningKey::from_bytes(&[8; 32]);
+        let other = BoardSnapshot {
+            public_key: Some(public_key(&wrong_key)),
+            ..board
+        };
+        assert_eq!(verify_receipt(&receipt, &other, |_| {}).unwrap_err(), ReceiptError::BadSignature);
+    }
+}
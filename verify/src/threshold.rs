@@ -0,0 +1,450 @@
+//! Threshold ElGamal for trustee-held election keys.
+//!
+//! Each trustee deals a random degree `t-1` polynomial with Feldman commitments
+//! (Pedersen DKG). Trustee `j`'s key share is the sum of the shares it received
+//! from every qualified dealer; the joint public key is the sum of the dealers'
+//! constant-term commitments. Any `t` partial decryptions recover `m·G`.
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Nonce,
+};
+use curve25519_dalek::{
+    constants::RISTRETTO_BASEPOINT_POINT as G, ristretto::RistrettoPoint, scalar::Scalar,
+    traits::Identity,
+};
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use super::elgamal::{self, point_hex, Ciphertext, DleqProof, ProofContext};
+
+const KNOWLEDGE_DOMAIN: &str = "solesigner-dkg-knowledge-v1";
+const PARTIAL_DOMAIN: &str = "solesigner-partial-decryption-v1";
+const COMPLAINT_DOMAIN: &str = "solesigner-dkg-complaint-v1";
+
+/// What a trustee publishes in the dealing phase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dealing {
+    /// Feldman commitments `a_k·G` to the polynomial coefficients, constant term first
+    #[serde(with = "points_hex")]
+    pub commitments: Vec<RistrettoPoint>,
+    /// Proof of knowledge of the constant term, preventing rogue-key contributions
+    pub knowledge_proof: DleqProof,
+    /// Recipient trustee index -> share encrypted to that trustee's share key
+    pub encrypted_shares: BTreeMap<u32, EncryptedShare>,
+}
+
+/// A scalar share encrypted with an ephemeral Diffie-Hellman key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedShare {
+    #[serde(with = "point_hex")]
+    pub ephemeral: RistrettoPoint,
+    pub ciphertext: String, // hex
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialDecryption {
+    pub trustee_index: u32,
+    #[serde(with = "point_hex")]
+    pub share: RistrettoPoint, // key_share · c1
+    pub proof: DleqProof,
+}
+
+/// Deals a fresh polynomial of degree `threshold - 1` to trustees `1..=share_keys.len()`
+pub fn deal<R: RngCore + CryptoRng>(
+    context: &ProofContext,
+    threshold: usize,
+    share_keys: &BTreeMap<u32, RistrettoPoint>,
+    rng: &mut R,
+) -> Dealing {
+    let coefficients: Vec<Scalar> = (0..threshold).map(|_| elgamal::random_scalar(rng)).collect();
+
+    let encrypted_shares = share_keys
+        .iter()
+        .map(|(&index, key)| {
+            let share = evaluate(&coefficients, index);
+            (index, encrypt_share(key, &share, rng))
+        })
+        .collect();
+
+    Dealing {
+        commitments: coefficients.iter().map(|a| a * G).collect(),
+        knowledge_proof: elgamal::prove_dleq(KNOWLEDGE_DOMAIN, context, &coefficients[0], &G, rng),
+        encrypted_shares,
+    }
+}
+
+/// Structural checks anyone can run on a published dealing
+pub fn verify_dealing(context: &ProofContext, dealing: &Dealing, threshold: usize, trustee_count: u32) -> bool {
+    dealing.commitments.len() == threshold
+        && elgamal::verify_dleq(
+            KNOWLEDGE_DOMAIN,
+            context,
+            &dealing.commitments[0],
+            &G,
+            &dealing.commitments[0],
+            &dealing.knowledge_proof,
+        )
+        && dealing.encrypted_shares.len() == trustee_count as usize
+        && (1..=trustee_count).all(|i| dealing.encrypted_shares.contains_key(&i))
+}
+
+fn evaluate(coefficients: &[Scalar], index: u32) -> Scalar {
+    let x = Scalar::from(index);
+    coefficients
+        .iter()
+        .rev()
+        .fold(Scalar::ZERO, |acc, coefficient| acc * x + coefficient)
+}
+
+/// `Σ_k commitments[k] · index^k`, the public image of the share dealt to `index`
+pub fn share_commitment(commitments: &[RistrettoPoint], index: u32) -> RistrettoPoint {
+    let x = Scalar::from(index);
+    commitments
+        .iter()
+        .rev()
+        .fold(RistrettoPoint::identity(), |acc, c| acc * x + c)
+}
+
+pub fn verify_share(commitments: &[RistrettoPoint], index: u32, share: &Scalar) -> bool {
+    share * G == share_commitment(commitments, index)
+}
+
+/// Joint election key: the sum of every qualified dealer's constant term
+pub fn joint_public_key<'a>(qualified: impl IntoIterator<Item = &'a Dealing>) -> RistrettoPoint {
+    qualified
+        .into_iter()
+        .map(|d| d.commitments[0])
+        .fold(RistrettoPoint::identity(), |acc, c| acc + c)
+}
+
+/// Public verification key of trustee `index`, derived from the qualified commitments
+pub fn verification_key<'a>(
+    qualified: impl IntoIterator<Item = &'a Dealing>,
+    index: u32,
+) -> RistrettoPoint {
+    qualified
+        .into_iter()
+        .map(|d| share_commitment(&d.commitments, index))
+        .fold(RistrettoPoint::identity(), |acc, c| acc + c)
+}
+
+fn share_cipher(shared_point: &RistrettoPoint) -> ChaCha20Poly1305 {
+    let key = Sha256::digest(shared_point.compress().as_bytes());
+    ChaCha20Poly1305::new(&key)
+}
+
+// Each ephemeral key encrypts exactly one message, so a fixed nonce is safe
+const SHARE_NONCE: [u8; 12] = [0; 12];
+
+pub fn encrypt_share<R: RngCore + CryptoRng>(
+    recipient: &RistrettoPoint,
+    share: &Scalar,
+    rng: &mut R,
+) -> EncryptedShare {
+    let r = elgamal::random_scalar(rng);
+    let ciphertext = share_cipher(&(r * recipient))
+        .encrypt(Nonce::from_slice(&SHARE_NONCE), share.as_bytes().as_slice())
+        .expect("encryption with a valid key cannot fail");
+
+    EncryptedShare {
+        ephemeral: r * G,
+        ciphertext: hex::encode(ciphertext),
+    }
+}
+
+/// Opens a share with the Diffie-Hellman point `recipient_secret · ephemeral`
+pub fn open_share(shared_point: &RistrettoPoint, share: &EncryptedShare) -> Option<Scalar> {
+    let ciphertext = hex::decode(&share.ciphertext).ok()?;
+    let bytes = share_cipher(shared_point)
+        .decrypt(Nonce::from_slice(&SHARE_NONCE), ciphertext.as_slice())
+        .ok()?;
+    elgamal::scalar_from_bytes(&bytes)
+}
+
+pub fn decrypt_share(recipient_secret: &Scalar, share: &EncryptedShare) -> Option<Scalar> {
+    open_share(&(recipient_secret * share.ephemeral), share)
+}
+
+/// A recipient's accusation that a dealer sent it a bad share. Revealing the
+/// Diffie-Hellman point (with a proof it is correct) lets anyone open that one
+/// share and check it against the dealer's commitments.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Complaint {
+    pub against: u32,
+    #[serde(with = "point_hex")]
+    pub shared_point: RistrettoPoint,
+    pub proof: DleqProof,
+}
+
+pub fn complain<R: RngCore + CryptoRng>(
+    context: &ProofContext,
+    recipient_secret: &Scalar,
+    against: u32,
+    share: &EncryptedShare,
+    rng: &mut R,
+) -> Complaint {
+    Complaint {
+        against,
+        shared_point: recipient_secret * share.ephemeral,
+        proof: elgamal::prove_dleq(COMPLAINT_DOMAIN, context, recipient_secret, &share.ephemeral, rng),
+    }
+}
+
+/// True when the complaint is honest and the dealer's share really is bad
+pub fn complaint_is_valid(
+    context: &ProofContext,
+    complaint: &Complaint,
+    recipient_index: u32,
+    recipient_key: &RistrettoPoint,
+    dealing: &Dealing,
+) -> bool {
+    let Some(share) = dealing.encrypted_shares.get(&recipient_index) else {
+        return true; // Missing share
+    };
+    if !elgamal::verify_dleq(
+        COMPLAINT_DOMAIN,
+        context,
+        recipient_key,
+        &share.ephemeral,
+        &complaint.shared_point,
+        &complaint.proof,
+    ) {
+        return false;
+    }
+
+    match open_share(&complaint.shared_point, share) {
+        Some(value) => !verify_share(&dealing.commitments, recipient_index, &value),
+        None => true, // Undecryptable share
+    }
+}
+
+pub fn partial_decrypt<R: RngCore + CryptoRng>(
+    context: &ProofContext,
+    trustee_index: u32,
+    key_share: &Scalar,
+    ct: &Ciphertext,
+    rng: &mut R,
+) -> PartialDecryption {
+    PartialDecryption {
+        trustee_index,
+        share: key_share * ct.c1,
+        proof: elgamal::prove_dleq(PARTIAL_DOMAIN, context, key_share, &ct.c1, rng),
+    }
+}
+
+pub fn verify_partial(
+    context: &ProofContext,
+    verification_key: &RistrettoPoint,
+    ct: &Ciphertext,
+    partial: &PartialDecryption,
+) -> bool {
+    elgamal::verify_dleq(
+        PARTIAL_DOMAIN,
+        context,
+        verification_key,
+        &ct.c1,
+        &partial.share,
+        &partial.proof,
+    )
+}
+
+/// Lagrange coefficient at zero for `index` within the set `indices`
+pub fn lagrange_at_zero(indices: &[u32], index: u32) -> Scalar {
+    let xi = Scalar::from(index);
+    indices
+        .iter()
+        .filter(|&&j| j != index)
+        .fold(Scalar::ONE, |acc, &j| {
+            let xj = Scalar::from(j);
+            acc * xj * (xj - xi).invert()
+        })
+}
+
+/// Combines partial decryptions from distinct trustees into `m·G`
+pub fn combine(ct: &Ciphertext, partials: &[PartialDecryption]) -> RistrettoPoint {
+    let indices: Vec<u32> = partials.iter().map(|p| p.trustee_index).collect();
+    let shared = partials
+        .iter()
+        .map(|p| lagrange_at_zero(&indices, p.trustee_index) * p.share)
+        .fold(RistrettoPoint::identity(), |acc, s| acc + s);
+    ct.c2 - shared
+}
+
+pub mod points_hex {
+    use curve25519_dalek::ristretto::RistrettoPoint;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use crate::elgamal::{point_from_hex, point_to_hex};
+
+    pub fn serialize<S: Serializer>(points: &[RistrettoPoint], s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(points.iter().map(point_to_hex))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<RistrettoPoint>, D::Error> {
+        Vec::<String>::deserialize(d)?
+            .iter()
+            .map(|encoded| {
+                point_from_hex(encoded).ok_or_else(|| D::Error::custom("invalid Ristretto point"))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ballot;
+    use rand::rngs::OsRng;
+    use serde_json::json;
+    use uuid::Uuid;
+
+    const THRESHOLD: usize = 3;
+    const TRUSTEES: u32 = 5;
+
+    struct Ceremony {
+        context: ProofContext,
+        secrets: BTreeMap<u32, Scalar>, // Share decryption keys
+        share_keys: BTreeMap<u32, RistrettoPoint>,
+        dealings: BTreeMap<u32, Dealing>,
+    }
+
+    fn ceremony() -> Ceremony {
+        let secrets: BTreeMap<u32, Scalar> = (1..=TRUSTEES).map(|i| (i, elgamal::random_scalar(&mut OsRng))).collect();
+        let share_keys = secrets.iter().map(|(&i, secret)| (i, secret * G)).collect();
+        let context = ProofContext::new(Uuid::new_v4());
+        let dealings = (1..=TRUSTEES)
+            .map(|i| (i, deal(&context, THRESHOLD, &share_keys, &mut OsRng)))
+            .collect();
+        Ceremony {
+            context,
+            secrets,
+            share_keys,
+            dealings,
+        }
+    }
+
+    /// Trustee `index`'s key share: the sum of what every qualified dealer sent it
+    fn key_share(ceremony: &Ceremony, qualified: &BTreeMap<u32, &Dealing>, index: u32) -> Scalar {
+        qualified
+            .values()
+            .map(|dealing| {
+                let share = decrypt_share(&ceremony.secrets[&index], &dealing.encrypted_shares[&index]).unwrap();
+                assert!(verify_share(&dealing.commitments, index, &share));
+                share
+            })
+            .sum()
+    }
+
+    #[test]
+    fn threshold_partial_decryptions_recover_the_tally() {
+        let mut ceremony = ceremony();
+        let context = ceremony.context;
+        for dealing in ceremony.dealings.values() {
+            assert!(verify_dealing(&context, dealing, THRESHOLD, TRUSTEES));
+        }
+
+        // Dealer 2 sends trustee 4 a share that does not match its commitments
+        let bad_share = encrypt_share(&ceremony.share_keys[&4], &Scalar::from(7u64), &mut OsRng);
+        ceremony.dealings.get_mut(&2).unwrap().encrypted_shares.insert(4, bad_share);
+        let complaint = complain(&context, &ceremony.secrets[&4], 2, &ceremony.dealings[&2].encrypted_shares[&4], &mut OsRng);
+        assert!(complaint_is_valid(&context, &complaint, 4, &ceremony.share_keys[&4], &ceremony.dealings[&2]));
+        // A complaint against an honest dealer does not hold
+        let unfounded = complain(&context, &ceremony.secrets[&4], 3, &ceremony.dealings[&3].encrypted_shares[&4], &mut OsRng);
+        assert!(!complaint_is_valid(&context, &unfounded, 4, &ceremony.share_keys[&4], &ceremony.dealings[&3]));
+
+        let qualified: BTreeMap<u32, &Dealing> =
+            ceremony.dealings.iter().filter(|(&i, _)| i != 2).map(|(&i, d)| (i, d)).collect();
+        let joint_key = joint_public_key(qualified.values().copied());
+        let key_shares: BTreeMap<u32, Scalar> =
+            (1..=TRUSTEES).map(|i| (i, key_share(&ceremony, &qualified, i))).collect();
+        let verification_keys: BTreeMap<u32, RistrettoPoint> = (1..=TRUSTEES)
+            .map(|i| (i, verification_key(qualified.values().copied(), i)))
+            .collect();
+        for (i, share) in &key_shares {
+            assert_eq!(share * G, verification_keys[i]);
+        }
+
+        // Four ballots encrypted under the joint key: A, B, A, C
+        let layout = ballot::ballot_layout(&json!({
+            "questions": [{ "id": "q1", "type": "radio", "options": ["A", "B", "C"] }]
+        }));
+        let ballots: Vec<_> = ["A", "B", "A", "C"]
+            .iter()
+            .map(|choice| {
+                let selections = BTreeMap::from([("q1".to_string(), vec![choice.to_string()])]);
+                let (encrypted, proofs, _) = ballot::encrypt_ballot(&context, &joint_key, &layout, &selections, &mut OsRng).unwrap();
+                ballot::verify_ballot(&context, &joint_key, &encrypted, &proofs, &layout).unwrap();
+                encrypted
+            })
+            .collect();
+        let tally = ballot::aggregate(&ballots, &layout);
+
+        let expected = BTreeMap::from([(
+            "q1".to_string(),
+            BTreeMap::from([("A".to_string(), 2), ("B".to_string(), 1), ("C".to_string(), 1)]),
+        )]);
+        for quorum in [[1, 3, 5], [2, 4, 5], [3, 4, 1]] {
+            let partials: Vec<ballot::PartialTally> = quorum
+                .iter()
+                .map(|&i| {
+                    let partial = ballot::partial_decrypt_tally(&context, i, &key_shares[&i], &tally, &mut OsRng);
+                    assert!(ballot::verify_partial_tally(&context, &verification_keys[&i], i, &tally, &partial));
+                    partial
+                })
+                .collect();
+
+            let decrypted = ballot::combine_tally(&tally, &partials, ballots.len() as u64).unwrap();
+            assert_eq!(ballot::tally_counts(&decrypted), expected);
+            assert!(ballot::verify_threshold_tally(&context, &verification_keys, THRESHOLD, &decrypted));
+
+            // One trustee short of the threshold does not recover the counts
+            let short = ballot::combine_tally(&tally, &partials[..THRESHOLD - 1], ballots.len() as u64);
+            assert!(short.is_none_or(|t| ballot::tally_counts(&t) != expected));
+        }
+    }
+
+    #[test]
+    fn partial_decryptions_must_match_the_trustee_key() {
+        let ceremony = ceremony();
+        let qualified: BTreeMap<u32, &Dealing> = ceremony.dealings.iter().map(|(&i, d)| (i, d)).collect();
+        let joint_key = joint_public_key(qualified.values().copied());
+        let layout = ballot::ballot_layout(&json!({
+            "questions": [{ "id": "q1", "type": "radio", "options": ["A", "B"] }]
+        }));
+        let selections = BTreeMap::from([("q1".to_string(), vec!["A".to_string()])]);
+        let (encrypted, _, _) = ballot::encrypt_ballot(&ceremony.context, &joint_key, &layout, &selections, &mut OsRng).unwrap();
+        let tally = ballot::aggregate([&encrypted], &layout);
+
+        // Trustee 1 decrypting with trustee 2's share is caught by the proof
+        let wrong = ballot::partial_decrypt_tally(
+            &ceremony.context,
+            1,
+            &key_share(&ceremony, &qualified, 2),
+            &tally,
+            &mut OsRng,
+        );
+        let vk = verification_key(qualified.values().copied(), 1);
+        assert!(!ballot::verify_partial_tally(&ceremony.context, &vk, 1, &tally, &wrong));
+    }
+
+    #[test]
+    fn ceremony_messages_are_bound_to_their_election() {
+        let ceremony = ceremony();
+        let other = ProofContext::new(Uuid::new_v4());
+
+        // A dealing replayed into another election's ceremony does not verify
+        assert!(!verify_dealing(&other, &ceremony.dealings[&1], THRESHOLD, TRUSTEES));
+
+        // Nor does a partial decryption submitted for another election's tally
+        let qualified: BTreeMap<u32, &Dealing> = ceremony.dealings.iter().map(|(&i, d)| (i, d)).collect();
+        let share = key_share(&ceremony, &qualified, 1);
+        let ct = elgamal::encrypt(&joint_public_key(qualified.values().copied()), 1, &mut OsRng).0;
+        let partial = partial_decrypt(&ceremony.context, 1, &share, &ct, &mut OsRng);
+        let vk = verification_key(qualified.values().copied(), 1);
+        assert!(verify_partial(&ceremony.context, &vk, &ct, &partial));
+        assert!(!verify_partial(&other, &vk, &ct, &partial));
+    }
+}
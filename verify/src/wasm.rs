@@ -0,0 +1,143 @@
+//! wasm-bindgen exports for the browser. Inputs and outputs are JSON strings
+//! in the same shapes the API serves, so the page never re-implements a check.
+
+use crate::ballot::{self, BallotProofs, EncryptedBallot};
+use crate::credential::{self, BlindingState};
+use crate::elgamal::{self, ProofContext};
+use crate::hash::HashSuite;
+use crate::merkle::{self, MerkleProof};
+use crate::receipt::{self, BoardSnapshot, Note, VoteReceipt};
+use rand::rngs::OsRng;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+use wasm_bindgen::prelude::*;
+
+#[derive(Serialize)]
+struct ReceiptOutcome {
+    valid: bool,
+    error_kind: Option<&'static str>,
+    error: Option<String>,
+    notes: Vec<Note>,
+    root_hash: Option<String>,
+    root_timestamp: Option<String>,
+}
+
+fn parse<T: for<'de> serde::Deserialize<'de>>(what: &str, json: &str) -> Result<T, JsError> {
+    serde_json::from_str(json).map_err(|e| JsError::new(&format!("Invalid {}: {}", what, e)))
+}
+
+fn parse_suite(suite: &str) -> Result<HashSuite, JsError> {
+    suite.parse().map_err(|e: String| JsError::new(&e))
+}
+
+/// Verifies a receipt against an optional board snapshot (`""` for none) and
+/// returns `{valid, error_kind, error, notes, root_hash, root_timestamp}` as JSON
+#[wasm_bindgen(js_name = verifyReceipt)]
+pub fn verify_receipt(receipt_json: &str, board_json: &str) -> Result<String, JsError> {
+    let receipt: VoteReceipt = parse("receipt", receipt_json)?;
+    let board: BoardSnapshot = if board_json.trim().is_empty() {
+        BoardSnapshot::default()
+    } else {
+        parse("board snapshot", board_json)?
+    };
+
+    let mut notes = Vec::new();
+    let outcome = match receipt::verify_receipt(&receipt, &board, |note| notes.push(note)) {
+        Ok(verified) => ReceiptOutcome {
+            valid: true,
+            error_kind: None,
+            error: None,
+            notes,
+            root_hash: Some(verified.root_hash),
+            root_timestamp: verified.root_timestamp,
+        },
+        Err(e) => ReceiptOutcome {
+            valid: false,
+            error_kind: Some(e.kind()),
+            error: Some(e.to_string()),
+            notes,
+            root_hash: None,
+            root_timestamp: None,
+        },
+    };
+    serde_json::to_string(&outcome).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Ballot tracker for `choices_json` under `suite` (e.g. "SHA-256")
+#[wasm_bindgen(js_name = ballotHash)]
+pub fn ballot_hash(suite: &str, ballot_id: &str, choices_json: &str) -> Result<String, JsError> {
+    let ballot_id: Uuid = ballot_id.parse().map_err(|_| JsError::new("Invalid ballot id"))?;
+    let choices = parse("choices", choices_json)?;
+    Ok(crate::ballot_hash(parse_suite(suite)?, &ballot_id, &choices))
+}
+
+/// RFC 8785 form of `json`
+#[wasm_bindgen]
+pub fn canonicalize(json: &str) -> Result<String, JsError> {
+    Ok(crate::jcs::canonicalize(&parse("JSON", json)?))
+}
+
+/// Inclusion proof check, with the proof in the `merkle_path` shape of a receipt
+#[wasm_bindgen(js_name = verifyProof)]
+pub fn verify_proof(suite: &str, leaf: &str, proof_json: &str, root: &str) -> Result<bool, JsError> {
+    let proof: MerkleProof = parse("proof", proof_json)?;
+    Ok(merkle::verify_proof(parse_suite(suite)?, leaf, &proof, root))
+}
+
+/// Ed25519 check over a UTF-8 message, with key and signature in hex
+#[wasm_bindgen(js_name = verifySignature)]
+pub fn verify_signature(public_key: &str, message: &str, signature: &str) -> bool {
+    crate::verify_signature(public_key, message.as_bytes(), signature)
+}
+
+#[derive(Serialize)]
+struct EncryptedVote {
+    choices: EncryptedBallot,
+    proofs: BallotProofs,
+}
+
+/// Encrypts `selections_json` (question id -> chosen options) under the election's
+/// key, with the proofs `/vote/submit` checks, and returns `{choices, proofs}` as JSON.
+/// `proof_version` and the key are the ones `GET /elections/:id` serves.
+#[wasm_bindgen(js_name = encryptBallot)]
+pub fn encrypt_ballot(
+    public_key: &str,
+    election_id: &str,
+    proof_version: i16,
+    form_config_json: &str,
+    selections_json: &str,
+) -> Result<String, JsError> {
+    let pk = elgamal::point_from_hex(public_key).ok_or_else(|| JsError::new("Invalid encryption key"))?;
+    let election_id: Uuid = election_id.parse().map_err(|_| JsError::new("Invalid election id"))?;
+    let layout = ballot::ballot_layout(&parse("form config", form_config_json)?);
+    let selections: BTreeMap<String, Vec<String>> = parse("selections", selections_json)?;
+
+    let context = ProofContext::for_version(election_id, proof_version);
+    let (choices, proofs, _) =
+        ballot::encrypt_ballot(&context, &pk, &layout, &selections, &mut OsRng).map_err(|e| JsError::new(&e))?;
+    serde_json::to_string(&EncryptedVote { choices, proofs }).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Picks a random credential token and blinds it for the election's credential
+/// key. Returns the blinding state as JSON; send its `blinded_token` with
+/// `validate-identity` and keep the rest for `finalizeCredential`.
+#[wasm_bindgen(js_name = blindToken)]
+pub fn blind_token(credential_public_key: &str) -> Result<String, JsError> {
+    let state = credential::blind(credential_public_key).map_err(|e| JsError::new(&e))?;
+    serde_json::to_string(&state).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Unblinds the `blind_signature` from `validate-identity` into the credential
+/// `/vote/submit` takes, as JSON
+#[wasm_bindgen(js_name = finalizeCredential)]
+pub fn finalize_credential(
+    credential_public_key: &str,
+    state_json: &str,
+    blind_signature: &str,
+) -> Result<String, JsError> {
+    let state: BlindingState = parse("blinding state", state_json)?;
+    let credential =
+        credential::finalize(credential_public_key, &state, blind_signature).map_err(|e| JsError::new(&e))?;
+    serde_json::to_string(&credential).map_err(|e| JsError::new(&e.to_string()))
+}
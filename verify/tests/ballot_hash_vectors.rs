@@ -0,0 +1,50 @@
+//! Checks the published ballot hash vectors against this crate, so the file and
+//! the implementation cannot drift apart.
+
+use serde::Deserialize;
+use solesigner_verify::{ballot_hash, jcs, HashSuite};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+struct Vectors {
+    vectors: Vec<Vector>,
+}
+
+#[derive(Deserialize)]
+struct Vector {
+    name: String,
+    ballot_id: Uuid,
+    choices: String,
+    canonical: String,
+    ballot_hash: BTreeMap<String, String>,
+}
+
+fn vectors() -> Vec<Vector> {
+    let file: Vectors = serde_json::from_str(include_str!("../../test-vectors/ballot_hash.json"))
+        .expect("test-vectors/ballot_hash.json parses");
+    assert!(!file.vectors.is_empty());
+    file.vectors
+}
+
+#[test]
+fn canonical_forms_match() {
+    for vector in vectors() {
+        let choices: serde_json::Value = serde_json::from_str(&vector.choices).unwrap();
+        assert_eq!(jcs::canonicalize(&choices), vector.canonical, "{}", vector.name);
+    }
+}
+
+#[test]
+fn ballot_hashes_match_for_every_suite() {
+    for vector in vectors() {
+        let choices: serde_json::Value = serde_json::from_str(&vector.choices).unwrap();
+        for suite in [HashSuite::Sha256, HashSuite::Sha3_256, HashSuite::Blake3] {
+            let expected = vector
+                .ballot_hash
+                .get(suite.name())
+                .unwrap_or_else(|| panic!("{} has no {} hash", vector.name, suite));
+            assert_eq!(&ballot_hash(suite, &vector.ballot_id, &choices), expected, "{} under {}", vector.name, suite);
+        }
+    }
+}